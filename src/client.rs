@@ -2,17 +2,30 @@ use crate::{
     grpc::{
         status_client::StatusClient as GRPCStatusClient,
         systemd_client::SystemdClient as GRPCSystemdClient, zfs_client::ZfsClient as GRPCZfsClient,
-        GrpcLogDirection, GrpcLogMessage, GrpcLogParams, GrpcUnitSettings, PingResult,
-        UnitEnabledState, UnitListFilter, UnitRuntimeState, ZfsListFilter, ZfsName,
+        zpool_client::ZpoolClient as GRPCZpoolClient, GrpcLogDirection, GrpcLogMessage,
+        GrpcLogParams, GrpcUnitSettings, PingResult, UnitEnabledState, UnitListFilter,
+        UnitRuntimeState, ZfsDiffEntry, ZfsListFilter, ZfsName, ZfsReceive, ZfsSnapshotPeriod,
+        ZfsStreamChunk, ZfsTriggerSnapshotPolicy, ZpoolClear, ZpoolDevice, ZpoolSearch,
     },
     systemd::{LogDirection, Unit, UnitSettings},
 };
 // we expose these types we should serve them
 pub use crate::{
     sysinfo::Info,
-    zfs::{Dataset, ModifyDataset, ModifyVolume, Volume, ZFSStat},
+    zfs::{
+        AddVdev, AttachDevice, Bookmark, BookmarkStat, CanMount, ChangeKey, CloneSnapshot, Dataset,
+        Destroy, DestroyReport, DiffChange, DiffEntry, DiffSnapshot, ExportPool, FileType,
+        GetProperties, Hold, HoldStat, ImportPool, ImportablePool, InheritProperty, Key,
+        ListFilter, ListPage, ListSort, LoadKey, ModifyDataset, ModifyVolume, OfflineDevice,
+        OnlineDevice, PolicyRun, PoolHealth, PoolStatus, Property, PropertySource, Quota,
+        QuotaFilter, QuotaKind, ReceiveSnapshot, ReplaceDevice, Rollback, ScanStatus, SendSnapshot,
+        SetProperties, Share, ShareProtocol, ShareStat, Snapshot, SnapshotPeriod, SnapshotPolicy,
+        SnapshotStat, SpaceStat, SpaceUsage, Unshare, UserSpaceStat, Vdev, VdevClass, VdevLayout,
+        Volume, ZFSNode, ZFSStat,
+    },
 };
 use std::path::PathBuf;
+use tokio_stream::{Stream, StreamExt};
 use tonic::{transport::Channel, Request, Streaming};
 
 type Result<T> = std::result::Result<T, tonic::Status>;
@@ -30,6 +43,10 @@ pub struct ZFSClient {
     client: GRPCZfsClient<Channel>,
 }
 
+pub struct ZpoolClient {
+    client: GRPCZpoolClient<Channel>,
+}
+
 pub struct SystemdClient {
     client: GRPCSystemdClient<Channel>,
 }
@@ -51,6 +68,12 @@ impl Client {
         Ok(ZFSClient { client })
     }
 
+    pub async fn zpool(&self) -> anyhow::Result<ZpoolClient> {
+        let client =
+            GRPCZpoolClient::connect(format!("unix://{}", self.socket.to_str().unwrap())).await?;
+        Ok(ZpoolClient { client })
+    }
+
     pub async fn systemd(&self) -> anyhow::Result<SystemdClient> {
         let client =
             GRPCSystemdClient::connect(format!("unix://{}", self.socket.to_str().unwrap())).await?;
@@ -145,16 +168,391 @@ impl ZFSClient {
     }
 
     pub async fn list(&mut self, filter: Option<String>) -> Result<Vec<ZFSStat>> {
+        Ok(self
+            .list_filtered(ListFilter {
+                prefix: filter,
+                ..Default::default()
+            })
+            .await?
+            .entries)
+    }
+
+    pub async fn list_filtered(&mut self, filter: ListFilter) -> Result<ListPage> {
+        Ok(self
+            .client
+            .list(Request::new(filter.into()))
+            .await?
+            .into_inner()
+            .into())
+    }
+
+    // the datasets as a tree rather than a flat list; paging in the filter is ignored
+    pub async fn tree(&mut self, filter: ListFilter) -> Result<Vec<ZFSNode>> {
         Ok(self
             .client
-            .list(Request::new(ZfsListFilter { filter }))
+            .tree(Request::new(filter.into()))
             .await?
             .into_inner()
             .into())
     }
 
     pub async fn destroy(&mut self, name: String) -> Result<()> {
-        self.client.destroy(Request::new(ZfsName { name })).await?;
+        self.destroy_with(Destroy {
+            name,
+            ..Default::default()
+        })
+        .await?;
+        Ok(())
+    }
+
+    pub async fn destroy_with(&mut self, destroy: Destroy) -> Result<DestroyReport> {
+        Ok(self
+            .client
+            .destroy(Request::new(destroy.into()))
+            .await?
+            .into_inner()
+            .into())
+    }
+
+    pub async fn create_snapshot(&mut self, snapshot: Snapshot) -> Result<()> {
+        self.client
+            .create_snapshot(Request::new(snapshot.into()))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn list_snapshots(&mut self, filter: Option<String>) -> Result<Vec<SnapshotStat>> {
+        Ok(self
+            .client
+            .list_snapshots(Request::new(ZfsListFilter {
+                filter,
+                ..Default::default()
+            }))
+            .await?
+            .into_inner()
+            .into())
+    }
+
+    pub async fn destroy_snapshot(&mut self, snapshot: Snapshot) -> Result<()> {
+        self.client
+            .destroy_snapshot(Request::new(snapshot.into()))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn hold(&mut self, hold: Hold) -> Result<()> {
+        self.client.hold(Request::new(hold.into())).await?;
+        Ok(())
+    }
+
+    pub async fn release(&mut self, hold: Hold) -> Result<()> {
+        self.client.release(Request::new(hold.into())).await?;
+        Ok(())
+    }
+
+    pub async fn list_holds(&mut self, snapshot: Snapshot) -> Result<Vec<HoldStat>> {
+        Ok(self
+            .client
+            .list_holds(Request::new(snapshot.into()))
+            .await?
+            .into_inner()
+            .into())
+    }
+
+    pub async fn create_bookmark(&mut self, bookmark: Bookmark) -> Result<()> {
+        self.client
+            .create_bookmark(Request::new(bookmark.into()))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn list_bookmarks(&mut self, filter: Option<String>) -> Result<Vec<BookmarkStat>> {
+        Ok(self
+            .client
+            .list_bookmarks(Request::new(ZfsListFilter {
+                filter,
+                ..Default::default()
+            }))
+            .await?
+            .into_inner()
+            .into())
+    }
+
+    pub async fn destroy_bookmark(&mut self, bookmark: Bookmark) -> Result<()> {
+        self.client
+            .destroy_bookmark(Request::new(bookmark.into()))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn list_snapshot_policies(&mut self) -> Result<Vec<SnapshotPolicy>> {
+        Ok(self
+            .client
+            .list_snapshot_policies(Request::new(()))
+            .await?
+            .into_inner()
+            .policies
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    // runs the dataset's policy now; with a period, takes a snapshot of it even if none is due
+    pub async fn trigger_snapshot_policy(
+        &mut self,
+        dataset: String,
+        period: Option<SnapshotPeriod>,
+    ) -> Result<PolicyRun> {
+        Ok(self
+            .client
+            .trigger_snapshot_policy(Request::new(ZfsTriggerSnapshotPolicy {
+                dataset,
+                period: period.map(|x| Into::<ZfsSnapshotPeriod>::into(x).into()),
+            }))
+            .await?
+            .into_inner()
+            .into())
+    }
+
+    pub async fn clone_snapshot(&mut self, clone: CloneSnapshot) -> Result<()> {
+        self.client
+            .clone_snapshot(Request::new(clone.into()))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn promote(&mut self, name: String) -> Result<()> {
+        self.client.promote(Request::new(ZfsName { name })).await?;
+        Ok(())
+    }
+
+    pub async fn send(&mut self, info: SendSnapshot) -> Result<Streaming<ZfsStreamChunk>> {
+        Ok(self
+            .client
+            .send(Request::new(info.into()))
+            .await?
+            .into_inner())
+    }
+
+    pub async fn receive<S>(&mut self, info: ReceiveSnapshot, data: S) -> Result<()>
+    where
+        S: Stream<Item = Vec<u8>> + Send + 'static,
+    {
+        let stream = data.map(move |data| ZfsReceive {
+            dataset: info.dataset.clone(),
+            force: info.force,
+            data,
+        });
+        self.client.receive(Request::new(stream)).await?;
+        Ok(())
+    }
+
+    pub async fn diff(&mut self, info: DiffSnapshot) -> Result<Streaming<ZfsDiffEntry>> {
+        Ok(self
+            .client
+            .diff(Request::new(info.into()))
+            .await?
+            .into_inner())
+    }
+
+    pub async fn get_properties(&mut self, info: GetProperties) -> Result<Vec<Property>> {
+        Ok(self
+            .client
+            .get_properties(Request::new(info.into()))
+            .await?
+            .into_inner()
+            .into())
+    }
+
+    pub async fn set_properties(&mut self, info: SetProperties) -> Result<()> {
+        self.client
+            .set_properties(Request::new(info.into()))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn inherit_property(&mut self, info: InheritProperty) -> Result<()> {
+        self.client
+            .inherit_property(Request::new(info.into()))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn space_usage(&mut self, filter: Option<String>) -> Result<SpaceUsage> {
+        Ok(self
+            .client
+            .space_usage(Request::new(ZfsListFilter {
+                filter,
+                ..Default::default()
+            }))
+            .await?
+            .into_inner()
+            .into())
+    }
+
+    pub async fn share(&mut self, share: Share) -> Result<()> {
+        self.client.share(Request::new(share.into())).await?;
+        Ok(())
+    }
+
+    pub async fn unshare(&mut self, unshare: Unshare) -> Result<()> {
+        self.client.unshare(Request::new(unshare.into())).await?;
+        Ok(())
+    }
+
+    pub async fn list_shares(&mut self, filter: Option<String>) -> Result<Vec<ShareStat>> {
+        Ok(self
+            .client
+            .list_shares(Request::new(ZfsListFilter {
+                filter,
+                ..Default::default()
+            }))
+            .await?
+            .into_inner()
+            .into())
+    }
+
+    pub async fn set_quota(&mut self, quota: Quota) -> Result<()> {
+        self.client.set_quota(Request::new(quota.into())).await?;
+        Ok(())
+    }
+
+    pub async fn list_quotas(&mut self, filter: QuotaFilter) -> Result<Vec<Quota>> {
+        Ok(self
+            .client
+            .list_quotas(Request::new(filter.into()))
+            .await?
+            .into_inner()
+            .into())
+    }
+
+    pub async fn user_space(&mut self, filter: QuotaFilter) -> Result<Vec<UserSpaceStat>> {
+        Ok(self
+            .client
+            .user_space(Request::new(filter.into()))
+            .await?
+            .into_inner()
+            .into())
+    }
+
+    pub async fn load_key(&mut self, info: LoadKey) -> Result<()> {
+        self.client.load_key(Request::new(info.into())).await?;
+        Ok(())
+    }
+
+    pub async fn unload_key(&mut self, name: String) -> Result<()> {
+        self.client
+            .unload_key(Request::new(ZfsName { name }))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn change_key(&mut self, info: ChangeKey) -> Result<()> {
+        self.client.change_key(Request::new(info.into())).await?;
+        Ok(())
+    }
+
+    pub async fn pool_status(&mut self) -> Result<PoolStatus> {
+        Ok(self
+            .client
+            .pool_status(Request::new(()))
+            .await?
+            .into_inner()
+            .into())
+    }
+
+    pub async fn start_scrub(&mut self) -> Result<()> {
+        self.client.start_scrub(Request::new(())).await?;
+        Ok(())
+    }
+
+    pub async fn pause_scrub(&mut self) -> Result<()> {
+        self.client.pause_scrub(Request::new(())).await?;
+        Ok(())
+    }
+
+    pub async fn cancel_scrub(&mut self) -> Result<()> {
+        self.client.cancel_scrub(Request::new(())).await?;
+        Ok(())
+    }
+
+    pub async fn start_trim(&mut self) -> Result<()> {
+        self.client.start_trim(Request::new(())).await?;
+        Ok(())
+    }
+
+    pub async fn pause_trim(&mut self) -> Result<()> {
+        self.client.pause_trim(Request::new(())).await?;
+        Ok(())
+    }
+
+    pub async fn cancel_trim(&mut self) -> Result<()> {
+        self.client.cancel_trim(Request::new(())).await?;
+        Ok(())
+    }
+
+    pub async fn rollback(&mut self, rollback: Rollback) -> Result<()> {
+        self.client.rollback(Request::new(rollback.into())).await?;
+        Ok(())
+    }
+}
+
+impl ZpoolClient {
+    pub async fn list_importable(&mut self, dirs: Vec<String>) -> Result<Vec<ImportablePool>> {
+        Ok(self
+            .client
+            .list_importable(Request::new(ZpoolSearch { dirs }))
+            .await?
+            .into_inner()
+            .into())
+    }
+
+    pub async fn import(&mut self, info: ImportPool) -> Result<()> {
+        self.client.import(Request::new(info.into())).await?;
+        Ok(())
+    }
+
+    pub async fn export(&mut self, info: ExportPool) -> Result<()> {
+        self.client.export(Request::new(info.into())).await?;
+        Ok(())
+    }
+
+    pub async fn add_vdev(&mut self, info: AddVdev) -> Result<()> {
+        self.client.add_vdev(Request::new(info.into())).await?;
+        Ok(())
+    }
+
+    pub async fn attach(&mut self, info: AttachDevice) -> Result<()> {
+        self.client.attach(Request::new(info.into())).await?;
+        Ok(())
+    }
+
+    pub async fn detach(&mut self, device: String) -> Result<()> {
+        self.client
+            .detach(Request::new(ZpoolDevice { device }))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn replace(&mut self, info: ReplaceDevice) -> Result<()> {
+        self.client.replace(Request::new(info.into())).await?;
+        Ok(())
+    }
+
+    pub async fn online(&mut self, info: OnlineDevice) -> Result<()> {
+        self.client.online(Request::new(info.into())).await?;
+        Ok(())
+    }
+
+    pub async fn offline(&mut self, info: OfflineDevice) -> Result<()> {
+        self.client.offline(Request::new(info.into())).await?;
+        Ok(())
+    }
+
+    pub async fn clear(&mut self, device: Option<String>) -> Result<()> {
+        self.client
+            .clear(Request::new(ZpoolClear { device }))
+            .await?;
         Ok(())
     }
 }
@@ -1,5 +1,6 @@
-use crate::zfs::Pool;
+use crate::zfs::{Pool, SnapshotPolicy};
 use anyhow::Result;
+use fancy_duration::FancyDuration;
 use serde::Deserialize;
 use tracing::info;
 use tracing_subscriber::FmtSubscriber;
@@ -11,6 +12,63 @@ fn default_zpool() -> String {
     DEFAULT_ZPOOL.to_string()
 }
 
+fn default_max_commands() -> usize {
+    4
+}
+
+fn deserialize_max_commands<'de, D>(deserializer: D) -> Result<usize, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match usize::deserialize(deserializer)? {
+        0 => Err(serde::de::Error::custom(
+            "max_commands must be greater than zero",
+        )),
+        x => Ok(x),
+    }
+}
+
+// a dataset may only have one policy, since it is what triggering one goes by
+fn deserialize_snapshot_policies<'de, D>(deserializer: D) -> Result<Vec<SnapshotPolicy>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let policies = Vec::<SnapshotPolicy>::deserialize(deserializer)?;
+
+    for (i, policy) in policies.iter().enumerate() {
+        if policies[..i].iter().any(|x| x.dataset == policy.dataset) {
+            return Err(serde::de::Error::custom(format!(
+                "more than one snapshot policy for '{}'",
+                policy.dataset
+            )));
+        }
+    }
+
+    Ok(policies)
+}
+
+// accepts the same notation we print durations with, e.g. "1w" or "30d 12h"
+fn deserialize_interval<'de, D>(deserializer: D) -> Result<Option<std::time::Duration>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let Some(s) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+
+    let interval = FancyDuration::<std::time::Duration>::parse(&s)
+        .map_err(serde::de::Error::custom)?
+        .duration();
+
+    if interval.is_zero() {
+        return Err(serde::de::Error::custom(
+            "interval must be greater than zero",
+        ));
+    }
+
+    Ok(Some(interval))
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub enum LogLevel {
     #[serde(rename = "warn")]
@@ -60,6 +118,21 @@ pub struct Config {
 pub struct ZFSConfig {
     #[serde(default = "default_zpool")]
     pub pool: String,
+    // how often buckled scrubs the pool itself; unset leaves scrubbing to the administrator
+    #[serde(default, deserialize_with = "deserialize_interval")]
+    pub scrub_interval: Option<std::time::Duration>,
+    // how many zfs and zpool commands may run at once; further requests wait their turn
+    #[serde(
+        default = "default_max_commands",
+        deserialize_with = "deserialize_max_commands"
+    )]
+    pub max_commands: usize,
+    // commands still running after this long are killed; unset lets them run as long as they take
+    #[serde(default, deserialize_with = "deserialize_interval")]
+    pub command_timeout: Option<std::time::Duration>,
+    // snapshots buckled takes and prunes on its own schedule
+    #[serde(default, deserialize_with = "deserialize_snapshot_policies")]
+    pub snapshot_policies: Vec<SnapshotPolicy>,
 }
 
 impl ZFSConfig {
@@ -3,31 +3,112 @@ use crate::{
         status_server::{Status, StatusServer},
         systemd_server::{Systemd, SystemdServer},
         zfs_server::{Zfs, ZfsServer},
+        zpool_server::{Zpool, ZpoolServer},
         GrpcLogMessage, GrpcLogParams, GrpcUnitList, GrpcUnitSettings, PingResult, UnitListFilter,
-        ZfsDataset, ZfsList, ZfsListFilter, ZfsModifyDataset, ZfsModifyVolume, ZfsName, ZfsVolume,
+        ZfsBookmark, ZfsBookmarkList, ZfsChangeKey, ZfsClone, ZfsDataset, ZfsDestroy, ZfsDestroyed,
+        ZfsDiff, ZfsDiffEntry, ZfsGetProperties, ZfsHold, ZfsHoldList, ZfsInheritProperty, ZfsList,
+        ZfsListFilter, ZfsLoadKey, ZfsModifyDataset, ZfsModifyVolume, ZfsName, ZfsPoolStatus,
+        ZfsProperties, ZfsQuota, ZfsQuotaFilter, ZfsQuotaList, ZfsReceive, ZfsRollback, ZfsSend,
+        ZfsSetProperties, ZfsShare, ZfsShareList, ZfsSnapshot, ZfsSnapshotList,
+        ZfsSnapshotPolicyList, ZfsSnapshotPolicyRun, ZfsSpaceUsage, ZfsStreamChunk, ZfsTree,
+        ZfsTriggerSnapshotPolicy, ZfsUnshare, ZfsUserSpace, ZfsVolume, ZpoolAddVdev, ZpoolAttach,
+        ZpoolClear, ZpoolDevice, ZpoolExport, ZpoolImport, ZpoolImportableList, ZpoolOffline,
+        ZpoolOnline, ZpoolReplace, ZpoolSearch,
     },
     sysinfo::Info,
+    zfs::{
+        DefaultBackend, InvalidName, ListFilter, PolicyRun, Pool, SnapshotPeriod, SnapshotPolicy,
+        ZfsBackend, STREAM_CHUNK_SIZE,
+    },
+};
+use fancy_duration::AsFancyDuration;
+use std::{
+    collections::HashMap,
+    fs::Permissions,
+    io::{Read, Write},
+    os::unix::fs::PermissionsExt,
+    pin::Pin,
+    sync::{Arc, Mutex, PoisonError},
+    time::SystemTime,
 };
-use std::{fs::Permissions, os::unix::fs::PermissionsExt, pin::Pin};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use tokio_stream::{wrappers::ReceiverStream, Stream};
-use tonic::{transport::Server as TransportServer, Request, Response, Result};
+use tonic::{transport::Server as TransportServer, Request, Response, Result, Streaming};
 use tonic_middleware::MiddlewareLayer;
-use tracing::info;
+use tracing::{error, info};
 
 // FIXME needs a way to shut down
-#[derive(Debug, Default, Clone)]
-pub struct Server {
+#[derive(Debug, Clone)]
+pub struct Server<B: ZfsBackend = DefaultBackend> {
     config: crate::config::Config,
+    backend: B,
+    // bounds how many ZFS operations run at once
+    limit: Arc<Semaphore>,
+    // one per snapshot policy dataset, so the scheduler and TriggerSnapshotPolicy never run the
+    // same policy at once
+    policy_locks: Arc<PolicyLocks>,
 }
 
+type PolicyLocks = HashMap<String, Mutex<()>>;
+
 impl Server {
     pub fn new_with_config(config: Option<crate::config::Config>) -> Self {
-        match config {
-            Some(config) => Self { config },
-            None => Self::default(),
+        let config = config.unwrap_or_default();
+        let backend = DefaultBackend::with_timeout(config.zfs.command_timeout);
+        Self::new_with_backend(config, backend)
+    }
+}
+
+impl<B: ZfsBackend> Server<B> {
+    pub fn new_with_backend(config: crate::config::Config, backend: B) -> Self {
+        Self {
+            limit: Arc::new(Semaphore::new(config.zfs.max_commands)),
+            policy_locks: Arc::new(
+                config
+                    .zfs
+                    .snapshot_policies
+                    .iter()
+                    .map(|x| (x.dataset.clone(), Mutex::default()))
+                    .collect(),
+            ),
+            config,
+            backend,
         }
     }
 
+    fn pool(&self) -> Pool<B> {
+        Pool::with_backend(&self.config.zfs.pool, self.backend.clone())
+    }
+
+    // a slot among the ZFS operations allowed to run at once, held until dropped
+    async fn permit(&self) -> Result<OwnedSemaphorePermit> {
+        self.limit
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|e| tonic::Status::new(tonic::Code::Internal, e.to_string()))
+    }
+
+    // Pool blocks on the commands it runs, so its work goes to the blocking thread pool instead
+    // of holding up an async worker, and with it every other request.
+    async fn run_zfs<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(Pool<B>) -> anyhow::Result<T> + Send + 'static,
+    {
+        let permit = self.permit().await?;
+        let pool = self.pool();
+
+        // the permit goes with the work; a cancelled request drops this future, not the task
+        tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            f(pool)
+        })
+        .await
+        .map_err(|e| tonic::Status::new(tonic::Code::Internal, e.to_string()))?
+        .map_err(zfs_status)
+    }
+
     pub fn start(
         &self,
     ) -> anyhow::Result<impl std::future::Future<Output = Result<(), tonic::transport::Error>>>
@@ -47,17 +128,177 @@ impl Server {
 
         std::fs::set_permissions(&self.config.socket, Permissions::from_mode(0o600))?;
 
+        if let Some(interval) = self.config.zfs.scrub_interval {
+            tokio::spawn(scrub_scheduler(self.pool(), self.limit.clone(), interval));
+        }
+
+        if !self.config.zfs.snapshot_policies.is_empty() {
+            tokio::spawn(snapshot_scheduler(
+                self.pool(),
+                self.limit.clone(),
+                self.policy_locks.clone(),
+                self.config.zfs.snapshot_policies.clone(),
+            ));
+        }
+
         Ok(TransportServer::builder()
             .layer(MiddlewareLayer::new(crate::middleware::LogMiddleware))
             .add_service(StatusServer::new(self.clone()))
             .add_service(ZfsServer::new(self.clone()))
+            .add_service(ZpoolServer::new(self.clone()))
             .add_service(SystemdServer::new(self.clone()))
             .serve_with_incoming(uds_stream))
     }
 }
 
+// how often the scrub scheduler looks at the pool. Scrubs are started late by at most this much,
+// and a scrub started or cancelled by hand is noticed within it.
+const SCRUB_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+// scheduled work counts against the same limit as requests
+async fn scrub_scheduler<B: ZfsBackend>(
+    pool: Pool<B>,
+    limit: Arc<Semaphore>,
+    interval: std::time::Duration,
+) {
+    info!(
+        "Scrubbing pool every {}.",
+        interval.fancy_duration().to_string()
+    );
+
+    loop {
+        // only fails once the server is going away
+        let Ok(permit) = limit.clone().acquire_owned().await else {
+            return;
+        };
+
+        let p = pool.clone();
+        let wait = tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            scrub_if_due(&p, interval)
+        })
+        .await
+        .unwrap_or(SCRUB_CHECK_INTERVAL);
+
+        tokio::time::sleep(wait).await;
+    }
+}
+
+// starts a scrub when one is due, returning how long to wait before checking again
+fn scrub_if_due<B: ZfsBackend>(
+    pool: &Pool<B>,
+    interval: std::time::Duration,
+) -> std::time::Duration {
+    match pool.next_scrub(interval) {
+        Ok(Some(wait)) if wait.is_zero() => {
+            info!("Starting scheduled scrub.");
+            if let Err(e) = pool.start_scrub() {
+                error!("Starting scheduled scrub: {}", e.to_string());
+            }
+            SCRUB_CHECK_INTERVAL
+        }
+        Ok(Some(wait)) => wait.min(SCRUB_CHECK_INTERVAL),
+        Ok(None) => SCRUB_CHECK_INTERVAL,
+        Err(e) => {
+            error!("Checking scrub schedule: {}", e.to_string());
+            SCRUB_CHECK_INTERVAL
+        }
+    }
+}
+
+// the longest the snapshot scheduler sleeps, so a policy whose dataset was missing or whose
+// snapshots were removed by hand is picked up again within it
+const SNAPSHOT_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(15 * 60);
+
+async fn snapshot_scheduler<B: ZfsBackend>(
+    pool: Pool<B>,
+    limit: Arc<Semaphore>,
+    locks: Arc<PolicyLocks>,
+    policies: Vec<SnapshotPolicy>,
+) {
+    info!("Running {} snapshot policies.", policies.len());
+
+    let policies = Arc::new(policies);
+    loop {
+        let Ok(permit) = limit.clone().acquire_owned().await else {
+            return;
+        };
+
+        let p = pool.clone();
+        let locks = locks.clone();
+        let policies = policies.clone();
+        let wait = tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            run_snapshot_policies(&p, &locks, &policies)
+        })
+        .await
+        .unwrap_or(SNAPSHOT_CHECK_INTERVAL);
+
+        tokio::time::sleep(wait).await;
+    }
+}
+
+// runs the policy once nothing else is, so two runs can't both take or prune the same snapshots
+fn run_snapshot_policy<B: ZfsBackend>(
+    pool: &Pool<B>,
+    locks: &PolicyLocks,
+    policy: &SnapshotPolicy,
+    period: Option<SnapshotPeriod>,
+) -> anyhow::Result<PolicyRun> {
+    let _guard = locks
+        .get(&policy.dataset)
+        .map(|x| x.lock().unwrap_or_else(PoisonError::into_inner));
+
+    // the time is taken with the lock held, so it moves forward from one run to the next
+    pool.run_snapshot_policy(policy, period, SystemTime::now())
+}
+
+// runs every policy that has something due, returning how long to wait before the next one does
+fn run_snapshot_policies<B: ZfsBackend>(
+    pool: &Pool<B>,
+    locks: &PolicyLocks,
+    policies: &[SnapshotPolicy],
+) -> std::time::Duration {
+    let mut wait = SNAPSHOT_CHECK_INTERVAL;
+
+    for policy in policies {
+        match run_snapshot_policy(pool, locks, policy, None) {
+            Ok(run) => {
+                for name in run.created {
+                    info!("Took scheduled snapshot {}.", name);
+                }
+                for name in run.destroyed {
+                    info!("Pruned scheduled snapshot {}.", name);
+                }
+            }
+            Err(e) => {
+                // don't go by this policy's schedule until the next check, or it would be
+                // retried straight away
+                error!(
+                    "Running snapshot policy for '{}': {}",
+                    policy.dataset,
+                    e.to_string()
+                );
+                continue;
+            }
+        }
+
+        match pool.next_snapshot_policy_run(policy, SystemTime::now()) {
+            Ok(Some(next)) => wait = wait.min(next),
+            Ok(None) => {}
+            Err(e) => error!(
+                "Checking snapshot policy for '{}': {}",
+                policy.dataset,
+                e.to_string()
+            ),
+        }
+    }
+
+    wait
+}
+
 #[tonic::async_trait]
-impl Systemd for Server {
+impl<B: ZfsBackend> Systemd for Server<B> {
     async fn reload(&self, _: tonic::Request<()>) -> Result<Response<()>> {
         Ok(Response::new(
             crate::systemd::Systemd::new_system()
@@ -89,186 +330,2278 @@ impl Systemd for Server {
             v.push(item.into());
         }
 
-        Ok(Response::new(GrpcUnitList { items: v }))
-    }
+        Ok(Response::new(GrpcUnitList { items: v }))
+    }
+
+    type UnitLogStream = Pin<Box<dyn Stream<Item = Result<GrpcLogMessage>> + Send>>;
+
+    async fn set_unit(&self, _filter: Request<GrpcUnitSettings>) -> Result<Response<()>> {
+        Ok(Response::new(()))
+    }
+
+    // FIXME: this really is only a streaming method because of memory usage concnerns. Maybe
+    // another way would be better
+    async fn unit_log(
+        &self,
+        params: Request<GrpcLogParams>,
+    ) -> Result<Response<Self::UnitLogStream>> {
+        let params = params.into_inner();
+        let (tx, rx) = tokio::sync::mpsc::channel(params.count as usize);
+        let output_stream = ReceiverStream::new(rx);
+        let systemd = crate::systemd::Systemd::new_system()
+            .await
+            .map_err(|e| tonic::Status::new(tonic::Code::Internal, e.to_string()))?;
+
+        let p2 = params.clone();
+        tokio::spawn(async move {
+            let params = p2;
+            let mut rcv = systemd
+                .log(&params.name, params.count as usize, None, None)
+                .await
+                .unwrap();
+            while let Some(items) = rcv.recv().await {
+                let mut time: Option<std::time::SystemTime> = None;
+                let mut msg: Option<String> = None;
+                let mut pid: Option<u64> = None;
+                let mut cursor: Option<String> = None;
+
+                for (key, value) in items {
+                    match key.as_str() {
+                        "_SOURCE_REALTIME_TIMESTAMP" => {
+                            time = Some(
+                                std::time::SystemTime::UNIX_EPOCH
+                                    + std::time::Duration::from_secs(value.parse::<u64>().unwrap()),
+                            )
+                        }
+                        "MESSAGE" => msg = Some(value),
+                        "_PID" => pid = Some(value.parse().unwrap()),
+                        "CURSOR" => cursor = Some(value),
+                        _ => {}
+                    }
+
+                    if time.is_some() && msg.is_some() && pid.is_some() {
+                        tx.send(Ok(GrpcLogMessage {
+                            service_name: params.name.clone(),
+                            msg: msg.clone().unwrap(),
+                            pid: pid.unwrap(),
+                            time: time.map(Into::into),
+                            cursor: cursor.unwrap(),
+                        }))
+                        .await
+                        .unwrap();
+                        time = None;
+                        msg = None;
+                        pid = None;
+                        cursor = None;
+                    }
+                }
+            }
+        });
+
+        Ok(Response::new(Box::pin(output_stream) as Self::UnitLogStream))
+    }
+}
+
+#[tonic::async_trait]
+impl<B: ZfsBackend> Status for Server<B> {
+    async fn ping(&self, _: Request<()>) -> Result<Response<PingResult>> {
+        Ok(Response::new(PingResult {
+            info: Some(Info::default().into()),
+        }))
+    }
+}
+
+// names Pool refused to pass along are the caller's mistake; anything else went wrong in zfs
+fn zfs_status(e: anyhow::Error) -> tonic::Status {
+    let code = if e.is::<InvalidName>() {
+        tonic::Code::InvalidArgument
+    } else {
+        tonic::Code::Internal
+    };
+
+    tonic::Status::new(code, e.to_string())
+}
+
+#[tonic::async_trait]
+impl<B: ZfsBackend> Zfs for Server<B> {
+    async fn modify_dataset(&self, info: Request<ZfsModifyDataset>) -> Result<Response<()>> {
+        let info = info.into_inner();
+        self.run_zfs(move |pool| pool.modify_dataset(info.into()))
+            .await?;
+        Ok(Response::new(()))
+    }
+
+    async fn modify_volume(&self, info: Request<ZfsModifyVolume>) -> Result<Response<()>> {
+        let info = info.into_inner();
+        self.run_zfs(move |pool| pool.modify_volume(info.into()))
+            .await?;
+        Ok(Response::new(()))
+    }
+
+    async fn list(&self, filter: Request<ZfsListFilter>) -> Result<Response<ZfsList>> {
+        let filter: ListFilter = filter.into_inner().into();
+        let list = self
+            .run_zfs(move |pool| pool.list_filtered(&filter))
+            .await?;
+        return Ok(Response::new(list.into()));
+    }
+
+    async fn tree(&self, filter: Request<ZfsListFilter>) -> Result<Response<ZfsTree>> {
+        let filter: ListFilter = filter.into_inner().into();
+        let tree = self.run_zfs(move |pool| pool.tree(&filter)).await?;
+        Ok(Response::new(tree.into()))
+    }
+
+    async fn create_dataset(&self, dataset: Request<ZfsDataset>) -> Result<Response<()>> {
+        let dataset = dataset.into_inner();
+        self.run_zfs(move |pool| pool.create_dataset(&dataset.into()))
+            .await?;
+
+        return Ok(Response::new(()));
+    }
+
+    async fn create_volume(&self, volume: Request<ZfsVolume>) -> Result<Response<()>> {
+        let volume = volume.into_inner();
+        self.run_zfs(move |pool| pool.create_volume(&volume.into()))
+            .await?;
+        return Ok(Response::new(()));
+    }
+
+    async fn destroy(&self, info: Request<ZfsDestroy>) -> Result<Response<ZfsDestroyed>> {
+        let info = info.into_inner();
+        let report = self
+            .run_zfs(move |pool| pool.destroy_with(&info.into()))
+            .await?;
+        return Ok(Response::new(report.into()));
+    }
+
+    async fn create_snapshot(&self, snapshot: Request<ZfsSnapshot>) -> Result<Response<()>> {
+        let snapshot = snapshot.into_inner();
+        self.run_zfs(move |pool| pool.create_snapshot(&snapshot.into()))
+            .await?;
+        Ok(Response::new(()))
+    }
+
+    async fn list_snapshots(
+        &self,
+        filter: Request<ZfsListFilter>,
+    ) -> Result<Response<ZfsSnapshotList>> {
+        let filter = filter.into_inner().filter;
+        let list = self
+            .run_zfs(move |pool| pool.list_snapshots(filter))
+            .await?;
+        Ok(Response::new(list.into()))
+    }
+
+    async fn destroy_snapshot(&self, snapshot: Request<ZfsSnapshot>) -> Result<Response<()>> {
+        let snapshot = snapshot.into_inner();
+        self.run_zfs(move |pool| pool.destroy_snapshot(&snapshot.into()))
+            .await?;
+        Ok(Response::new(()))
+    }
+
+    async fn hold(&self, hold: Request<ZfsHold>) -> Result<Response<()>> {
+        let hold = hold.into_inner();
+        self.run_zfs(move |pool| pool.hold(&hold.into())).await?;
+        Ok(Response::new(()))
+    }
+
+    async fn release(&self, hold: Request<ZfsHold>) -> Result<Response<()>> {
+        let hold = hold.into_inner();
+        self.run_zfs(move |pool| pool.release(&hold.into())).await?;
+        Ok(Response::new(()))
+    }
+
+    async fn list_holds(&self, snapshot: Request<ZfsSnapshot>) -> Result<Response<ZfsHoldList>> {
+        let snapshot = snapshot.into_inner();
+        let list = self
+            .run_zfs(move |pool| pool.list_holds(&snapshot.into()))
+            .await?;
+        Ok(Response::new(list.into()))
+    }
+
+    async fn create_bookmark(&self, bookmark: Request<ZfsBookmark>) -> Result<Response<()>> {
+        let bookmark = bookmark.into_inner();
+        self.run_zfs(move |pool| pool.create_bookmark(&bookmark.into()))
+            .await?;
+        Ok(Response::new(()))
+    }
+
+    async fn list_bookmarks(
+        &self,
+        filter: Request<ZfsListFilter>,
+    ) -> Result<Response<ZfsBookmarkList>> {
+        let filter = filter.into_inner().filter;
+        let list = self
+            .run_zfs(move |pool| pool.list_bookmarks(filter))
+            .await?;
+        Ok(Response::new(list.into()))
+    }
+
+    async fn destroy_bookmark(&self, bookmark: Request<ZfsBookmark>) -> Result<Response<()>> {
+        let bookmark = bookmark.into_inner();
+        self.run_zfs(move |pool| pool.destroy_bookmark(&bookmark.into()))
+            .await?;
+        Ok(Response::new(()))
+    }
+
+    async fn clone_snapshot(&self, clone: Request<ZfsClone>) -> Result<Response<()>> {
+        let clone = clone.into_inner();
+        self.run_zfs(move |pool| pool.clone_snapshot(&clone.into()))
+            .await?;
+        Ok(Response::new(()))
+    }
+
+    async fn promote(&self, name: Request<ZfsName>) -> Result<Response<()>> {
+        let name = name.into_inner().name;
+        self.run_zfs(move |pool| pool.promote(name)).await?;
+        Ok(Response::new(()))
+    }
+
+    type SendStream = Pin<Box<dyn Stream<Item = Result<ZfsStreamChunk>> + Send>>;
+
+    async fn send(&self, info: Request<ZfsSend>) -> Result<Response<Self::SendStream>> {
+        // held for as long as zfs send runs, not just while it starts
+        let permit = self.permit().await?;
+        let mut pipe = self
+            .pool()
+            .send(&info.into_inner().into())
+            .map_err(zfs_status)?;
+
+        let (tx, rx) = tokio::sync::mpsc::channel(16);
+        let output_stream = ReceiverStream::new(rx);
+
+        // zfs send is a blocking pipe read, keep it off the async workers
+        tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            let mut buf = vec![0u8; STREAM_CHUNK_SIZE];
+            loop {
+                match pipe.read(&mut buf) {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        // the client hung up; dropping the pipe kills zfs send
+                        if tx
+                            .blocking_send(Ok(ZfsStreamChunk {
+                                data: buf[..n].to_vec(),
+                            }))
+                            .is_err()
+                        {
+                            return;
+                        }
+                    }
+                    Err(e) => {
+                        let _ = tx.blocking_send(Err(tonic::Status::new(
+                            tonic::Code::Internal,
+                            e.to_string(),
+                        )));
+                        return;
+                    }
+                }
+            }
+
+            if let Err(e) = pipe.finish() {
+                let _ = tx.blocking_send(Err(tonic::Status::new(
+                    tonic::Code::Internal,
+                    e.to_string(),
+                )));
+            }
+        });
+
+        Ok(Response::new(Box::pin(output_stream) as Self::SendStream))
+    }
+
+    type DiffStream = Pin<Box<dyn Stream<Item = Result<ZfsDiffEntry>> + Send>>;
+
+    async fn diff(&self, info: Request<ZfsDiff>) -> Result<Response<Self::DiffStream>> {
+        let permit = self.permit().await?;
+        let mut diff = self
+            .pool()
+            .diff(&info.into_inner().into())
+            .map_err(zfs_status)?;
+
+        let (tx, rx) = tokio::sync::mpsc::channel(16);
+        let output_stream = ReceiverStream::new(rx);
+
+        tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            for entry in diff.by_ref() {
+                let item = entry
+                    .map(Into::into)
+                    .map_err(|e| tonic::Status::new(tonic::Code::Internal, e.to_string()));
+                let failed = item.is_err();
+
+                // the client hung up; dropping the diff kills zfs diff
+                if tx.blocking_send(item).is_err() || failed {
+                    return;
+                }
+            }
+
+            if let Err(e) = diff.finish() {
+                let _ = tx.blocking_send(Err(tonic::Status::new(
+                    tonic::Code::Internal,
+                    e.to_string(),
+                )));
+            }
+        });
+
+        Ok(Response::new(Box::pin(output_stream) as Self::DiffStream))
+    }
+
+    async fn receive(&self, stream: Request<Streaming<ZfsReceive>>) -> Result<Response<()>> {
+        let mut stream = stream.into_inner();
+        let Some(first) = stream.message().await? else {
+            return Err(tonic::Status::new(
+                tonic::Code::InvalidArgument,
+                "receive stream was empty",
+            ));
+        };
+
+        let permit = self.permit().await?;
+        let mut pipe = self
+            .pool()
+            .receive(&first.clone().into())
+            .map_err(zfs_status)?;
+
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<Vec<u8>>(16);
+
+        let writer = tokio::task::spawn_blocking(move || -> anyhow::Result<()> {
+            let _permit = permit;
+            while let Some(data) = rx.blocking_recv() {
+                pipe.write_all(&data)?;
+            }
+            pipe.finish()
+        });
+
+        let mut data = Some(first.data);
+        while let Some(chunk) = data {
+            // the writer only goes away when zfs receive failed; its error is reported below
+            if tx.send(chunk).await.is_err() {
+                break;
+            }
+            data = stream.message().await?.map(|m| m.data);
+        }
+        drop(tx);
+
+        writer
+            .await
+            .map_err(|e| tonic::Status::new(tonic::Code::Internal, e.to_string()))?
+            .map_err(|e| tonic::Status::new(tonic::Code::Internal, e.to_string()))?;
+
+        Ok(Response::new(()))
+    }
+
+    async fn get_properties(
+        &self,
+        info: Request<ZfsGetProperties>,
+    ) -> Result<Response<ZfsProperties>> {
+        let info = info.into_inner();
+        let properties = self
+            .run_zfs(move |pool| pool.get_properties(&info.into()))
+            .await?;
+        Ok(Response::new(properties.into()))
+    }
+
+    async fn set_properties(&self, info: Request<ZfsSetProperties>) -> Result<Response<()>> {
+        let info = info.into_inner();
+        self.run_zfs(move |pool| pool.set_properties(&info.into()))
+            .await?;
+        Ok(Response::new(()))
+    }
+
+    async fn inherit_property(&self, info: Request<ZfsInheritProperty>) -> Result<Response<()>> {
+        let info = info.into_inner();
+        self.run_zfs(move |pool| pool.inherit_property(&info.into()))
+            .await?;
+        Ok(Response::new(()))
+    }
+
+    async fn space_usage(&self, filter: Request<ZfsListFilter>) -> Result<Response<ZfsSpaceUsage>> {
+        let filter = filter.into_inner().filter;
+        let usage = self.run_zfs(move |pool| pool.space_usage(filter)).await?;
+        Ok(Response::new(usage.into()))
+    }
+
+    async fn share(&self, share: Request<ZfsShare>) -> Result<Response<()>> {
+        let share = share.into_inner();
+        self.run_zfs(move |pool| pool.share(&share.into())).await?;
+        Ok(Response::new(()))
+    }
+
+    async fn unshare(&self, unshare: Request<ZfsUnshare>) -> Result<Response<()>> {
+        let unshare = unshare.into_inner();
+        self.run_zfs(move |pool| pool.unshare(&unshare.into()))
+            .await?;
+        Ok(Response::new(()))
+    }
+
+    async fn list_shares(&self, filter: Request<ZfsListFilter>) -> Result<Response<ZfsShareList>> {
+        let filter = filter.into_inner().filter;
+        let list = self.run_zfs(move |pool| pool.list_shares(filter)).await?;
+        Ok(Response::new(list.into()))
+    }
+
+    async fn set_quota(&self, quota: Request<ZfsQuota>) -> Result<Response<()>> {
+        let quota = quota.into_inner();
+        self.run_zfs(move |pool| pool.set_quota(&quota.into()))
+            .await?;
+        Ok(Response::new(()))
+    }
+
+    async fn list_quotas(&self, filter: Request<ZfsQuotaFilter>) -> Result<Response<ZfsQuotaList>> {
+        let filter = filter.into_inner();
+        let list = self
+            .run_zfs(move |pool| pool.list_quotas(&filter.into()))
+            .await?;
+        Ok(Response::new(list.into()))
+    }
+
+    async fn user_space(&self, filter: Request<ZfsQuotaFilter>) -> Result<Response<ZfsUserSpace>> {
+        let filter = filter.into_inner();
+        let list = self
+            .run_zfs(move |pool| pool.user_space(&filter.into()))
+            .await?;
+        Ok(Response::new(list.into()))
+    }
+
+    async fn load_key(&self, info: Request<ZfsLoadKey>) -> Result<Response<()>> {
+        let info = info.into_inner();
+        self.run_zfs(move |pool| pool.load_key(&info.into()))
+            .await?;
+        Ok(Response::new(()))
+    }
+
+    async fn unload_key(&self, name: Request<ZfsName>) -> Result<Response<()>> {
+        let name = name.into_inner().name;
+        self.run_zfs(move |pool| pool.unload_key(name)).await?;
+        Ok(Response::new(()))
+    }
+
+    async fn change_key(&self, info: Request<ZfsChangeKey>) -> Result<Response<()>> {
+        let info = info.into_inner();
+        self.run_zfs(move |pool| pool.change_key(&info.into()))
+            .await?;
+        Ok(Response::new(()))
+    }
+
+    async fn pool_status(&self, _: Request<()>) -> Result<Response<ZfsPoolStatus>> {
+        let status = self.run_zfs(move |pool| pool.status()).await?;
+        Ok(Response::new(status.into()))
+    }
+
+    async fn start_scrub(&self, _: Request<()>) -> Result<Response<()>> {
+        self.run_zfs(move |pool| pool.start_scrub()).await?;
+        Ok(Response::new(()))
+    }
+
+    async fn pause_scrub(&self, _: Request<()>) -> Result<Response<()>> {
+        self.run_zfs(move |pool| pool.pause_scrub()).await?;
+        Ok(Response::new(()))
+    }
+
+    async fn cancel_scrub(&self, _: Request<()>) -> Result<Response<()>> {
+        self.run_zfs(move |pool| pool.cancel_scrub()).await?;
+        Ok(Response::new(()))
+    }
+
+    async fn start_trim(&self, _: Request<()>) -> Result<Response<()>> {
+        self.run_zfs(move |pool| pool.start_trim()).await?;
+        Ok(Response::new(()))
+    }
+
+    async fn pause_trim(&self, _: Request<()>) -> Result<Response<()>> {
+        self.run_zfs(move |pool| pool.pause_trim()).await?;
+        Ok(Response::new(()))
+    }
+
+    async fn cancel_trim(&self, _: Request<()>) -> Result<Response<()>> {
+        self.run_zfs(move |pool| pool.cancel_trim()).await?;
+        Ok(Response::new(()))
+    }
+
+    async fn list_snapshot_policies(
+        &self,
+        _: Request<()>,
+    ) -> Result<Response<ZfsSnapshotPolicyList>> {
+        Ok(Response::new(ZfsSnapshotPolicyList {
+            policies: self
+                .config
+                .zfs
+                .snapshot_policies
+                .iter()
+                .cloned()
+                .map(Into::into)
+                .collect(),
+        }))
+    }
+
+    async fn trigger_snapshot_policy(
+        &self,
+        trigger: Request<ZfsTriggerSnapshotPolicy>,
+    ) -> Result<Response<ZfsSnapshotPolicyRun>> {
+        let trigger = trigger.into_inner();
+        let period = trigger.period.map(|_| trigger.period().into());
+
+        let Some(policy) = self
+            .config
+            .zfs
+            .snapshot_policies
+            .iter()
+            .find(|x| x.dataset == trigger.dataset)
+            .cloned()
+        else {
+            return Err(tonic::Status::new(
+                tonic::Code::NotFound,
+                format!("no snapshot policy for '{}'", trigger.dataset),
+            ));
+        };
+
+        let locks = self.policy_locks.clone();
+        let run = self
+            .run_zfs(move |pool| run_snapshot_policy(&pool, &locks, &policy, period))
+            .await?;
+        Ok(Response::new(run.into()))
+    }
+
+    async fn rollback(&self, rollback: Request<ZfsRollback>) -> Result<Response<()>> {
+        let rollback = rollback.into_inner();
+        self.run_zfs(move |pool| pool.rollback(&rollback.into()))
+            .await?;
+        Ok(Response::new(()))
+    }
+}
+
+#[tonic::async_trait]
+impl<B: ZfsBackend> Zpool for Server<B> {
+    async fn list_importable(
+        &self,
+        search: Request<ZpoolSearch>,
+    ) -> Result<Response<ZpoolImportableList>> {
+        let dirs = search.into_inner().dirs;
+        let list = self.run_zfs(move |pool| pool.importable(&dirs)).await?;
+        Ok(Response::new(list.into()))
+    }
+
+    async fn import(&self, info: Request<ZpoolImport>) -> Result<Response<()>> {
+        let info = info.into_inner();
+        self.run_zfs(move |pool| pool.import(&info.into())).await?;
+        Ok(Response::new(()))
+    }
+
+    async fn export(&self, info: Request<ZpoolExport>) -> Result<Response<()>> {
+        let info = info.into_inner();
+        self.run_zfs(move |pool| pool.export(&info.into())).await?;
+        Ok(Response::new(()))
+    }
+
+    async fn add_vdev(&self, info: Request<ZpoolAddVdev>) -> Result<Response<()>> {
+        let info = info.into_inner();
+        self.run_zfs(move |pool| pool.add_vdev(&info.into()))
+            .await?;
+        Ok(Response::new(()))
+    }
+
+    async fn attach(&self, info: Request<ZpoolAttach>) -> Result<Response<()>> {
+        let info = info.into_inner();
+        self.run_zfs(move |pool| pool.attach(&info.into())).await?;
+        Ok(Response::new(()))
+    }
+
+    async fn detach(&self, device: Request<ZpoolDevice>) -> Result<Response<()>> {
+        let device = device.into_inner().device;
+        self.run_zfs(move |pool| pool.detach(device)).await?;
+        Ok(Response::new(()))
+    }
+
+    async fn replace(&self, info: Request<ZpoolReplace>) -> Result<Response<()>> {
+        let info = info.into_inner();
+        self.run_zfs(move |pool| pool.replace(&info.into())).await?;
+        Ok(Response::new(()))
+    }
+
+    async fn online(&self, info: Request<ZpoolOnline>) -> Result<Response<()>> {
+        let info = info.into_inner();
+        self.run_zfs(move |pool| pool.online(&info.into())).await?;
+        Ok(Response::new(()))
+    }
+
+    async fn offline(&self, info: Request<ZpoolOffline>) -> Result<Response<()>> {
+        let info = info.into_inner();
+        self.run_zfs(move |pool| pool.offline(&info.into())).await?;
+        Ok(Response::new(()))
+    }
+
+    async fn clear(&self, info: Request<ZpoolClear>) -> Result<Response<()>> {
+        let device = info.into_inner().device;
+        self.run_zfs(move |pool| pool.clear(device)).await?;
+        Ok(Response::new(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod systemd {
+        use tokio_stream::StreamExt;
+
+        use crate::{
+            grpc::{GrpcLogDirection, GrpcLogParams},
+            testutil::{get_systemd_client, make_server},
+        };
+
+        #[tokio::test]
+        async fn test_log() {
+            let mut client = get_systemd_client(make_server(None).await.unwrap())
+                .await
+                .unwrap();
+            let log = client
+                .unit_log(GrpcLogParams {
+                    name: "network.target".into(),
+                    count: 100,
+                    cursor: "".into(),
+                    direction: GrpcLogDirection::Forward.into(),
+                })
+                .await
+                .unwrap();
+
+            let mut log = log.into_inner();
+            let mut total = 0;
+
+            while let Some(item) = log.next().await {
+                let item = item.unwrap();
+                assert!(!item.msg.is_empty());
+                assert!(item.time.is_some());
+                assert_ne!(!item.time.unwrap().seconds, 0);
+                assert_ne!(item.pid, 0);
+                assert!(!item.cursor.is_empty());
+                total += 1;
+            }
+
+            assert!(total < 100);
+            assert!(total > 0);
+        }
+    }
+
+    // the zfs service against the in-memory backend; these run anywhere
+    mod mock {
+        use crate::{
+            grpc::{
+                ZfsBookmark, ZfsClone, ZfsDataset, ZfsDestroy, ZfsGetProperties, ZfsHold,
+                ZfsInheritProperty, ZfsList, ZfsListFilter, ZfsListSort, ZfsModifyDataset,
+                ZfsModifyVolume, ZfsName, ZfsNode, ZfsPoolHealth, ZfsPropertySource, ZfsQuota,
+                ZfsQuotaFilter, ZfsQuotaType, ZfsRollback, ZfsSetProperties, ZfsShare,
+                ZfsShareProtocol, ZfsSnapshot, ZfsSnapshotPeriod, ZfsTriggerSnapshotPolicy,
+                ZfsType, ZfsUnshare, ZfsVolume, ZpoolAddVdev, ZpoolAttach, ZpoolClear, ZpoolDevice,
+                ZpoolExport, ZpoolImport,
+            },
+            testutil::{get_zfs_client, get_zpool_client, make_mock_server, DEFAULT_CONFIG},
+            zfs::SnapshotPolicy,
+        };
+
+        #[tokio::test]
+        async fn test_dataset_volume_lifecycle() {
+            let mut client = get_zfs_client(make_mock_server(None).await.unwrap())
+                .await
+                .unwrap();
+
+            client
+                .create_dataset(tonic::Request::new(ZfsDataset {
+                    name: "dataset".to_string(),
+                    quota: Some(5 * 1024 * 1024),
+                    ..Default::default()
+                }))
+                .await
+                .unwrap();
+
+            client
+                .create_volume(tonic::Request::new(ZfsVolume {
+                    name: "dataset/volume".to_string(),
+                    size: 1024 * 1024,
+                    ..Default::default()
+                }))
+                .await
+                .unwrap();
+
+            assert!(client
+                .create_dataset(tonic::Request::new(ZfsDataset {
+                    name: "dataset".to_string(),
+                    ..Default::default()
+                }))
+                .await
+                .is_err());
+
+            assert!(client
+                .create_dataset(tonic::Request::new(ZfsDataset {
+                    name: "missing/dataset".to_string(),
+                    ..Default::default()
+                }))
+                .await
+                .is_err());
+
+            let list = client
+                .list(tonic::Request::new(ZfsListFilter::default()))
+                .await
+                .unwrap()
+                .into_inner();
+
+            let dataset = list.entries.iter().find(|x| x.name == "dataset").unwrap();
+            assert_eq!(dataset.kind(), ZfsType::Dataset);
+            assert_eq!(
+                dataset.full_name,
+                format!("{}/dataset", DEFAULT_CONFIG.zfs.pool)
+            );
+            assert_eq!(
+                dataset.mountpoint.as_deref(),
+                Some(format!("/{}/dataset", DEFAULT_CONFIG.zfs.pool).as_str())
+            );
+            assert_ne!(dataset.used, 0);
+
+            let volume = list
+                .entries
+                .iter()
+                .find(|x| x.name == "dataset/volume")
+                .unwrap();
+            assert_eq!(volume.kind(), ZfsType::Volume);
+            assert_eq!(volume.size, 1024 * 1024);
+            assert!(volume.mountpoint.is_none());
+
+            // children have to go first
+            assert!(client
+                .destroy(tonic::Request::new(ZfsDestroy {
+                    name: "dataset".to_string(),
+                    ..Default::default()
+                }))
+                .await
+                .is_err());
+
+            client
+                .modify_dataset(tonic::Request::new(ZfsModifyDataset {
+                    name: "dataset".to_string(),
+                    modifications: Some(ZfsDataset {
+                        name: "renamed".to_string(),
+                        ..Default::default()
+                    }),
+                }))
+                .await
+                .unwrap();
+
+            for name in ["renamed/volume", "renamed"] {
+                client
+                    .destroy(tonic::Request::new(ZfsDestroy {
+                        name: name.to_string(),
+                        ..Default::default()
+                    }))
+                    .await
+                    .unwrap();
+            }
+
+            let list = client
+                .list(tonic::Request::new(ZfsListFilter::default()))
+                .await
+                .unwrap()
+                .into_inner();
+            assert!(list.entries.is_empty());
+        }
+
+        #[tokio::test]
+        async fn test_list_fields() {
+            let mut client = get_zfs_client(make_mock_server(None).await.unwrap())
+                .await
+                .unwrap();
+
+            client
+                .create_dataset(tonic::Request::new(ZfsDataset {
+                    name: "dataset".to_string(),
+                    quota: Some(5 * 1024 * 1024),
+                    compression: Some("zstd".to_string()),
+                    ..Default::default()
+                }))
+                .await
+                .unwrap();
+
+            let list = client
+                .list(tonic::Request::new(ZfsListFilter::default()))
+                .await
+                .unwrap()
+                .into_inner();
+            let entry = &list.entries[0];
+            assert_eq!(entry.size, 5 * 1024 * 1024);
+            assert_eq!(entry.quota, Some(5 * 1024 * 1024));
+            assert_eq!(entry.compression.as_deref(), Some("zstd"));
+            assert_eq!(entry.compressratio, Some(1.0));
+            assert!(entry.creation.is_some());
+            assert!(entry.recordsize.is_some());
+            assert!(entry.volblocksize.is_none());
+
+            let list = client
+                .list(tonic::Request::new(ZfsListFilter {
+                    fields: vec!["compression".to_string(), "name".to_string()],
+                    ..Default::default()
+                }))
+                .await
+                .unwrap()
+                .into_inner();
+            let entry = &list.entries[0];
+            // size is always there, even when the quota behind it isn't asked for
+            assert_eq!(entry.size, 5 * 1024 * 1024);
+            assert!(entry.quota.is_none());
+            assert!(entry.creation.is_none());
+            assert_eq!(entry.compression.as_deref(), Some("zstd"));
+
+            assert!(client
+                .list(tonic::Request::new(ZfsListFilter {
+                    fields: vec!["bogus".to_string()],
+                    ..Default::default()
+                }))
+                .await
+                .is_err());
+        }
+
+        #[tokio::test]
+        async fn test_list_filters() {
+            let mut client = get_zfs_client(make_mock_server(None).await.unwrap())
+                .await
+                .unwrap();
+
+            for name in ["a", "a/b", "a/b/c"] {
+                client
+                    .create_dataset(tonic::Request::new(ZfsDataset {
+                        name: name.to_string(),
+                        compression: (name == "a/b").then(|| "lz4".to_string()),
+                        ..Default::default()
+                    }))
+                    .await
+                    .unwrap();
+            }
+
+            client
+                .create_volume(tonic::Request::new(ZfsVolume {
+                    name: "vol".to_string(),
+                    size: 100 * 1024 * 1024,
+                    ..Default::default()
+                }))
+                .await
+                .unwrap();
+
+            client
+                .create_snapshot(tonic::Request::new(ZfsSnapshot {
+                    dataset: "a".to_string(),
+                    name: "snap".to_string(),
+                    recursive: false,
+                }))
+                .await
+                .unwrap();
+
+            let names = |list: &ZfsList| {
+                list.entries
+                    .iter()
+                    .map(|x| x.name.clone())
+                    .collect::<Vec<String>>()
+            };
+
+            let list = client
+                .list(tonic::Request::new(ZfsListFilter::default()))
+                .await
+                .unwrap()
+                .into_inner();
+            assert_eq!(names(&list), vec!["a", "a/b", "a/b/c", "vol"]);
+            assert!(list.next_page_token.is_empty());
+
+            let list = client
+                .list(tonic::Request::new(ZfsListFilter {
+                    types: vec![ZfsType::Snapshot.into()],
+                    ..Default::default()
+                }))
+                .await
+                .unwrap()
+                .into_inner();
+            assert_eq!(names(&list), vec!["a@snap"]);
+            assert_eq!(list.entries[0].kind(), ZfsType::Snapshot);
+
+            // a snapshot sits a level below its dataset
+            let list = client
+                .list(tonic::Request::new(ZfsListFilter {
+                    types: vec![ZfsType::Dataset.into(), ZfsType::Snapshot.into()],
+                    depth: Some(2),
+                    ..Default::default()
+                }))
+                .await
+                .unwrap()
+                .into_inner();
+            assert_eq!(names(&list), vec!["a", "a/b", "a@snap"]);
+
+            // inherited values match too
+            let list = client
+                .list(tonic::Request::new(ZfsListFilter {
+                    properties: [("compression".to_string(), "lz4".to_string())].into(),
+                    ..Default::default()
+                }))
+                .await
+                .unwrap()
+                .into_inner();
+            assert_eq!(names(&list), vec!["a/b", "a/b/c"]);
+
+            let list = client
+                .list(tonic::Request::new(ZfsListFilter {
+                    sort: ZfsListSort::Used.into(),
+                    descending: true,
+                    ..Default::default()
+                }))
+                .await
+                .unwrap()
+                .into_inner();
+            assert_eq!(list.entries[0].name, "vol");
+            assert!(list.entries.windows(2).all(|x| x[0].used >= x[1].used));
+
+            let list = client
+                .list(tonic::Request::new(ZfsListFilter {
+                    page_size: 3,
+                    ..Default::default()
+                }))
+                .await
+                .unwrap()
+                .into_inner();
+            assert_eq!(names(&list), vec!["a", "a/b", "a/b/c"]);
+            assert!(!list.next_page_token.is_empty());
+
+            let list = client
+                .list(tonic::Request::new(ZfsListFilter {
+                    page_size: 3,
+                    page_token: list.next_page_token,
+                    ..Default::default()
+                }))
+                .await
+                .unwrap()
+                .into_inner();
+            assert_eq!(names(&list), vec!["vol"]);
+            assert!(list.next_page_token.is_empty());
+
+            assert!(client
+                .list(tonic::Request::new(ZfsListFilter {
+                    page_token: "bogus".to_string(),
+                    ..Default::default()
+                }))
+                .await
+                .is_err());
+        }
+
+        #[tokio::test]
+        async fn test_tree() {
+            let mut client = get_zfs_client(make_mock_server(None).await.unwrap())
+                .await
+                .unwrap();
+
+            for name in ["a", "a/b", "a/b/c", "d"] {
+                client
+                    .create_dataset(tonic::Request::new(ZfsDataset {
+                        name: name.to_string(),
+                        compression: (name == "a/b").then(|| "lz4".to_string()),
+                        ..Default::default()
+                    }))
+                    .await
+                    .unwrap();
+            }
+
+            client
+                .create_snapshot(tonic::Request::new(ZfsSnapshot {
+                    dataset: "a".to_string(),
+                    name: "snap".to_string(),
+                    recursive: false,
+                }))
+                .await
+                .unwrap();
+
+            let name = |node: &ZfsNode| node.entry.as_ref().unwrap().name.clone();
+
+            let tree = client
+                .tree(tonic::Request::new(ZfsListFilter::default()))
+                .await
+                .unwrap()
+                .into_inner();
+            assert_eq!(
+                tree.nodes.iter().map(name).collect::<Vec<_>>(),
+                vec!["a", "d"]
+            );
+
+            let a = &tree.nodes[0];
+            let b = &a.children[0];
+            assert_eq!(a.children.iter().map(name).collect::<Vec<_>>(), vec!["a/b"]);
+            assert_eq!(
+                b.children.iter().map(name).collect::<Vec<_>>(),
+                vec!["a/b/c"]
+            );
+            assert_eq!(a.used_by_children, b.entry.as_ref().unwrap().used);
+            assert_eq!(b.children[0].used_by_children, 0);
+            assert!(tree.nodes[1].children.is_empty());
+
+            let tree = client
+                .tree(tonic::Request::new(ZfsListFilter {
+                    types: vec![ZfsType::Dataset.into(), ZfsType::Snapshot.into()],
+                    ..Default::default()
+                }))
+                .await
+                .unwrap()
+                .into_inner();
+            let a = &tree.nodes[0];
+            assert_eq!(
+                a.children.iter().map(name).collect::<Vec<_>>(),
+                vec!["a/b", "a@snap"]
+            );
+            assert_eq!(
+                a.used_by_children,
+                a.children[0].entry.as_ref().unwrap().used
+            );
+
+            // without a listed parent, a node becomes a root
+            let tree = client
+                .tree(tonic::Request::new(ZfsListFilter {
+                    properties: [("compression".to_string(), "lz4".to_string())].into(),
+                    ..Default::default()
+                }))
+                .await
+                .unwrap()
+                .into_inner();
+            assert_eq!(tree.nodes.iter().map(name).collect::<Vec<_>>(), vec!["a/b"]);
+            assert_eq!(tree.nodes[0].children.len(), 1);
+        }
+
+        #[tokio::test]
+        async fn test_properties() {
+            let mut client = get_zfs_client(make_mock_server(None).await.unwrap())
+                .await
+                .unwrap();
+
+            for name in ["parent", "parent/child"] {
+                client
+                    .create_dataset(tonic::Request::new(ZfsDataset {
+                        name: name.to_string(),
+                        ..Default::default()
+                    }))
+                    .await
+                    .unwrap();
+            }
+
+            client
+                .set_properties(tonic::Request::new(ZfsSetProperties {
+                    name: "parent".to_string(),
+                    properties: [
+                        ("compression".to_string(), "zstd".to_string()),
+                        ("buckle:note".to_string(), "hello".to_string()),
+                    ]
+                    .into(),
+                }))
+                .await
+                .unwrap();
+
+            let properties = client
+                .get_properties(tonic::Request::new(ZfsGetProperties {
+                    name: "parent/child".to_string(),
+                    properties: vec!["compression".to_string(), "buckle:note".to_string()],
+                }))
+                .await
+                .unwrap()
+                .into_inner()
+                .properties;
+
+            assert_eq!(properties.len(), 2);
+            for property in &properties {
+                assert_eq!(property.source(), ZfsPropertySource::Inherited);
+                assert_eq!(property.inherited_from.as_deref(), Some("parent"));
+            }
+
+            assert!(client
+                .set_properties(tonic::Request::new(ZfsSetProperties {
+                    name: "parent".to_string(),
+                    properties: [("used".to_string(), "1".to_string())].into(),
+                }))
+                .await
+                .is_err());
+
+            client
+                .inherit_property(tonic::Request::new(ZfsInheritProperty {
+                    name: "parent".to_string(),
+                    property: "compression".to_string(),
+                    recursive: true,
+                }))
+                .await
+                .unwrap();
+
+            let properties = client
+                .get_properties(tonic::Request::new(ZfsGetProperties {
+                    name: "parent/child".to_string(),
+                    properties: vec!["compression".to_string()],
+                }))
+                .await
+                .unwrap()
+                .into_inner()
+                .properties;
+            assert_eq!(properties[0].source(), ZfsPropertySource::Default);
+
+            // names that would turn into other arguments on zfs' command line
+            for key in ["", "-r", "a=b", "a b", "a,b"] {
+                let err = client
+                    .set_properties(tonic::Request::new(ZfsSetProperties {
+                        name: "parent".to_string(),
+                        properties: [(key.to_string(), "1".to_string())].into(),
+                    }))
+                    .await
+                    .unwrap_err();
+                assert_eq!(err.code(), tonic::Code::InvalidArgument, "{}", key);
+
+                let err = client
+                    .inherit_property(tonic::Request::new(ZfsInheritProperty {
+                        name: "parent".to_string(),
+                        property: key.to_string(),
+                        recursive: false,
+                    }))
+                    .await
+                    .unwrap_err();
+                assert_eq!(err.code(), tonic::Code::InvalidArgument, "{}", key);
+
+                let err = client
+                    .get_properties(tonic::Request::new(ZfsGetProperties {
+                        name: "parent".to_string(),
+                        properties: vec![key.to_string()],
+                    }))
+                    .await
+                    .unwrap_err();
+                assert_eq!(err.code(), tonic::Code::InvalidArgument, "{}", key);
+            }
+        }
+
+        #[tokio::test]
+        async fn test_snapshots() {
+            let mut client = get_zfs_client(make_mock_server(None).await.unwrap())
+                .await
+                .unwrap();
+
+            client
+                .create_dataset(tonic::Request::new(ZfsDataset {
+                    name: "dataset".to_string(),
+                    ..Default::default()
+                }))
+                .await
+                .unwrap();
+
+            for name in ["first", "second"] {
+                client
+                    .create_snapshot(tonic::Request::new(ZfsSnapshot {
+                        dataset: "dataset".to_string(),
+                        name: name.to_string(),
+                        recursive: false,
+                    }))
+                    .await
+                    .unwrap();
+            }
+
+            let snapshots = client
+                .list_snapshots(tonic::Request::new(ZfsListFilter::default()))
+                .await
+                .unwrap()
+                .into_inner()
+                .entries;
+            assert_eq!(snapshots.len(), 2);
+
+            assert!(client
+                .rollback(tonic::Request::new(ZfsRollback {
+                    dataset: "dataset".to_string(),
+                    snapshot: "first".to_string(),
+                    destroy_newer: false,
+                }))
+                .await
+                .is_err());
+
+            client
+                .rollback(tonic::Request::new(ZfsRollback {
+                    dataset: "dataset".to_string(),
+                    snapshot: "first".to_string(),
+                    destroy_newer: true,
+                }))
+                .await
+                .unwrap();
+
+            client
+                .clone_snapshot(tonic::Request::new(ZfsClone {
+                    dataset: "dataset".to_string(),
+                    snapshot: "first".to_string(),
+                    target: "clone".to_string(),
+                }))
+                .await
+                .unwrap();
+
+            // the clone depends on the snapshot until it's promoted
+            assert!(client
+                .destroy_snapshot(tonic::Request::new(ZfsSnapshot {
+                    dataset: "dataset".to_string(),
+                    name: "first".to_string(),
+                    recursive: false,
+                }))
+                .await
+                .is_err());
+
+            client
+                .promote(tonic::Request::new(ZfsName {
+                    name: "clone".to_string(),
+                }))
+                .await
+                .unwrap();
+
+            let list = client
+                .list(tonic::Request::new(ZfsListFilter::default()))
+                .await
+                .unwrap()
+                .into_inner();
+            let clone = list.entries.iter().find(|x| x.name == "clone").unwrap();
+            assert!(clone.origin.is_none());
+            let dataset = list.entries.iter().find(|x| x.name == "dataset").unwrap();
+            assert_eq!(dataset.origin.as_deref(), Some("clone@first"));
+
+            let snapshots = client
+                .list_snapshots(tonic::Request::new(ZfsListFilter::default()))
+                .await
+                .unwrap()
+                .into_inner()
+                .entries;
+            assert_eq!(snapshots.len(), 1);
+            assert_eq!(snapshots[0].dataset, "clone");
+        }
+
+        #[tokio::test]
+        async fn test_holds_bookmarks() {
+            let mut client = get_zfs_client(make_mock_server(None).await.unwrap())
+                .await
+                .unwrap();
+
+            for name in ["dataset", "dataset/child"] {
+                client
+                    .create_dataset(tonic::Request::new(ZfsDataset {
+                        name: name.to_string(),
+                        ..Default::default()
+                    }))
+                    .await
+                    .unwrap();
+            }
+
+            let snapshot = ZfsSnapshot {
+                dataset: "dataset".to_string(),
+                name: "backup".to_string(),
+                recursive: true,
+            };
+            client
+                .create_snapshot(tonic::Request::new(snapshot.clone()))
+                .await
+                .unwrap();
+
+            let hold = ZfsHold {
+                dataset: "dataset".to_string(),
+                snapshot: "backup".to_string(),
+                tag: "offsite".to_string(),
+                recursive: true,
+            };
+            client
+                .hold(tonic::Request::new(hold.clone()))
+                .await
+                .unwrap();
+            assert!(client
+                .hold(tonic::Request::new(hold.clone()))
+                .await
+                .is_err());
+
+            let holds = client
+                .list_holds(tonic::Request::new(snapshot.clone()))
+                .await
+                .unwrap()
+                .into_inner()
+                .entries;
+            assert_eq!(holds.len(), 2);
+            assert_eq!(holds[0].dataset, "dataset");
+            assert_eq!(holds[0].snapshot, "backup");
+            assert_eq!(holds[0].tag, "offsite");
+            assert!(holds[0].created.is_some());
+            assert_eq!(holds[1].dataset, "dataset/child");
+
+            // held snapshots can't be destroyed
+            assert!(client
+                .destroy_snapshot(tonic::Request::new(snapshot.clone()))
+                .await
+                .is_err());
+
+            let bookmark = ZfsBookmark {
+                dataset: "dataset".to_string(),
+                name: "backup".to_string(),
+                snapshot: "backup".to_string(),
+            };
+            client
+                .create_bookmark(tonic::Request::new(bookmark.clone()))
+                .await
+                .unwrap();
+            assert!(client
+                .create_bookmark(tonic::Request::new(bookmark.clone()))
+                .await
+                .is_err());
+
+            client
+                .release(tonic::Request::new(hold.clone()))
+                .await
+                .unwrap();
+            assert!(client.release(tonic::Request::new(hold)).await.is_err());
+            client
+                .destroy_snapshot(tonic::Request::new(snapshot))
+                .await
+                .unwrap();
+
+            // the bookmark outlives its snapshot
+            let bookmarks = client
+                .list_bookmarks(tonic::Request::new(ZfsListFilter::default()))
+                .await
+                .unwrap()
+                .into_inner()
+                .entries;
+            assert_eq!(bookmarks.len(), 1);
+            assert_eq!(bookmarks[0].dataset, "dataset");
+            assert_eq!(bookmarks[0].name, "backup");
+            assert_eq!(
+                bookmarks[0].full_name,
+                format!("{}/dataset#backup", DEFAULT_CONFIG.zfs.pool)
+            );
+            assert!(bookmarks[0].created.is_some());
+
+            client
+                .destroy_bookmark(tonic::Request::new(bookmark.clone()))
+                .await
+                .unwrap();
+            assert!(client
+                .destroy_bookmark(tonic::Request::new(bookmark))
+                .await
+                .is_err());
+            assert!(client
+                .list_bookmarks(tonic::Request::new(ZfsListFilter::default()))
+                .await
+                .unwrap()
+                .into_inner()
+                .entries
+                .is_empty());
+        }
+
+        #[tokio::test]
+        async fn test_quotas() {
+            let mut client = get_zfs_client(make_mock_server(None).await.unwrap())
+                .await
+                .unwrap();
+
+            client
+                .create_dataset(tonic::Request::new(ZfsDataset {
+                    name: "dataset".to_string(),
+                    ..Default::default()
+                }))
+                .await
+                .unwrap();
+
+            for (kind, id, quota) in [
+                (ZfsQuotaType::User, "1000", 10 * 1024 * 1024),
+                (ZfsQuotaType::User, "1001", 20 * 1024 * 1024),
+                (ZfsQuotaType::Group, "100", 30 * 1024 * 1024),
+            ] {
+                client
+                    .set_quota(tonic::Request::new(ZfsQuota {
+                        dataset: "dataset".to_string(),
+                        kind: kind.into(),
+                        id: id.to_string(),
+                        quota: Some(quota),
+                    }))
+                    .await
+                    .unwrap();
+            }
+
+            let err = client
+                .set_quota(tonic::Request::new(ZfsQuota {
+                    dataset: "dataset".to_string(),
+                    kind: ZfsQuotaType::User.into(),
+                    id: "bad id".to_string(),
+                    quota: Some(1024),
+                }))
+                .await
+                .unwrap_err();
+            assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+            let filter = ZfsQuotaFilter {
+                dataset: "dataset".to_string(),
+                kind: ZfsQuotaType::User.into(),
+            };
+            let quotas = client
+                .list_quotas(tonic::Request::new(filter.clone()))
+                .await
+                .unwrap()
+                .into_inner()
+                .quotas;
+            assert_eq!(
+                quotas
+                    .iter()
+                    .map(|x| (x.id.as_str(), x.quota))
+                    .collect::<Vec<_>>(),
+                vec![
+                    ("1000", Some(10 * 1024 * 1024)),
+                    ("1001", Some(20 * 1024 * 1024))
+                ]
+            );
+
+            // quotas aren't regular properties
+            let properties = client
+                .get_properties(tonic::Request::new(ZfsGetProperties {
+                    name: "dataset".to_string(),
+                    properties: Vec::new(),
+                }))
+                .await
+                .unwrap()
+                .into_inner()
+                .properties;
+            assert!(!properties.iter().any(|x| x.name.contains('@')));
+
+            client
+                .set_quota(tonic::Request::new(ZfsQuota {
+                    dataset: "dataset".to_string(),
+                    kind: ZfsQuotaType::User.into(),
+                    id: "1000".to_string(),
+                    quota: None,
+                }))
+                .await
+                .unwrap();
+
+            let space = client
+                .user_space(tonic::Request::new(filter))
+                .await
+                .unwrap()
+                .into_inner()
+                .entries;
+            assert_eq!(space.len(), 1);
+            assert_eq!(space[0].id, "1001");
+            assert_eq!(space[0].kind(), ZfsQuotaType::User);
+            assert_eq!(space[0].quota, Some(20 * 1024 * 1024));
+            assert_eq!(space[0].objquota, None);
+
+            let groups = client
+                .list_quotas(tonic::Request::new(ZfsQuotaFilter {
+                    dataset: "dataset".to_string(),
+                    kind: ZfsQuotaType::Group.into(),
+                }))
+                .await
+                .unwrap()
+                .into_inner()
+                .quotas;
+            assert_eq!(groups.len(), 1);
+            assert_eq!(groups[0].id, "100");
+
+            assert!(client
+                .user_space(tonic::Request::new(ZfsQuotaFilter {
+                    dataset: "missing".to_string(),
+                    kind: ZfsQuotaType::Project.into(),
+                }))
+                .await
+                .is_err());
+        }
+
+        #[tokio::test]
+        async fn test_shares() {
+            let mut client = get_zfs_client(make_mock_server(None).await.unwrap())
+                .await
+                .unwrap();
+
+            for name in ["media", "media/music", "backup"] {
+                client
+                    .create_dataset(tonic::Request::new(ZfsDataset {
+                        name: name.to_string(),
+                        ..Default::default()
+                    }))
+                    .await
+                    .unwrap();
+            }
+
+            client
+                .share(tonic::Request::new(ZfsShare {
+                    dataset: "media".to_string(),
+                    protocol: ZfsShareProtocol::Nfs.into(),
+                    networks: vec!["192.168.1.0/24".to_string(), "nas".to_string()],
+                    read_only: true,
+                    root_squash: None,
+                }))
+                .await
+                .unwrap();
+            client
+                .share(tonic::Request::new(ZfsShare {
+                    dataset: "backup".to_string(),
+                    protocol: ZfsShareProtocol::Smb.into(),
+                    ..Default::default()
+                }))
+                .await
+                .unwrap();
+
+            // smb shares take no options, and networks can't smuggle in more of them
+            for share in [
+                ZfsShare {
+                    dataset: "backup".to_string(),
+                    protocol: ZfsShareProtocol::Smb.into(),
+                    read_only: true,
+                    ..Default::default()
+                },
+                ZfsShare {
+                    dataset: "media".to_string(),
+                    protocol: ZfsShareProtocol::Nfs.into(),
+                    networks: vec!["nas,no_root_squash".to_string()],
+                    ..Default::default()
+                },
+            ] {
+                assert!(client.share(tonic::Request::new(share)).await.is_err());
+            }
+
+            let shares = client
+                .list_shares(tonic::Request::new(ZfsListFilter::default()))
+                .await
+                .unwrap()
+                .into_inner()
+                .entries;
+            assert_eq!(shares.len(), 3);
+
+            let media = shares
+                .iter()
+                .find(|x| x.share.as_ref().unwrap().dataset == "media")
+                .unwrap();
+            assert_eq!(media.options, "ro=@192.168.1.0/24:nas");
+            let share = media.share.as_ref().unwrap();
+            assert_eq!(share.protocol(), ZfsShareProtocol::Nfs);
+            assert_eq!(share.networks, vec!["192.168.1.0/24", "nas"]);
+            assert!(share.read_only);
+            assert_eq!(share.root_squash, Some(true));
+
+            // inherited by children
+            assert!(shares
+                .iter()
+                .any(|x| x.share.as_ref().unwrap().dataset == "media/music"));
+
+            let list = client
+                .list(tonic::Request::new(ZfsListFilter::default()))
+                .await
+                .unwrap()
+                .into_inner();
+            let backup = list.entries.iter().find(|x| x.name == "backup").unwrap();
+            assert_eq!(backup.sharenfs.as_deref(), Some("off"));
+            assert_eq!(backup.sharesmb.as_deref(), Some("on"));
+
+            client
+                .unshare(tonic::Request::new(ZfsUnshare {
+                    dataset: "media".to_string(),
+                    protocol: ZfsShareProtocol::Nfs.into(),
+                }))
+                .await
+                .unwrap();
+
+            let shares = client
+                .list_shares(tonic::Request::new(ZfsListFilter {
+                    filter: Some("media".to_string()),
+                    ..Default::default()
+                }))
+                .await
+                .unwrap()
+                .into_inner()
+                .entries;
+            assert!(shares.is_empty());
+        }
+
+        #[tokio::test]
+        async fn test_volume_without_device() {
+            let mut client = get_zfs_client(make_mock_server(None).await.unwrap())
+                .await
+                .unwrap();
+
+            // volmode=none volumes never show up under /dev/zvol, so there's nothing to wait for
+            client
+                .create_volume(tonic::Request::new(ZfsVolume {
+                    name: "volume".to_string(),
+                    size: 1024 * 1024,
+                    volmode: Some("none".to_string()),
+                    ..Default::default()
+                }))
+                .await
+                .unwrap();
+
+            client
+                .modify_volume(tonic::Request::new(ZfsModifyVolume {
+                    name: "volume".to_string(),
+                    modifications: Some(ZfsVolume {
+                        name: "renamed".to_string(),
+                        ..Default::default()
+                    }),
+                }))
+                .await
+                .unwrap();
+
+            let list = client
+                .list(tonic::Request::new(ZfsListFilter::default()))
+                .await
+                .unwrap()
+                .into_inner();
+            assert!(list.entries.iter().any(|x| x.name == "renamed"));
+        }
+
+        #[tokio::test]
+        async fn test_invalid_names() {
+            let mut client = get_zfs_client(make_mock_server(None).await.unwrap())
+                .await
+                .unwrap();
+
+            client
+                .create_dataset(tonic::Request::new(ZfsDataset {
+                    name: "dataset".to_string(),
+                    ..Default::default()
+                }))
+                .await
+                .unwrap();
+
+            for name in [
+                "",
+                "/dataset",
+                "dataset/",
+                "dataset//child",
+                "../dataset",
+                "dataset/..",
+                "dataset@snapshot",
+                "dataset#bookmark",
+                "-o",
+                "dataset/-child",
+                "data\nset",
+            ] {
+                let err = client
+                    .create_dataset(tonic::Request::new(ZfsDataset {
+                        name: name.to_string(),
+                        ..Default::default()
+                    }))
+                    .await
+                    .unwrap_err();
+                assert_eq!(err.code(), tonic::Code::InvalidArgument, "{}", name);
+
+                let err = client
+                    .destroy(tonic::Request::new(ZfsDestroy {
+                        name: name.to_string(),
+                        ..Default::default()
+                    }))
+                    .await
+                    .unwrap_err();
+                assert_eq!(err.code(), tonic::Code::InvalidArgument, "{}", name);
+            }
+
+            let err = client
+                .modify_dataset(tonic::Request::new(ZfsModifyDataset {
+                    name: "dataset".to_string(),
+                    modifications: Some(ZfsDataset {
+                        name: "other/../dataset".to_string(),
+                        ..Default::default()
+                    }),
+                }))
+                .await
+                .unwrap_err();
+            assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+            for snapshot in ["", "-r", "first@second", "first/second"] {
+                let err = client
+                    .create_snapshot(tonic::Request::new(ZfsSnapshot {
+                        dataset: "dataset".to_string(),
+                        name: snapshot.to_string(),
+                        recursive: false,
+                    }))
+                    .await
+                    .unwrap_err();
+                assert_eq!(err.code(), tonic::Code::InvalidArgument, "{}", snapshot);
+            }
+
+            let err = client
+                .clone_snapshot(tonic::Request::new(ZfsClone {
+                    dataset: "dataset".to_string(),
+                    snapshot: "snapshot".to_string(),
+                    target: "dataset@clone".to_string(),
+                }))
+                .await
+                .unwrap_err();
+            assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+            // zfs failures are still internal errors
+            let err = client
+                .destroy(tonic::Request::new(ZfsDestroy {
+                    name: "missing".to_string(),
+                    ..Default::default()
+                }))
+                .await
+                .unwrap_err();
+            assert_eq!(err.code(), tonic::Code::Internal);
+
+            // names with spaces, dots and colons are fine by zfs
+            client
+                .create_dataset(tonic::Request::new(ZfsDataset {
+                    name: "dataset/my files.v2:old".to_string(),
+                    ..Default::default()
+                }))
+                .await
+                .unwrap();
+
+            // the pool's name counts against zfs' limit on the full name
+            let room = 255 - DEFAULT_CONFIG.zfs.pool.len() - "/dataset/".len();
+            for (len, code) in [
+                (room, tonic::Code::Ok),
+                (room + 1, tonic::Code::InvalidArgument),
+            ] {
+                let res = client
+                    .create_dataset(tonic::Request::new(ZfsDataset {
+                        name: format!("dataset/{}", "a".repeat(len)),
+                        ..Default::default()
+                    }))
+                    .await;
+                assert_eq!(res.err().map_or(tonic::Code::Ok, |e| e.code()), code);
+            }
+        }
+
+        #[tokio::test]
+        async fn test_invalid_pool_arguments() {
+            let mut client = get_zpool_client(make_mock_server(None).await.unwrap())
+                .await
+                .unwrap();
+
+            for name in [
+                "",
+                "-a",
+                "-f",
+                "1pool",
+                "mirror0",
+                "log",
+                "c0d0",
+                "pool/child",
+            ] {
+                let err = client
+                    .import(tonic::Request::new(ZpoolImport {
+                        name: name.to_string(),
+                        ..Default::default()
+                    }))
+                    .await
+                    .unwrap_err();
+                assert_eq!(err.code(), tonic::Code::InvalidArgument, "{}", name);
+
+                let err = client
+                    .export(tonic::Request::new(ZpoolExport {
+                        name: name.to_string(),
+                        force: false,
+                    }))
+                    .await
+                    .unwrap_err();
+                assert_eq!(err.code(), tonic::Code::InvalidArgument, "{}", name);
+            }
+
+            let err = client
+                .import(tonic::Request::new(ZpoolImport {
+                    name: "1234".to_string(),
+                    new_name: Some("-a".to_string()),
+                    ..Default::default()
+                }))
+                .await
+                .unwrap_err();
+            assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+            for device in ["", "-f", "sda", "disk/by-id/x", "12ab"] {
+                let err = client
+                    .add_vdev(tonic::Request::new(ZpoolAddVdev {
+                        devices: vec!["/dev/sda".to_string(), device.to_string()],
+                        ..Default::default()
+                    }))
+                    .await
+                    .unwrap_err();
+                assert_eq!(err.code(), tonic::Code::InvalidArgument, "{}", device);
+
+                let err = client
+                    .attach(tonic::Request::new(ZpoolAttach {
+                        device: "/dev/sda".to_string(),
+                        new_device: device.to_string(),
+                        force: false,
+                    }))
+                    .await
+                    .unwrap_err();
+                assert_eq!(err.code(), tonic::Code::InvalidArgument, "{}", device);
+
+                let err = client
+                    .detach(tonic::Request::new(ZpoolDevice {
+                        device: device.to_string(),
+                    }))
+                    .await
+                    .unwrap_err();
+                assert_eq!(err.code(), tonic::Code::InvalidArgument, "{}", device);
+
+                let err = client
+                    .clear(tonic::Request::new(ZpoolClear {
+                        device: Some(device.to_string()),
+                    }))
+                    .await
+                    .unwrap_err();
+                assert_eq!(err.code(), tonic::Code::InvalidArgument, "{}", device);
+            }
+
+            // paths and guids make it through to zpool
+            for device in ["/dev/sda", "1234567890"] {
+                let err = client
+                    .detach(tonic::Request::new(ZpoolDevice {
+                        device: device.to_string(),
+                    }))
+                    .await
+                    .unwrap_err();
+                assert_eq!(err.code(), tonic::Code::Internal, "{}", device);
+            }
+            client
+                .clear(tonic::Request::new(ZpoolClear { device: None }))
+                .await
+                .unwrap();
+        }
+
+        #[tokio::test]
+        async fn test_destroy() {
+            let mut client = get_zfs_client(make_mock_server(None).await.unwrap())
+                .await
+                .unwrap();
+
+            for name in ["app", "app/data", "other"] {
+                client
+                    .create_dataset(tonic::Request::new(ZfsDataset {
+                        name: name.to_string(),
+                        ..Default::default()
+                    }))
+                    .await
+                    .unwrap();
+            }
+
+            client
+                .create_snapshot(tonic::Request::new(ZfsSnapshot {
+                    dataset: "app".to_string(),
+                    name: "snap".to_string(),
+                    recursive: true,
+                }))
+                .await
+                .unwrap();
+            client
+                .clone_snapshot(tonic::Request::new(ZfsClone {
+                    dataset: "app".to_string(),
+                    snapshot: "snap".to_string(),
+                    target: "other/copy".to_string(),
+                }))
+                .await
+                .unwrap();
+
+            // children, then clones, have to be asked for
+            for recursive in [false, true] {
+                assert!(client
+                    .destroy(tonic::Request::new(ZfsDestroy {
+                        name: "app".to_string(),
+                        recursive,
+                        dry_run: true,
+                        ..Default::default()
+                    }))
+                    .await
+                    .is_err());
+            }
+
+            let report = client
+                .destroy(tonic::Request::new(ZfsDestroy {
+                    name: "app".to_string(),
+                    recursive_clones: true,
+                    dry_run: true,
+                    ..Default::default()
+                }))
+                .await
+                .unwrap()
+                .into_inner();
+            let mut entries = report.entries.clone();
+            entries.sort();
+            assert_eq!(
+                entries,
+                vec!["app", "app/data", "app/data@snap", "app@snap", "other/copy"]
+            );
+            assert!(report.reclaimed > 0);
+
+            // a dry run leaves everything where it was
+            let list = client
+                .list(tonic::Request::new(ZfsListFilter::default()))
+                .await
+                .unwrap()
+                .into_inner();
+            assert_eq!(list.entries.len(), 4);
+
+            let report = client
+                .destroy(tonic::Request::new(ZfsDestroy {
+                    name: "app".to_string(),
+                    recursive_clones: true,
+                    ..Default::default()
+                }))
+                .await
+                .unwrap()
+                .into_inner();
+            assert!(report.entries.is_empty());
+
+            let list = client
+                .list(tonic::Request::new(ZfsListFilter::default()))
+                .await
+                .unwrap()
+                .into_inner();
+            assert_eq!(
+                list.entries
+                    .iter()
+                    .map(|x| x.name.as_str())
+                    .collect::<Vec<_>>(),
+                vec!["other"]
+            );
+
+            client
+                .destroy(tonic::Request::new(ZfsDestroy {
+                    name: "other".to_string(),
+                    force: true,
+                    ..Default::default()
+                }))
+                .await
+                .unwrap();
+            let list = client
+                .list(tonic::Request::new(ZfsListFilter::default()))
+                .await
+                .unwrap()
+                .into_inner();
+            assert!(list.entries.is_empty());
+        }
+
+        #[tokio::test]
+        async fn test_space_usage() {
+            let mut client = get_zfs_client(make_mock_server(None).await.unwrap())
+                .await
+                .unwrap();
+
+            for name in ["app", "app/data"] {
+                client
+                    .create_dataset(tonic::Request::new(ZfsDataset {
+                        name: name.to_string(),
+                        ..Default::default()
+                    }))
+                    .await
+                    .unwrap();
+            }
+            client
+                .create_volume(tonic::Request::new(ZfsVolume {
+                    name: "disk".to_string(),
+                    size: 100 * 1024 * 1024,
+                    ..Default::default()
+                }))
+                .await
+                .unwrap();
+
+            let usage = client
+                .space_usage(tonic::Request::new(ZfsListFilter::default()))
+                .await
+                .unwrap()
+                .into_inner();
+            assert_eq!(
+                usage
+                    .entries
+                    .iter()
+                    .map(|x| x.name.as_str())
+                    .collect::<Vec<_>>(),
+                vec!["app", "app/data", "disk"]
+            );
+
+            // the breakdown always adds up to used
+            let pool = usage.pool.unwrap();
+            for entry in usage.entries.iter().chain([&pool]) {
+                assert_eq!(
+                    entry.used,
+                    entry.used_by_snapshots
+                        + entry.used_by_dataset
+                        + entry.used_by_children
+                        + entry.used_by_refreservation,
+                    "{}",
+                    entry.name
+                );
+                assert_eq!(entry.compressratio, 1.0);
+            }
 
-    type UnitLogStream = Pin<Box<dyn Stream<Item = Result<GrpcLogMessage>> + Send>>;
+            assert_eq!(pool.name, DEFAULT_CONFIG.zfs.pool);
+            assert!(pool.used > usage.entries[0].used + usage.entries[2].used);
 
-    async fn set_unit(&self, _filter: Request<GrpcUnitSettings>) -> Result<Response<()>> {
-        Ok(Response::new(()))
-    }
+            let app = &usage.entries[0];
+            assert_eq!(app.kind(), ZfsType::Dataset);
+            assert_eq!(app.used_by_children, usage.entries[1].used);
 
-    // FIXME: this really is only a streaming method because of memory usage concnerns. Maybe
-    // another way would be better
-    async fn unit_log(
-        &self,
-        params: Request<GrpcLogParams>,
-    ) -> Result<Response<Self::UnitLogStream>> {
-        let params = params.into_inner();
-        let (tx, rx) = tokio::sync::mpsc::channel(params.count as usize);
-        let output_stream = ReceiverStream::new(rx);
-        let systemd = crate::systemd::Systemd::new_system()
-            .await
-            .map_err(|e| tonic::Status::new(tonic::Code::Internal, e.to_string()))?;
+            let disk = &usage.entries[2];
+            assert_eq!(disk.kind(), ZfsType::Volume);
+            assert!(disk.used_by_refreservation > 0);
 
-        let p2 = params.clone();
-        tokio::spawn(async move {
-            let params = p2;
-            let mut rcv = systemd
-                .log(&params.name, params.count as usize, None, None)
+            let usage = client
+                .space_usage(tonic::Request::new(ZfsListFilter {
+                    filter: Some("app".to_string()),
+                    ..Default::default()
+                }))
                 .await
-                .unwrap();
-            while let Some(items) = rcv.recv().await {
-                let mut time: Option<std::time::SystemTime> = None;
-                let mut msg: Option<String> = None;
-                let mut pid: Option<u64> = None;
-                let mut cursor: Option<String> = None;
+                .unwrap()
+                .into_inner();
+            assert_eq!(usage.entries.len(), 2);
+        }
 
-                for (key, value) in items {
-                    match key.as_str() {
-                        "_SOURCE_REALTIME_TIMESTAMP" => {
-                            time = Some(
-                                std::time::SystemTime::UNIX_EPOCH
-                                    + std::time::Duration::from_secs(value.parse::<u64>().unwrap()),
-                            )
-                        }
-                        "MESSAGE" => msg = Some(value),
-                        "_PID" => pid = Some(value.parse().unwrap()),
-                        "CURSOR" => cursor = Some(value),
-                        _ => {}
-                    }
+        #[tokio::test]
+        async fn test_snapshot_policies() {
+            let mut config = DEFAULT_CONFIG.clone();
+            config.zfs.snapshot_policies = vec![SnapshotPolicy {
+                dataset: "home".to_string(),
+                recursive: true,
+                hourly: 1,
+                daily: 2,
+                ..Default::default()
+            }];
+            let mut client = get_zfs_client(make_mock_server(Some(config)).await.unwrap())
+                .await
+                .unwrap();
 
-                    if time.is_some() && msg.is_some() && pid.is_some() {
-                        tx.send(Ok(GrpcLogMessage {
-                            service_name: params.name.clone(),
-                            msg: msg.clone().unwrap(),
-                            pid: pid.unwrap(),
-                            time: time.map(Into::into),
-                            cursor: cursor.unwrap(),
-                        }))
-                        .await
-                        .unwrap();
-                        time = None;
-                        msg = None;
-                        pid = None;
-                        cursor = None;
-                    }
-                }
+            for name in ["home", "home/user"] {
+                client
+                    .create_dataset(tonic::Request::new(ZfsDataset {
+                        name: name.to_string(),
+                        ..Default::default()
+                    }))
+                    .await
+                    .unwrap();
             }
-        });
 
-        Ok(Response::new(Box::pin(output_stream) as Self::UnitLogStream))
-    }
-}
+            let policies = client
+                .list_snapshot_policies(tonic::Request::new(()))
+                .await
+                .unwrap()
+                .into_inner()
+                .policies;
+            assert_eq!(policies.len(), 1);
+            assert_eq!(policies[0].dataset, "home");
+            assert_eq!(policies[0].daily, 2);
+
+            let trigger = |period: Option<ZfsSnapshotPeriod>| ZfsTriggerSnapshotPolicy {
+                dataset: "home".to_string(),
+                period: period.map(Into::into),
+            };
+
+            // nothing taken yet, so every period the policy keeps is due
+            let first = client
+                .trigger_snapshot_policy(tonic::Request::new(trigger(None)))
+                .await
+                .unwrap()
+                .into_inner();
+            assert_eq!(first.created.len(), 2);
+            assert!(first.created[0].starts_with("home@buckle-hourly-"));
+            assert!(first.created[1].starts_with("home@buckle-daily-"));
+            assert!(first.destroyed.is_empty());
 
-#[tonic::async_trait]
-impl Status for Server {
-    async fn ping(&self, _: Request<()>) -> Result<Response<PingResult>> {
-        Ok(Response::new(PingResult {
-            info: Some(Info::default().into()),
-        }))
-    }
-}
+            let snapshots = client
+                .list_snapshots(tonic::Request::new(ZfsListFilter::default()))
+                .await
+                .unwrap()
+                .into_inner();
+            assert_eq!(snapshots.entries.len(), 4);
 
-#[tonic::async_trait]
-impl Zfs for Server {
-    async fn modify_dataset(&self, info: Request<ZfsModifyDataset>) -> Result<Response<()>> {
-        self.config
-            .zfs
-            .controller()
-            .modify_dataset(info.into_inner().into())
-            .map_err(|e| tonic::Status::new(tonic::Code::Internal, e.to_string()))?;
-        Ok(Response::new(()))
-    }
+            let run = client
+                .trigger_snapshot_policy(tonic::Request::new(trigger(None)))
+                .await
+                .unwrap()
+                .into_inner();
+            assert!(run.created.is_empty());
 
-    async fn modify_volume(&self, info: Request<ZfsModifyVolume>) -> Result<Response<()>> {
-        self.config
-            .zfs
-            .controller()
-            .modify_volume(info.into_inner().into())
-            .map_err(|e| tonic::Status::new(tonic::Code::Internal, e.to_string()))?;
-        Ok(Response::new(()))
-    }
+            // only one hourly is kept, so taking another prunes the first, even within the second
+            let run = client
+                .trigger_snapshot_policy(tonic::Request::new(trigger(Some(
+                    ZfsSnapshotPeriod::Hourly,
+                ))))
+                .await
+                .unwrap()
+                .into_inner();
+            assert_eq!(run.created.len(), 1);
+            assert_eq!(run.destroyed, vec![first.created[0].clone()]);
+
+            // runs of the same policy take their turn
+            let runs: Vec<_> = (0..4)
+                .map(|_| {
+                    let mut client = client.clone();
+                    tokio::spawn(async move {
+                        client
+                            .trigger_snapshot_policy(tonic::Request::new(trigger(Some(
+                                ZfsSnapshotPeriod::Hourly,
+                            ))))
+                            .await
+                    })
+                })
+                .collect();
+            for run in runs {
+                let run = run.await.unwrap().unwrap().into_inner();
+                assert_eq!(run.created.len(), 1);
+                assert_eq!(run.destroyed.len(), 1);
+            }
 
-    async fn list(&self, filter: Request<ZfsListFilter>) -> Result<Response<ZfsList>> {
-        let list = self
-            .config
-            .zfs
-            .controller()
-            .list(filter.get_ref().filter.clone())
-            .map_err(|e| tonic::Status::new(tonic::Code::Internal, e.to_string()))?;
-        return Ok(Response::new(list.into()));
-    }
+            let snapshots = client
+                .list_snapshots(tonic::Request::new(ZfsListFilter::default()))
+                .await
+                .unwrap()
+                .into_inner();
+            // an hourly and a daily for each of the two datasets
+            assert_eq!(snapshots.entries.len(), 4);
 
-    async fn create_dataset(&self, dataset: Request<ZfsDataset>) -> Result<Response<()>> {
-        self.config
-            .zfs
-            .controller()
-            .create_dataset(&dataset.into_inner().into())
-            .map_err(|e| tonic::Status::new(tonic::Code::Internal, e.to_string()))?;
+            let err = client
+                .trigger_snapshot_policy(tonic::Request::new(trigger(Some(
+                    ZfsSnapshotPeriod::Weekly,
+                ))))
+                .await
+                .unwrap_err();
+            assert_eq!(err.code(), tonic::Code::InvalidArgument);
 
-        return Ok(Response::new(()));
-    }
+            let err = client
+                .trigger_snapshot_policy(tonic::Request::new(ZfsTriggerSnapshotPolicy {
+                    dataset: "other".to_string(),
+                    period: None,
+                }))
+                .await
+                .unwrap_err();
+            assert_eq!(err.code(), tonic::Code::NotFound);
+        }
 
-    async fn create_volume(&self, volume: Request<ZfsVolume>) -> Result<Response<()>> {
-        self.config
-            .zfs
-            .controller()
-            .create_volume(&volume.into_inner().into())
-            .map_err(|e| tonic::Status::new(tonic::Code::Internal, e.to_string()))?;
-        return Ok(Response::new(()));
-    }
+        #[tokio::test]
+        async fn test_snapshot_policy_held() {
+            let mut config = DEFAULT_CONFIG.clone();
+            config.zfs.snapshot_policies = vec![SnapshotPolicy {
+                dataset: "home".to_string(),
+                hourly: 1,
+                daily: 1,
+                ..Default::default()
+            }];
+            let mut client = get_zfs_client(make_mock_server(Some(config)).await.unwrap())
+                .await
+                .unwrap();
 
-    async fn destroy(&self, name: Request<ZfsName>) -> Result<Response<()>> {
-        self.config
-            .zfs
-            .controller()
-            .destroy(name.get_ref().name.clone())
-            .map_err(|e| tonic::Status::new(tonic::Code::Internal, e.to_string()))?;
-        return Ok(Response::new(()));
-    }
-}
+            client
+                .create_dataset(tonic::Request::new(ZfsDataset {
+                    name: "home".to_string(),
+                    ..Default::default()
+                }))
+                .await
+                .unwrap();
 
-#[cfg(test)]
-mod tests {
-    mod systemd {
-        use tokio_stream::StreamExt;
+            let trigger = |period: Option<ZfsSnapshotPeriod>| ZfsTriggerSnapshotPolicy {
+                dataset: "home".to_string(),
+                period: period.map(Into::into),
+            };
 
-        use crate::{
-            grpc::{GrpcLogDirection, GrpcLogParams},
-            testutil::{get_systemd_client, make_server},
-        };
+            let first = client
+                .trigger_snapshot_policy(tonic::Request::new(trigger(Some(
+                    ZfsSnapshotPeriod::Hourly,
+                ))))
+                .await
+                .unwrap()
+                .into_inner();
+            assert_eq!(first.created.len(), 1);
 
-        #[tokio::test]
-        async fn test_log() {
-            let mut client = get_systemd_client(make_server(None).await.unwrap())
+            // held for a backup, so the next hourly can't prune it
+            let (_, snapshot) = first.created[0].split_once('@').unwrap();
+            client
+                .hold(tonic::Request::new(ZfsHold {
+                    dataset: "home".to_string(),
+                    snapshot: snapshot.to_string(),
+                    tag: "backup".to_string(),
+                    recursive: false,
+                }))
                 .await
                 .unwrap();
-            let log = client
-                .unit_log(GrpcLogParams {
-                    name: "network.target".into(),
-                    count: 100,
-                    cursor: "".into(),
-                    direction: GrpcLogDirection::Forward.into(),
-                })
+
+            let run = client
+                .trigger_snapshot_policy(tonic::Request::new(trigger(Some(
+                    ZfsSnapshotPeriod::Hourly,
+                ))))
+                .await
+                .unwrap()
+                .into_inner();
+            assert_eq!(run.created.len(), 1);
+            assert!(run.destroyed.is_empty());
+            assert_eq!(run.failed.len(), 1);
+            assert_eq!(run.failed[0].snapshot, first.created[0]);
+            assert!(!run.failed[0].error.is_empty());
+
+            // the stuck hourly doesn't stop the daily from being taken
+            let run = client
+                .trigger_snapshot_policy(tonic::Request::new(trigger(None)))
+                .await
+                .unwrap()
+                .into_inner();
+            assert_eq!(run.created.len(), 1);
+            assert!(run.created[0].starts_with("home@buckle-daily-"));
+            assert_eq!(run.failed.len(), 1);
+
+            let snapshots = client
+                .list_snapshots(tonic::Request::new(ZfsListFilter::default()))
+                .await
+                .unwrap()
+                .into_inner();
+            assert_eq!(snapshots.entries.len(), 3);
+        }
+
+        #[test]
+        fn test_scrub_schedule() {
+            use crate::server::{scrub_if_due, SCRUB_CHECK_INTERVAL};
+            use crate::zfs::{mock::MockBackend, Pool};
+            use std::time::Duration;
+
+            let backend = MockBackend::new("pool");
+            let pool = Pool::with_backend("pool", backend.clone());
+            let day = Duration::from_secs(24 * 60 * 60);
+            let scan = || pool.status().unwrap().scan.unwrap();
+
+            // never scrubbed, so one is due
+            assert!(pool.status().unwrap().scan.is_none());
+            assert_eq!(scrub_if_due(&pool, day), SCRUB_CHECK_INTERVAL);
+            assert_eq!(scan().function, "scrub");
+
+            // just scrubbed; the wait is capped so scrubs started by hand are noticed
+            backend.set_scan("SCRUB", "FINISHED", Duration::from_secs(60));
+            assert_eq!(scrub_if_due(&pool, day), SCRUB_CHECK_INTERVAL);
+            let wait = scrub_if_due(&pool, Duration::from_secs(30 * 60));
+            assert!(wait <= Duration::from_secs(29 * 60) && wait > Duration::from_secs(28 * 60));
+            assert_eq!(scan().state, "finished");
+
+            // an old scrub is due again
+            backend.set_scan("SCRUB", "FINISHED", 2 * day);
+            scrub_if_due(&pool, day);
+            assert!(scan().start_time.unwrap().elapsed().unwrap() < Duration::from_secs(60));
+
+            // nothing is started over a scan that's still going
+            backend.set_scan("SCRUB", "SCANNING", Duration::from_secs(60));
+            assert_eq!(scrub_if_due(&pool, day), SCRUB_CHECK_INTERVAL);
+            assert_eq!(scan().state, "scanning");
+
+            // a resilver doesn't stand in for a scrub
+            backend.set_scan("RESILVER", "FINISHED", Duration::from_secs(60));
+            scrub_if_due(&pool, day);
+            assert_eq!(scan().function, "scrub");
+        }
+
+        #[tokio::test]
+        async fn test_pool_status() {
+            let mut client = get_zfs_client(make_mock_server(None).await.unwrap())
                 .await
                 .unwrap();
 
-            let mut log = log.into_inner();
-            let mut total = 0;
+            let status = client
+                .pool_status(tonic::Request::new(()))
+                .await
+                .unwrap()
+                .into_inner();
+            assert_eq!(status.name, DEFAULT_CONFIG.zfs.pool);
+            assert_eq!(status.health(), ZfsPoolHealth::Online);
+            assert!(status.scan.is_none());
+            assert_ne!(status.size, 0);
 
-            while let Some(item) = log.next().await {
-                let item = item.unwrap();
-                assert!(!item.msg.is_empty());
-                assert!(item.time.is_some());
-                assert_ne!(!item.time.unwrap().seconds, 0);
-                assert_ne!(item.pid, 0);
-                assert!(!item.cursor.is_empty());
-                total += 1;
-            }
+            client.start_scrub(tonic::Request::new(())).await.unwrap();
+            assert!(client.pause_scrub(tonic::Request::new(())).await.is_err());
 
-            assert!(total < 100);
-            assert!(total > 0);
+            let status = client
+                .pool_status(tonic::Request::new(()))
+                .await
+                .unwrap()
+                .into_inner();
+            assert!(status.scan.is_some());
         }
     }
 
@@ -304,13 +2637,132 @@ mod tests {
     mod zfs {
         use crate::{
             grpc::{
-                ZfsDataset, ZfsListFilter, ZfsModifyDataset, ZfsModifyVolume, ZfsName, ZfsType,
-                ZfsVolume,
+                ZfsDataset, ZfsDestroy, ZfsListFilter, ZfsModifyDataset, ZfsModifyVolume,
+                ZfsReceive, ZfsSend, ZfsSnapshot, ZfsType, ZfsVolume,
             },
             testutil::{
                 create_zpool, destroy_zpool, get_zfs_client, make_server, BUCKLE_TEST_ZPOOL_PREFIX,
+                DEFAULT_CONFIG,
             },
         };
+        use tokio_stream::StreamExt;
+
+        #[tokio::test]
+        async fn test_zfs_send_receive() {
+            let _ = destroy_zpool("send", None);
+            let file = create_zpool("send").unwrap();
+            let mut config = DEFAULT_CONFIG.clone();
+            config.zfs.pool = format!("{}-send", BUCKLE_TEST_ZPOOL_PREFIX);
+            let mut client = get_zfs_client(make_server(Some(config)).await.unwrap())
+                .await
+                .unwrap();
+
+            client
+                .create_dataset(tonic::Request::new(ZfsDataset {
+                    name: "dataset".to_string(),
+                    ..Default::default()
+                }))
+                .await
+                .unwrap();
+
+            let mountpoint = format!("/{}-send/dataset", BUCKLE_TEST_ZPOOL_PREFIX);
+            std::fs::write(format!("{}/first", mountpoint), "first").unwrap();
+
+            for name in ["first", "second"] {
+                if name == "second" {
+                    std::fs::write(format!("{}/second", mountpoint), "second").unwrap();
+                }
+
+                client
+                    .create_snapshot(tonic::Request::new(ZfsSnapshot {
+                        dataset: "dataset".to_string(),
+                        name: name.to_string(),
+                        recursive: false,
+                    }))
+                    .await
+                    .unwrap();
+            }
+
+            let mut full = Vec::new();
+            let mut stream = client
+                .send(tonic::Request::new(ZfsSend {
+                    dataset: "dataset".to_string(),
+                    snapshot: "first".to_string(),
+                    from: None,
+                }))
+                .await
+                .unwrap()
+                .into_inner();
+
+            while let Some(chunk) = stream.next().await {
+                full.push(chunk.unwrap().data);
+            }
+            assert!(!full.is_empty());
+
+            let mut incremental = Vec::new();
+            let mut stream = client
+                .send(tonic::Request::new(ZfsSend {
+                    dataset: "dataset".to_string(),
+                    snapshot: "second".to_string(),
+                    from: Some("first".to_string()),
+                }))
+                .await
+                .unwrap()
+                .into_inner();
+
+            while let Some(chunk) = stream.next().await {
+                incremental.push(chunk.unwrap().data);
+            }
+            assert!(!incremental.is_empty());
+
+            for chunks in [full, incremental] {
+                client
+                    .receive(tonic::Request::new(tokio_stream::iter(
+                        chunks.into_iter().map(|data| ZfsReceive {
+                            dataset: "restored".to_string(),
+                            force: false,
+                            data,
+                        }),
+                    )))
+                    .await
+                    .unwrap();
+            }
+
+            let res = client
+                .list(tonic::Request::new(ZfsListFilter {
+                    filter: Some("restored".to_string()),
+                    ..Default::default()
+                }))
+                .await
+                .unwrap()
+                .into_inner()
+                .entries;
+            assert_eq!(res.len(), 1);
+
+            let restored = format!("/{}-send/restored", BUCKLE_TEST_ZPOOL_PREFIX);
+            assert_eq!(
+                std::fs::read_to_string(format!("{}/first", restored)).unwrap(),
+                "first"
+            );
+            assert_eq!(
+                std::fs::read_to_string(format!("{}/second", restored)).unwrap(),
+                "second"
+            );
+
+            // an empty stream has no destination to receive into
+            assert_eq!(
+                client
+                    .receive(tonic::Request::new(tokio_stream::iter(
+                        Vec::<ZfsReceive>::new()
+                    )))
+                    .await
+                    .unwrap_err()
+                    .code(),
+                tonic::Code::InvalidArgument
+            );
+
+            destroy_zpool("send", Some(&file)).unwrap();
+        }
 
         #[tokio::test]
         async fn test_zfs_operations() {
@@ -369,6 +2821,7 @@ mod tests {
                     ZfsVolume {
                         name: "volume".to_string(),
                         size: 100 * 1024 * 1024,
+                        ..Default::default()
                     }
                     .into(),
                 ))
@@ -387,6 +2840,7 @@ mod tests {
             let res = client
                 .list(tonic::Request::new(ZfsListFilter {
                     filter: Some("dataset".to_string()),
+                    ..Default::default()
                 }))
                 .await
                 .unwrap()
@@ -418,6 +2872,7 @@ mod tests {
                     modifications: Some(ZfsDataset {
                         name: "dataset2".into(),
                         quota: Some(5 * 1024 * 1024),
+                        ..Default::default()
                     }),
                 }))
                 .await
@@ -426,6 +2881,7 @@ mod tests {
             let res = client
                 .list(tonic::Request::new(ZfsListFilter {
                     filter: Some("dataset2".to_string()),
+                    ..Default::default()
                 }))
                 .await
                 .unwrap()
@@ -454,6 +2910,7 @@ mod tests {
             let res = client
                 .list(tonic::Request::new(ZfsListFilter {
                     filter: Some("volume".to_string()),
+                    ..Default::default()
                 }))
                 .await
                 .unwrap()
@@ -482,6 +2939,7 @@ mod tests {
                     modifications: Some(ZfsVolume {
                         name: "volume2".into(),
                         size: 5 * 1024 * 1024,
+                        ..Default::default()
                     }),
                 }))
                 .await
@@ -490,6 +2948,7 @@ mod tests {
             let res = client
                 .list(tonic::Request::new(ZfsListFilter {
                     filter: Some("volume2".to_string()),
+                    ..Default::default()
                 }))
                 .await
                 .unwrap()
@@ -518,8 +2977,9 @@ mod tests {
             assert_eq!(item.mountpoint, None);
 
             client
-                .destroy(tonic::Request::new(ZfsName {
+                .destroy(tonic::Request::new(ZfsDestroy {
                     name: "volume2".to_string(),
+                    ..Default::default()
                 }))
                 .await
                 .unwrap();
@@ -527,6 +2987,7 @@ mod tests {
             let res = client
                 .list(tonic::Request::new(ZfsListFilter {
                     filter: Some("volume2".to_string()),
+                    ..Default::default()
                 }))
                 .await
                 .unwrap()
@@ -536,8 +2997,9 @@ mod tests {
             assert_eq!(res.len(), 0);
 
             client
-                .destroy(tonic::Request::new(ZfsName {
+                .destroy(tonic::Request::new(ZfsDestroy {
                     name: "dataset2".to_string(),
+                    ..Default::default()
                 }))
                 .await
                 .unwrap();
@@ -545,6 +3007,7 @@ mod tests {
             let res = client
                 .list(tonic::Request::new(ZfsListFilter {
                     filter: Some("dataset2".to_string()),
+                    ..Default::default()
                 }))
                 .await
                 .unwrap()
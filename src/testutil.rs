@@ -1,6 +1,7 @@
 use crate::config::LogLevel;
 use crate::grpc::{status_client::StatusClient, systemd_client::SystemdClient};
 use crate::server::Server;
+use crate::zfs::{mock::MockBackend, ZfsBackend};
 use anyhow::Result;
 use std::sync::LazyLock;
 use std::time::Duration;
@@ -9,11 +10,15 @@ use tonic::transport::Channel;
 
 pub const BUCKLE_TEST_ZPOOL_PREFIX: &str = "buckle-test";
 
-pub const DEFAULT_CONFIG: LazyLock<crate::config::Config> =
+pub static DEFAULT_CONFIG: LazyLock<crate::config::Config> =
     LazyLock::new(|| crate::config::Config {
         socket: "/tmp/buckled.sock".into(),
         zfs: crate::config::ZFSConfig {
             pool: format!("{}-default", BUCKLE_TEST_ZPOOL_PREFIX),
+            scrub_interval: None,
+            max_commands: 4,
+            command_timeout: None,
+            snapshot_policies: Vec::new(),
         },
         log_level: LogLevel::Error,
     });
@@ -28,14 +33,27 @@ pub fn find_listener() -> Result<std::path::PathBuf> {
 pub async fn make_server(config: Option<crate::config::Config>) -> Result<std::path::PathBuf> {
     let mut config = config.unwrap_or_else(|| DEFAULT_CONFIG.clone());
     config.socket = find_listener()?;
-    let server = Server::new_with_config(Some(config.clone()));
+    spawn_server(Server::new_with_config(Some(config.clone()))).await?;
+    Ok(config.socket)
+}
+
+// Like make_server, but the zfs services run against an in-memory pool named after the
+// configured one, so they can be tested without ZFS or root.
+pub async fn make_mock_server(config: Option<crate::config::Config>) -> Result<std::path::PathBuf> {
+    let mut config = config.unwrap_or_else(|| DEFAULT_CONFIG.clone());
+    config.socket = find_listener()?;
+    let backend = MockBackend::new(&config.zfs.pool);
+    spawn_server(Server::new_with_backend(config.clone(), backend)).await?;
+    Ok(config.socket)
+}
 
+async fn spawn_server<B: ZfsBackend>(server: Server<B>) -> Result<()> {
     tokio::spawn(async move { server.start().unwrap().await.unwrap() });
 
     // wait for server to start
     tokio::time::sleep(Duration::from_millis(100)).await;
 
-    Ok(config.socket)
+    Ok(())
 }
 
 pub async fn get_status_client(socket: std::path::PathBuf) -> Result<StatusClient<Channel>> {
@@ -46,19 +64,22 @@ pub async fn get_systemd_client(socket: std::path::PathBuf) -> Result<SystemdCli
     Ok(SystemdClient::connect(format!("unix://{}", socket.to_str().unwrap())).await?)
 }
 
-#[cfg(feature = "zfs")]
 use crate::grpc::zfs_client::ZfsClient;
-#[cfg(feature = "zfs")]
 pub async fn get_zfs_client(socket: std::path::PathBuf) -> Result<ZfsClient<Channel>> {
     Ok(ZfsClient::connect(format!("unix://{}", socket.to_str().unwrap())).await?)
 }
 
+use crate::grpc::zpool_client::ZpoolClient;
+pub async fn get_zpool_client(socket: std::path::PathBuf) -> Result<ZpoolClient<Channel>> {
+    Ok(ZpoolClient::connect(format!("unix://{}", socket.to_str().unwrap())).await?)
+}
+
 // FIXME these commands should accept Option<&str>, setting the name to "default" when None. This
 // would match the default zpool configuration setup for the server.
 #[cfg(feature = "zfs")]
 use anyhow::anyhow;
 #[cfg(feature = "zfs")]
-pub fn create_zpool(name: &str) -> Result<String> {
+pub fn create_vdev_file() -> Result<std::path::PathBuf> {
     std::fs::create_dir_all("tmp")?;
 
     let (_, path) = tempfile::NamedTempFile::new_in("tmp")?.keep()?;
@@ -73,6 +94,13 @@ pub fn create_zpool(name: &str) -> Result<String> {
         return Err(anyhow!("Could not grow file for zpool"));
     }
 
+    Ok(path)
+}
+
+#[cfg(feature = "zfs")]
+pub fn create_zpool(name: &str) -> Result<String> {
+    let path = create_vdev_file()?;
+
     let name = format!("{}-{}", BUCKLE_TEST_ZPOOL_PREFIX, name);
     if !std::process::Command::new("zpool")
         .args(vec!["create", &name, path.to_str().unwrap()])
@@ -1,22 +1,317 @@
 use crate::grpc::{
-    ZfsDataset, ZfsEntry, ZfsList, ZfsModifyDataset, ZfsModifyVolume, ZfsType, ZfsVolume,
+    ZfsBookmark, ZfsBookmarkEntry, ZfsBookmarkList, ZfsCanMount, ZfsChangeKey, ZfsClone,
+    ZfsDataset, ZfsDestroy, ZfsDestroyed, ZfsDiff, ZfsDiffChange, ZfsDiffEntry, ZfsEntry,
+    ZfsFileType, ZfsGetProperties, ZfsHold, ZfsHoldEntry, ZfsHoldList, ZfsInheritProperty, ZfsList,
+    ZfsListFilter, ZfsListSort, ZfsLoadKey, ZfsModifyDataset, ZfsModifyVolume, ZfsNode,
+    ZfsPoolHealth, ZfsPoolStatus, ZfsProperties, ZfsProperty, ZfsPropertySource, ZfsPruneFailure,
+    ZfsQuota, ZfsQuotaFilter, ZfsQuotaList, ZfsQuotaType, ZfsReceive, ZfsRollback, ZfsScan,
+    ZfsSend, ZfsSetProperties, ZfsShare, ZfsShareEntry, ZfsShareList, ZfsShareProtocol,
+    ZfsSnapshot, ZfsSnapshotEntry, ZfsSnapshotList, ZfsSnapshotPeriod, ZfsSnapshotPolicy,
+    ZfsSnapshotPolicyRun, ZfsSpaceEntry, ZfsSpaceUsage, ZfsTree, ZfsType, ZfsUnshare, ZfsUserSpace,
+    ZfsUserSpaceEntry, ZfsVdev, ZfsVolume, ZpoolAddVdev, ZpoolAttach, ZpoolExport, ZpoolImport,
+    ZpoolImportable, ZpoolImportableList, ZpoolOffline, ZpoolOnline, ZpoolReplace, ZpoolVdevClass,
+    ZpoolVdevLayout,
 };
 use anyhow::{anyhow, Result};
 use fancy_duration::AsFancyDuration;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, str::FromStr};
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{BufRead, Read, Write},
+    str::FromStr,
+    time::SystemTime,
+};
 use tracing::{debug, error, trace};
 
+#[cfg(feature = "libzfs_core")]
+pub(crate) mod lzc;
+#[cfg(any(test, feature = "test"))]
+pub(crate) mod mock;
+
+// what Pool and the server use unless told otherwise
+#[cfg(feature = "libzfs_core")]
+pub type DefaultBackend = lzc::LzcBackend;
+#[cfg(not(feature = "libzfs_core"))]
+pub type DefaultBackend = Controller;
+
+// A request zfs was never asked to carry out because something in it was malformed. The server
+// reports these as the caller's mistake rather than an internal error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidName(pub String);
+
+impl std::fmt::Display for InvalidName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for InvalidName {}
+
+// zfs' limit on a full name, pool included
+const MAX_NAME_LEN: usize = 255;
+
+// A dataset or volume name below the pool, checked before it goes anywhere near a command line.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DatasetName(String);
+
+impl DatasetName {
+    pub fn new(name: &str) -> Result<Self> {
+        let invalid = |reason: &str| {
+            Err(InvalidName(format!("invalid dataset name '{}': {}", name, reason)).into())
+        };
+
+        if name.is_empty() {
+            return invalid("name is empty");
+        }
+
+        if name.len() > MAX_NAME_LEN {
+            return invalid("name is too long");
+        }
+
+        if name.starts_with('/') || name.ends_with('/') {
+            return invalid("leading or trailing '/'");
+        }
+
+        for component in name.split('/') {
+            if let Err(reason) = check_component(component) {
+                return invalid(reason);
+            }
+        }
+
+        Ok(Self(name.to_string()))
+    }
+
+    // what zfs sees is the name below the pool's, which has to fit too
+    pub fn within(pool: &str, name: &str) -> Result<Self> {
+        let name = Self::new(name)?;
+
+        if pool.len() + 1 + name.len() > MAX_NAME_LEN {
+            return Err(
+                InvalidName(format!("invalid dataset name '{}': name is too long", name)).into(),
+            );
+        }
+
+        Ok(name)
+    }
+
+    // snapshots, bookmarks and hold tags are a single component, and can't start with '-' either
+    pub fn check_component(kind: &str, name: &str) -> Result<()> {
+        match check_component(name) {
+            Ok(()) => Ok(()),
+            Err(reason) => {
+                Err(InvalidName(format!("invalid {} name '{}': {}", kind, name, reason)).into())
+            }
+        }
+    }
+}
+
+impl std::ops::Deref for DatasetName {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for DatasetName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for DatasetName {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::new(s)
+    }
+}
+
+fn check_component(name: &str) -> std::result::Result<(), &'static str> {
+    if name.is_empty() {
+        return Err("empty component");
+    }
+
+    if name == "." || name == ".." {
+        return Err("'.' and '..' are not allowed");
+    }
+
+    // would be taken for an option by the zfs command line
+    if name.starts_with('-') {
+        return Err("components can't start with '-'");
+    }
+
+    // what zfs allows, which rules out '@', '#' and '%' along with everything else
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "_-.: ".contains(c))
+    {
+        return Err("only letters, digits, spaces and '_-.:' are allowed");
+    }
+
+    if name.len() > MAX_NAME_LEN {
+        return Err("name is too long");
+    }
+
+    Ok(())
+}
+
+// zpool's naming rules; requiring a leading letter also keeps getopt from taking the name for an
+// option, as it reorders arguments and would happily read "-a" as "every pool".
+fn check_pool_name(name: &str) -> Result<()> {
+    let invalid =
+        |reason: &str| Err(InvalidName(format!("invalid pool name '{}': {}", name, reason)).into());
+
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return invalid("must start with a letter");
+    }
+
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "_-.: ".contains(c))
+    {
+        return invalid("only letters, digits, spaces and '_-.:' are allowed");
+    }
+
+    if name.len() > MAX_NAME_LEN {
+        return invalid("name is too long");
+    }
+
+    if ["mirror", "raidz", "draid", "spare"]
+        .iter()
+        .any(|x| name.starts_with(x))
+        || name == "log"
+    {
+        return invalid("name is reserved");
+    }
+
+    // reserved so pools can't be mistaken for solaris style disk names
+    if name.len() >= 2 && name.starts_with('c') && name.as_bytes()[1].is_ascii_digit() {
+        return invalid("name is reserved");
+    }
+
+    Ok(())
+}
+
+// devices reach zpool as positional arguments, so only take what can't be read as an option: an
+// absolute path, or the numeric guid zpool status reports for a missing device
+fn check_device(device: &str) -> Result<()> {
+    if device.starts_with('/') || (!device.is_empty() && device.bytes().all(|b| b.is_ascii_digit()))
+    {
+        return Ok(());
+    }
+
+    Err(InvalidName(format!(
+        "invalid device '{}': must be an absolute path or a vdev guid",
+        device
+    ))
+    .into())
+}
+
+// property names go to zfs as arguments of their own, or ahead of the '=' in key=value
+fn check_property_name(name: &str) -> Result<()> {
+    if name.is_empty()
+        || name.starts_with('-')
+        || name.contains(|c: char| c == '=' || c == ',' || c.is_whitespace())
+    {
+        return Err(InvalidName(format!("invalid property name '{}'", name)).into());
+    }
+
+    Ok(())
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum ZFSKind {
     Dataset,
     Volume,
+    Snapshot,
+}
+
+impl ZFSKind {
+    // the name zfs list -t takes
+    fn zfs_type(&self) -> &'static str {
+        match self {
+            Self::Dataset => "filesystem",
+            Self::Volume => "volume",
+            Self::Snapshot => "snapshot",
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum CanMount {
+    On,
+    Off,
+    NoAuto,
+}
+
+impl std::fmt::Display for CanMount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::On => "on",
+            Self::Off => "off",
+            Self::NoAuto => "noauto",
+        })
+    }
+}
+
+impl FromStr for CanMount {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "on" => Ok(Self::On),
+            "off" => Ok(Self::Off),
+            "noauto" => Ok(Self::NoAuto),
+            _ => Err(anyhow!("invalid canmount '{}'", s)),
+        }
+    }
+}
+
+/// Key material for an encrypted dataset. It is only ever written to the stdin of zfs, and is
+/// kept out of logs and serialized output.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Key(Vec<u8>);
+
+impl Key {
+    pub fn new(key: impl Into<Vec<u8>>) -> Self {
+        Self(key.into())
+    }
+}
+
+impl std::fmt::Debug for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Key(<redacted>)")
+    }
+}
+
+impl std::ops::Deref for Key {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Default)]
 pub struct Dataset {
     pub name: String,
     pub quota: Option<u64>,
+    pub compression: Option<String>,
+    pub recordsize: Option<u64>,
+    pub reservation: Option<u64>,
+    pub refreservation: Option<u64>,
+    pub refquota: Option<u64>,
+    pub atime: Option<bool>,
+    pub mountpoint: Option<String>,
+    pub canmount: Option<CanMount>,
+    // any other properties, passed through to zfs as-is
+    pub properties: BTreeMap<String, String>,
+    pub encryption: Option<String>,
+    pub keyformat: Option<String>,
+    pub keylocation: Option<String>,
+    #[serde(skip)]
+    pub key: Option<Key>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -25,10 +320,20 @@ pub struct ModifyDataset {
     pub modifications: Dataset,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Default)]
 pub struct Volume {
     pub name: String,
     pub size: u64,
+    // sparse volumes are created without a reservation
+    pub sparse: bool,
+    pub volblocksize: Option<u64>,
+    pub volmode: Option<String>,
+    pub compression: Option<String>,
+    pub encryption: Option<String>,
+    pub keyformat: Option<String>,
+    pub keylocation: Option<String>,
+    #[serde(skip)]
+    pub key: Option<Key>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -37,751 +342,7194 @@ pub struct ModifyVolume {
     pub modifications: Volume,
 }
 
-#[derive(Debug, Clone)]
-pub struct Pool {
-    name: String,
-    controller: Controller,
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub dataset: String,
+    pub name: String,
+    pub recursive: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ZFSStat {
-    pub kind: ZFSKind,
-    pub name: String,
-    pub full_name: String,
-    pub size: u64,
-    pub used: u64,
-    pub avail: u64,
-    pub refer: u64,
-    pub mountpoint: Option<String>,
-    // FIXME collect options (like quotas)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SnapshotPeriod {
+    Hourly,
+    Daily,
+    Weekly,
+    Monthly,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ZFSOutputInfo {
-    command: String,
-    vers_major: u64,
-    vers_minor: u64,
+impl SnapshotPeriod {
+    pub const ALL: [Self; 4] = [Self::Hourly, Self::Daily, Self::Weekly, Self::Monthly];
+
+    pub fn interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(match self {
+            Self::Hourly => 60 * 60,
+            Self::Daily => 24 * 60 * 60,
+            Self::Weekly => 7 * 24 * 60 * 60,
+            Self::Monthly => 30 * 24 * 60 * 60,
+        })
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Hourly => "hourly",
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+            Self::Monthly => "monthly",
+        }
+    }
+
+    // policy snapshots are this followed by when they were taken, so each period prunes only
+    // its own
+    fn prefix(&self) -> String {
+        format!("buckle-{}-", self.label())
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ZFSGet<T> {
-    output_version: ZFSOutputInfo,
-    datasets: HashMap<String, ZFSGetItem<T>>,
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct SnapshotPolicy {
+    pub dataset: String,
+    #[serde(default)]
+    pub recursive: bool,
+    // how many of each to keep; periods left at 0 are not taken
+    #[serde(default)]
+    pub hourly: u32,
+    #[serde(default)]
+    pub daily: u32,
+    #[serde(default)]
+    pub weekly: u32,
+    #[serde(default)]
+    pub monthly: u32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ZFSGetItem<T> {
-    name: String,
-    #[serde(rename = "type")]
-    typ: String,
-    pool: String,
-    createtxg: u64,
-    properties: HashMap<String, ZFSValue<T>>,
+impl SnapshotPolicy {
+    pub fn keep(&self, period: SnapshotPeriod) -> u32 {
+        match period {
+            SnapshotPeriod::Hourly => self.hourly,
+            SnapshotPeriod::Daily => self.daily,
+            SnapshotPeriod::Weekly => self.weekly,
+            SnapshotPeriod::Monthly => self.monthly,
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ZFSList {
-    output_version: ZFSOutputInfo,
-    datasets: HashMap<String, ZFSListItem>,
+// full snapshot names, dataset@snapshot
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct PolicyRun {
+    pub created: Vec<String>,
+    pub destroyed: Vec<String>,
+    // snapshots due to be pruned that are still there, held ones for instance
+    pub failed: Vec<PruneFailure>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ZFSListItem {
-    name: String,
-    #[serde(rename = "type")]
-    typ: String,
-    pool: String,
-    createtxg: u64,
-    properties: ZFSListItemProperties,
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct PruneFailure {
+    pub snapshot: String,
+    pub error: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ZFSListItemProperties {
-    used: ZFSValue<u64>,
-    available: ZFSValue<u64>,
-    referenced: ZFSValue<u64>,
-    mountpoint: ZFSValue<String>,
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Hold {
+    pub dataset: String,
+    pub snapshot: String,
+    pub tag: String,
+    // also the snapshot of the same name on every descendant
+    pub recursive: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ZFSValue<T> {
-    value: T,
-    source: ZFSSource,
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct HoldStat {
+    pub dataset: String,
+    pub snapshot: String,
+    pub tag: String,
+    pub created: SystemTime,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ZFSSource {
-    #[serde(rename = "type")]
-    typ: String,
-    data: String,
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub dataset: String,
+    pub name: String,
+    // the snapshot to bookmark; only used when creating one
+    pub snapshot: String,
 }
 
-impl From<ModifyVolume> for ZfsModifyVolume {
-    fn from(value: ModifyVolume) -> Self {
-        Self {
-            name: value.name,
-            modifications: Some(value.modifications.into()),
-        }
-    }
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct BookmarkStat {
+    pub dataset: String,
+    pub name: String,
+    pub full_name: String,
+    pub created: SystemTime,
 }
 
-impl From<ZfsModifyVolume> for ModifyVolume {
-    fn from(value: ZfsModifyVolume) -> Self {
-        Self {
-            name: value.name,
-            modifications: value.modifications.unwrap_or_default().into(),
-        }
-    }
+// where used goes; the usedby* properties add up to it
+#[derive(Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct SpaceStat {
+    // below the pool, or the pool itself for its totals
+    pub name: String,
+    pub kind: ZFSKind,
+    pub used: u64,
+    pub avail: u64,
+    pub used_by_snapshots: u64,
+    pub used_by_dataset: u64,
+    pub used_by_children: u64,
+    pub used_by_refreservation: u64,
+    pub logical_used: u64,
+    pub compressratio: f64,
 }
 
-impl From<ModifyDataset> for ZfsModifyDataset {
-    fn from(value: ModifyDataset) -> Self {
-        Self {
-            name: value.name,
-            modifications: Some(value.modifications.into()),
-        }
-    }
+#[derive(Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub struct SpaceUsage {
+    pub pool: SpaceStat,
+    pub entries: Vec<SpaceStat>,
 }
 
-impl From<ZfsModifyDataset> for ModifyDataset {
-    fn from(value: ZfsModifyDataset) -> Self {
-        Self {
-            name: value.name,
-            modifications: value.modifications.unwrap_or_default().into(),
-        }
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+pub enum ShareProtocol {
+    #[default]
+    Nfs,
+    Smb,
 }
 
-impl From<Dataset> for ZfsDataset {
-    fn from(value: Dataset) -> Self {
-        Self {
-            name: value.name,
-            quota: value.quota,
+impl ShareProtocol {
+    pub const ALL: [Self; 2] = [Self::Nfs, Self::Smb];
+
+    pub fn property(&self) -> &'static str {
+        match self {
+            Self::Nfs => "sharenfs",
+            Self::Smb => "sharesmb",
         }
     }
 }
 
-impl From<ZfsDataset> for Dataset {
-    fn from(value: ZfsDataset) -> Self {
-        Self {
-            name: value.name,
-            quota: value.quota,
-        }
-    }
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Share {
+    pub dataset: String,
+    pub protocol: ShareProtocol,
+    // the rest only apply to nfs; everyone may mount it when networks is empty
+    pub networks: Vec<String>,
+    pub read_only: bool,
+    pub root_squash: bool,
 }
 
-impl From<Volume> for ZfsVolume {
-    fn from(value: Volume) -> Self {
-        Self {
-            name: value.name,
-            size: value.size,
+impl Share {
+    // the sharenfs or sharesmb value, in the linux exports syntax zfs passes along
+    fn property_value(&self) -> Result<String> {
+        if self.protocol == ShareProtocol::Smb {
+            if !self.networks.is_empty() || self.read_only || !self.root_squash {
+                return Err(anyhow!(
+                    "networks, read only and root squash only apply to nfs shares"
+                ));
+            }
+
+            return Ok("on".to_string());
         }
-    }
-}
 
-impl From<ZfsVolume> for Volume {
-    fn from(value: ZfsVolume) -> Self {
-        Self {
-            name: value.name,
-            size: value.size,
+        let access = if self.read_only { "ro" } else { "rw" };
+        let mut options = Vec::new();
+
+        if self.networks.is_empty() {
+            options.push(access.to_string());
+        } else {
+            let mut hosts = Vec::new();
+            for network in &self.networks {
+                if network.is_empty()
+                    || network.contains(|c: char| c.is_whitespace() || ",:=".contains(c))
+                {
+                    return Err(anyhow!("invalid share network '{}'", network));
+                }
+
+                // zfs wants networks marked as such, plain hosts are left alone
+                if network.contains('/') && !network.starts_with('@') {
+                    hosts.push(format!("@{}", network));
+                } else {
+                    hosts.push(network.clone());
+                }
+            }
+
+            options.push(format!("{}={}", access, hosts.join(":")));
         }
+
+        if !self.root_squash {
+            options.push("no_root_squash".to_string());
+        }
+
+        Ok(options.join(","))
     }
-}
 
-impl From<ZfsList> for Vec<ZFSStat> {
-    fn from(value: ZfsList) -> Self {
-        let mut list = Self::default();
-        for item in value.entries {
-            list.push(item.into())
+    // anything not understood is left to the raw options in ShareStat
+    fn parse(dataset: &str, protocol: ShareProtocol, value: &str) -> Self {
+        let mut share = Self {
+            dataset: dataset.to_string(),
+            protocol,
+            networks: Vec::new(),
+            read_only: false,
+            root_squash: true,
+        };
+
+        if protocol == ShareProtocol::Smb {
+            return share;
         }
-        list
-    }
-}
 
-impl From<Vec<ZFSStat>> for ZfsList {
-    fn from(value: Vec<ZFSStat>) -> Self {
-        let mut list = Self::default();
-        for item in value {
-            list.entries.push(item.into())
+        for option in value.split(',') {
+            let (name, hosts) = match option.split_once('=') {
+                Some((name, hosts)) => (name, Some(hosts)),
+                None => (option, None),
+            };
+
+            match name {
+                "ro" | "rw" => {
+                    share.read_only = name == "ro";
+                    if let Some(hosts) = hosts {
+                        share.networks = hosts
+                            .split(':')
+                            .map(|x| match x.strip_prefix('@') {
+                                Some(network) if network.contains('/') => network.to_string(),
+                                _ => x.to_string(),
+                            })
+                            .collect();
+                    }
+                }
+                "no_root_squash" => share.root_squash = false,
+                _ => {}
+            }
         }
-        list
+
+        share
     }
 }
 
-impl From<ZfsEntry> for ZFSStat {
-    fn from(value: ZfsEntry) -> Self {
-        Self {
-            kind: match value.kind() {
-                ZfsType::Volume => ZFSKind::Volume,
-                ZfsType::Dataset => ZFSKind::Dataset,
-            },
-            name: value.name,
-            full_name: value.full_name,
-            size: value.size,
-            used: value.used,
-            avail: value.avail,
-            refer: value.refer,
-            mountpoint: value.mountpoint,
-        }
-    }
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Unshare {
+    pub dataset: String,
+    pub protocol: ShareProtocol,
 }
 
-impl From<ZFSStat> for ZfsEntry {
-    fn from(value: ZFSStat) -> Self {
-        Self {
-            kind: match value.kind {
-                ZFSKind::Volume => ZfsType::Volume,
-                ZFSKind::Dataset => ZfsType::Dataset,
-            }
-            .into(),
-            name: value.name,
-            full_name: value.full_name,
-            size: value.size,
-            used: value.used,
-            avail: value.avail,
-            refer: value.refer,
-            mountpoint: value.mountpoint,
-        }
-    }
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ShareStat {
+    pub share: Share,
+    // the property as zfs has it, which may be set on an ancestor
+    pub options: String,
 }
 
-impl Pool {
-    pub fn new(name: &str) -> Self {
-        Self {
-            name: name.to_string(),
-            controller: Controller,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+pub enum QuotaKind {
+    #[default]
+    User,
+    Group,
+    Project,
+}
+
+impl QuotaKind {
+    // the property prefix, as in userquota@alice
+    pub fn property(&self) -> &'static str {
+        match self {
+            Self::User => "userquota",
+            Self::Group => "groupquota",
+            Self::Project => "projectquota",
         }
     }
+}
 
-    pub fn create_dataset(&self, info: &Dataset) -> Result<()> {
-        let mut options: Option<CommandOptions> = None;
-
-        if let Some(quota) = &info.quota {
-            let mut tmp = CommandOptions::default();
-            tmp.insert("quota".to_string(), format!("{}", quota));
-            options = Some(tmp);
-        }
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Quota {
+    pub dataset: String,
+    pub kind: QuotaKind,
+    // user or group name or number, or project number
+    pub id: String,
+    // removes the quota when None
+    pub quota: Option<u64>,
+}
 
-        if let Err(e) = self
-            .controller
-            .create_dataset(&self.name, &info.name, options)
-        {
-            error!("Creating dataset: {}", e.to_string());
-            return Err(e);
-        }
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct QuotaFilter {
+    pub dataset: String,
+    pub kind: QuotaKind,
+}
 
-        self.controller.mount(&self.name)?;
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct UserSpaceStat {
+    pub kind: QuotaKind,
+    // numeric uid, gid or project
+    pub id: String,
+    pub used: u64,
+    pub quota: Option<u64>,
+    pub objused: u64,
+    pub objquota: Option<u64>,
+}
 
-        Ok(())
-    }
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+pub struct Destroy {
+    pub name: String,
+    // children and snapshots, zfs destroy -r
+    pub recursive: bool,
+    // and clones of those snapshots, zfs destroy -R
+    pub clones: bool,
+    pub dry_run: bool,
+    // unmount busy filesystems, zfs destroy -f
+    pub force: bool,
+}
 
-    pub fn create_volume(&self, info: &Volume) -> Result<()> {
-        if let Err(e) = self
-            .controller
-            .create_volume(&self.name, &info.name, info.size, None)
-        {
-            error!("Creating volume: {}", e.to_string());
-            return Err(e);
-        }
-        Ok(())
-    }
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct DestroyReport {
+    // what would be destroyed, below the pool; empty unless it was a dry run
+    pub entries: Vec<String>,
+    pub reclaimed: u64,
+}
 
-    pub fn modify_dataset(&self, info: ModifyDataset) -> Result<()> {
-        let mut map = HashMap::default();
-        if let Some(quota) = &info.modifications.quota {
-            map.insert("quota", format!("{}", quota));
-        }
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct CloneSnapshot {
+    pub dataset: String,
+    pub snapshot: String,
+    pub target: String,
+}
 
-        if let Err(e) = self.controller.set(&self.name, &info.name, map) {
-            error!("Setting options on dataset: {}", e.to_string());
-            return Err(e);
-        }
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct SendSnapshot {
+    pub dataset: String,
+    pub snapshot: String,
+    // when set, only the changes since this snapshot are sent
+    pub from: Option<String>,
+}
 
-        if info.modifications.name != "" && info.name != info.modifications.name {
-            self.controller.unmount(&self.name, &info.name)?;
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct DiffSnapshot {
+    pub dataset: String,
+    pub from: String,
+    // a later snapshot; None compares against the live dataset
+    pub to: Option<String>,
+}
 
-            if let Err(e) = self
-                .controller
-                .rename(&self.name, &info.name, &info.modifications.name)
-            {
-                error!("Renaming dataset: {}", e.to_string());
-                return Err(e);
-            }
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum DiffChange {
+    Added,
+    Removed,
+    Modified,
+    Renamed,
+}
 
-            self.controller.mount(&self.name)?;
-        }
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum FileType {
+    File,
+    Directory,
+    Symlink,
+    Pipe,
+    Socket,
+    Door,
+    BlockDevice,
+    CharDevice,
+    EventPort,
+}
 
-        Ok(())
-    }
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct DiffEntry {
+    pub change: DiffChange,
+    pub file_type: FileType,
+    pub path: String,
+    // where a renamed file went
+    pub new_path: Option<String>,
+}
 
-    pub fn modify_volume(&self, info: ModifyVolume) -> Result<()> {
-        let mut map = HashMap::default();
-        if info.modifications.size != 0 {
-            map.insert("volsize", format!("{}", info.modifications.size));
-        }
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ReceiveSnapshot {
+    pub dataset: String,
+    pub force: bool,
+}
 
-        if let Err(e) = self.controller.set(&self.name, &info.name, map) {
-            error!("Setting options on volume: {}", e.to_string());
-            return Err(e);
-        }
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum PropertySource {
+    Local,
+    Inherited,
+    Default,
+    Temporary,
+    Received,
+    None,
+}
 
-        if info.modifications.name != "" && info.name != info.modifications.name {
-            if let Err(e) = self
-                .controller
-                .rename(&self.name, &info.name, &info.modifications.name)
-            {
-                error!("Renaming volume: {}", e.to_string());
-                return Err(e);
-            }
-        }
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Property {
+    pub name: String,
+    pub value: String,
+    pub source: PropertySource,
+    // the dataset the value comes from, without the pool, when inherited
+    pub inherited_from: Option<String>,
+}
 
-        Ok(())
-    }
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct GetProperties {
+    pub name: String,
+    // all properties are fetched when empty
+    pub properties: Vec<String>,
+}
 
-    pub fn destroy(&self, name: String) -> Result<()> {
-        if let Err(e) = self.controller.destroy(&self.name, &name) {
-            error!("Destroying dataset: {}", e.to_string());
-            return Err(e);
-        }
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SetProperties {
+    pub name: String,
+    pub properties: HashMap<String, String>,
+}
 
-        Ok(())
-    }
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct InheritProperty {
+    pub name: String,
+    pub property: String,
+    pub recursive: bool,
+}
 
-    pub fn list(&self, filter: Option<String>) -> Result<Vec<ZFSStat>> {
-        let mut ret = Vec::new();
-        let list = match self.controller.list() {
-            Ok(x) => x,
-            Err(e) => {
-                error!("Listing datasets: {}", e.to_string());
-                return Err(e);
-            }
-        };
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct LoadKey {
+    pub name: String,
+    // when unset, the key is read from the dataset's keylocation
+    #[serde(skip)]
+    pub key: Option<Key>,
+}
 
-        for (name, item) in list.datasets {
-            if let Some(filter) = &filter {
-                if !item.name.starts_with(&format!("{}/{}", self.name, filter)) {
-                    continue;
-                }
-            }
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ChangeKey {
+    pub name: String,
+    #[serde(skip)]
+    pub key: Option<Key>,
+    pub keyformat: Option<String>,
+    pub keylocation: Option<String>,
+}
 
-            if !name.starts_with(&self.name) {
-                continue;
-            }
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Rollback {
+    pub dataset: String,
+    pub snapshot: String,
+    pub destroy_newer: bool,
+}
 
-            if name == self.name {
-                // skip root-level datasets since they correspond to pools
-                continue;
-            }
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum PoolHealth {
+    Online,
+    Degraded,
+    Faulted,
+    Offline,
+    Unavail,
+    Removed,
+    Suspended,
+    // spares report their availability instead of a vdev state
+    SpareAvail,
+    SpareInUse,
+}
 
-            let short_name = name
-                .strip_prefix(&format!("{}/", self.name))
-                .unwrap_or_else(|| &name)
-                .to_owned();
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Vdev {
+    pub name: String,
+    pub kind: String,
+    pub state: PoolHealth,
+    pub read_errors: u64,
+    pub write_errors: u64,
+    pub checksum_errors: u64,
+    // only set for leaf vdevs
+    pub path: Option<String>,
+    pub children: Vec<Vdev>,
+    // only set for leaf vdevs that support TRIM
+    pub trim_state: Option<String>,
+    pub trim_done: u64,
+    pub trim_total: u64,
+}
 
-            ret.push(ZFSStat {
-                // volumes don't have a mountpath, '-' is indicated
-                // FIXME relying on datasets being mounted is a thing we're doing right now, it'll
-                //       probably have to change eventually, but zfs handles all the mounting for
-                //       us at create and destroy time.
-                kind: if item.typ == "VOLUME" {
-                    ZFSKind::Volume
-                } else {
-                    ZFSKind::Dataset
-                },
-                full_name: name.clone(),
-                name: short_name.clone(), // strip the pool
-                used: item.properties.used.value,
-                avail: item.properties.available.value,
-                // this is just easier to use in places
-                size: if item.typ == "VOLUME" {
-                    match self.controller.get(&self.name, &short_name, "volsize") {
-                        Ok(x) => x,
-                        Err(e) => {
-                            error!("Getting volume size for {}: {}", name, e.to_string());
-                            return Err(e);
-                        }
-                    }
-                } else {
-                    let quota = self
-                        .controller
-                        .get(&self.name, &short_name, "quota")
-                        .unwrap_or_default();
-
-                    if quota != 0 {
-                        quota
-                    } else {
-                        self.controller.get(&self.name, &short_name, "available")?
-                    }
-                },
-                refer: item.properties.referenced.value,
-                mountpoint: if item.properties.mountpoint.value == "-" {
-                    None
-                } else {
-                    Some(item.properties.mountpoint.value)
-                },
-            })
-        }
-        Ok(ret)
-    }
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScanStatus {
+    pub function: String,
+    pub state: String,
+    pub start_time: Option<SystemTime>,
+    pub end_time: Option<SystemTime>,
+    pub to_examine: u64,
+    pub examined: u64,
+    pub issued: u64,
+    pub errors: u64,
+    pub progress: f64,
+    pub paused: bool,
 }
 
-#[derive(Debug, Clone, Default)]
-struct CommandOptions(HashMap<String, String>);
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PoolStatus {
+    pub name: String,
+    pub health: PoolHealth,
+    pub status: Option<String>,
+    pub action: Option<String>,
+    pub vdevs: Vec<Vdev>,
+    pub logs: Vec<Vdev>,
+    pub cache: Vec<Vdev>,
+    pub spares: Vec<Vdev>,
+    pub scan: Option<ScanStatus>,
+    pub size: u64,
+    pub allocated: u64,
+    pub free: u64,
+    pub capacity: u64,
+    pub fragmentation: u64,
+    pub dedup_ratio: f64,
+    pub data_errors: u64,
+}
 
-impl CommandOptions {
-    fn to_options(&self) -> Vec<String> {
-        let mut args = Vec::new();
-        for (key, value) in &self.0 {
-            args.push("-o".to_string());
-            args.push(format!("{}={}", key, value));
-        }
-        args
-    }
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum VdevClass {
+    #[default]
+    Data,
+    Log,
+    Cache,
+    Spare,
+    Special,
+    Dedup,
 }
 
-impl std::ops::Deref for CommandOptions {
-    type Target = HashMap<String, String>;
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum VdevLayout {
+    #[default]
+    Stripe,
+    Mirror,
+    Raidz1,
+    Raidz2,
+    Raidz3,
+}
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ImportablePool {
+    pub name: String,
+    pub id: String,
+    pub health: PoolHealth,
+    pub status: Option<String>,
+    pub action: Option<String>,
 }
 
-impl std::ops::DerefMut for CommandOptions {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
-    }
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ImportPool {
+    // the pool name or its numeric id
+    pub name: String,
+    pub new_name: Option<String>,
+    pub dirs: Vec<String>,
+    pub force: bool,
 }
 
-#[derive(Debug, Clone, Default)]
-struct Controller;
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ExportPool {
+    pub name: String,
+    pub force: bool,
+}
 
-impl Controller {
-    fn run(command: &str, args: Vec<String>) -> Result<String> {
-        debug!("Running command: [{}, {}]", command, args.join(", "));
-        let time = std::time::Instant::now();
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct AddVdev {
+    pub class: VdevClass,
+    pub layout: VdevLayout,
+    pub devices: Vec<String>,
+    pub force: bool,
+}
 
-        let out = match std::process::Command::new(command)
-            .args(args.clone())
-            .stdout(std::process::Stdio::piped())
-            .stderr(std::process::Stdio::piped())
-            .output()
-        {
-            Ok(x) => x,
-            Err(e) => {
-                error!(
-                    "Error running command: [{}, {}]: {}",
-                    command,
-                    args.join(", "),
-                    e.to_string()
-                );
-                return Err(e.into());
-            }
-        };
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct AttachDevice {
+    pub device: String,
+    pub new_device: String,
+    pub force: bool,
+}
 
-        trace!(
-            "ZFS command took {}",
-            (std::time::Instant::now() - time).fancy_duration()
-        );
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ReplaceDevice {
+    pub device: String,
+    pub new_device: Option<String>,
+    pub force: bool,
+}
 
-        if out.status.success() {
-            Ok(String::from_utf8(out.stdout.trim_ascii().to_vec())?)
-        } else {
-            Err(anyhow!(
-                "Error: {}",
-                String::from_utf8(out.stderr.trim_ascii().to_vec())?.as_str()
-            ))
-        }
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct OnlineDevice {
+    pub device: String,
+    pub expand: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct OfflineDevice {
+    pub device: String,
+    pub temporary: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Pool<B: ZfsBackend = DefaultBackend> {
+    name: String,
+    backend: B,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZFSStat {
+    pub kind: ZFSKind,
+    pub name: String,
+    pub full_name: String,
+    pub size: u64,
+    pub used: u64,
+    pub avail: u64,
+    pub refer: u64,
+    pub mountpoint: Option<String>,
+    // the snapshot this entry was cloned from, without the pool
+    pub origin: Option<String>,
+    // block device path, only set for volumes
+    pub device: Option<String>,
+    // only set for encrypted entries
+    pub keystatus: Option<String>,
+    pub encryption_root: Option<String>,
+    // the rest are only filled in when asked for; see LIST_FIELDS
+    pub quota: Option<u64>,
+    pub refquota: Option<u64>,
+    pub reservation: Option<u64>,
+    pub refreservation: Option<u64>,
+    pub compression: Option<String>,
+    pub compressratio: Option<f64>,
+    pub creation: Option<SystemTime>,
+    pub used_by_snapshots: Option<u64>,
+    pub logical_used: Option<u64>,
+    // filesystems only
+    pub recordsize: Option<u64>,
+    pub atime: Option<bool>,
+    pub canmount: Option<CanMount>,
+    pub sharenfs: Option<String>,
+    pub sharesmb: Option<String>,
+    // volumes only
+    pub volblocksize: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+pub enum ListSort {
+    #[default]
+    Name,
+    Used,
+    Avail,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+pub struct ListFilter {
+    // name prefix, below the pool
+    pub prefix: Option<String>,
+    // the optional ZFSStat fields to fill in; all of them when empty
+    pub fields: Vec<String>,
+    // filesystems and volumes when empty
+    pub kinds: Vec<ZFSKind>,
+    pub properties: BTreeMap<String, String>,
+    // levels below the pool to descend
+    pub depth: Option<u32>,
+    pub sort: ListSort,
+    pub descending: bool,
+    // everything at once when 0
+    pub page_size: u32,
+    pub page_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZFSNode {
+    pub stat: ZFSStat,
+    // already counted in stat.used
+    pub used_by_children: u64,
+    pub children: Vec<ZFSNode>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListPage {
+    pub entries: Vec<ZFSStat>,
+    // None on the last page
+    pub next_page_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotStat {
+    pub dataset: String,
+    pub name: String,
+    pub full_name: String,
+    pub created: SystemTime,
+    pub used: u64,
+    pub refer: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZFSOutputInfo {
+    command: String,
+    vers_major: u64,
+    vers_minor: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZFSGet<T> {
+    output_version: ZFSOutputInfo,
+    datasets: HashMap<String, ZFSGetItem<T>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZFSGetItem<T> {
+    name: String,
+    #[serde(rename = "type")]
+    typ: String,
+    pool: String,
+    createtxg: u64,
+    properties: HashMap<String, ZFSValue<T>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZFSList {
+    output_version: ZFSOutputInfo,
+    datasets: HashMap<String, ZFSListItem>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZFSListItem {
+    name: String,
+    #[serde(rename = "type")]
+    typ: String,
+    pool: String,
+    createtxg: u64,
+    // whichever properties were asked for; numbers are numbers with --json-int, and properties
+    // that don't apply to the entry are "-"
+    properties: HashMap<String, ZFSValue<serde_json::Value>>,
+}
+
+impl ZFSListItem {
+    fn value(&self, property: &str) -> Option<&serde_json::Value> {
+        self.properties
+            .get(property)
+            .map(|x| &x.value)
+            .filter(|x| x.as_str() != Some("-"))
     }
 
-    fn list(&self) -> Result<ZFSList> {
-        Ok(serde_json::from_str(&Self::run(
-            "zfs",
-            vec![
-                "list".to_string(),
-                "-j".to_string(),
-                "--json-int".to_string(),
-            ],
-        )?)?)
+    fn number(&self, property: &str) -> Option<u64> {
+        let value = self.value(property)?;
+        value.as_u64().or_else(|| value.as_str()?.parse().ok())
     }
 
-    fn destroy(&self, pool: &str, name: &str) -> Result<()> {
-        Self::run(
-            "zfs",
-            vec![
-                "destroy".to_string(),
-                "-f".to_string(),
-                format!("{}/{}", pool, name),
-            ],
-        )?;
-        Ok(())
+    fn string(&self, property: &str) -> Option<String> {
+        self.value(property).map(|x| match x.as_str() {
+            Some(s) => s.to_string(),
+            None => x.to_string(),
+        })
     }
 
-    fn create_dataset(
-        &self,
-        pool: &str,
-        name: &str,
-        options: Option<CommandOptions>,
-    ) -> Result<()> {
-        let mut args = vec!["create".to_string(), format!("{}/{}", pool, name)];
+    fn ratio(&self, property: &str) -> Option<f64> {
+        serde_json::from_value::<ZFSNumber>(self.value(property)?.clone())
+            .ok()
+            .map(|x| x.as_f64())
+    }
+}
 
-        if let Some(options) = options {
-            args.append(&mut options.to_options())
-        }
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZFSSnapshotList {
+    output_version: ZFSOutputInfo,
+    // zfs omits this when there are no snapshots at all
+    #[serde(default)]
+    datasets: HashMap<String, ZFSSnapshotListItem>,
+}
 
-        Self::run("zfs", args)?;
-        Ok(())
-    }
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZFSSnapshotListItem {
+    name: String,
+    #[serde(rename = "type")]
+    typ: String,
+    pool: String,
+    createtxg: u64,
+    properties: ZFSSnapshotListItemProperties,
+}
 
-    fn rename(&self, pool: &str, orig: &str, new: &str) -> Result<()> {
-        let args = vec![
-            "rename",
-            "-p",
-            &format!("{}/{}", pool, orig),
-            &format!("{}/{}", pool, new),
-        ]
-        .iter()
-        .map(|s| s.to_string())
-        .collect();
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZFSSnapshotListItemProperties {
+    used: ZFSValue<u64>,
+    referenced: ZFSValue<u64>,
+    creation: ZFSValue<u64>,
+}
 
-        Self::run("zfs", args)?;
-        Ok(())
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZFSValue<T> {
+    value: T,
+    source: ZFSSource,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZFSSource {
+    #[serde(rename = "type")]
+    typ: String,
+    data: String,
+}
+
+// zpool's json output is much less consistent than zfs's: depending on the property and the
+// version, numbers show up as integers, floats or strings like "1.00x" and "12%".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ZFSNumber {
+    Int(u64),
+    Float(f64),
+    Str(String),
+}
+
+impl Default for ZFSNumber {
+    fn default() -> Self {
+        Self::Int(0)
     }
+}
 
-    fn set(&self, pool: &str, name: &str, properties: HashMap<&str, String>) -> Result<()> {
-        if properties.is_empty() {
-            return Ok(());
+impl ZFSNumber {
+    fn as_u64(&self) -> u64 {
+        match self {
+            Self::Int(x) => *x,
+            Self::Float(x) => *x as u64,
+            Self::Str(_) => self.as_f64() as u64,
         }
+    }
 
-        let mut args = vec!["set".to_string()];
+    fn as_f64(&self) -> f64 {
+        match self {
+            Self::Int(x) => *x as f64,
+            Self::Float(x) => *x,
+            Self::Str(s) => s.trim_end_matches(['%', 'x']).parse().unwrap_or_default(),
+        }
+    }
 
-        for (key, value) in &properties {
-            args.push(format!("{}={}", key, value));
+    // timestamps are only integers with --json-int; anything else (like "-") means unset
+    fn as_time(&self) -> Option<SystemTime> {
+        match self {
+            Self::Int(0) => None,
+            Self::Int(x) => Some(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(*x)),
+            _ => None,
         }
+    }
+}
 
-        args.push(format!("{}/{}", pool, name));
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZPoolStatus {
+    output_version: ZFSOutputInfo,
+    #[serde(default)]
+    pools: HashMap<String, ZPoolStatusItem>,
+}
 
-        Self::run("zfs", args)?;
-        Ok(())
-    }
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZPoolStatusItem {
+    name: String,
+    state: String,
+    status: Option<String>,
+    action: Option<String>,
+    scan_stats: Option<ZPoolScanStats>,
+    #[serde(default)]
+    vdevs: BTreeMap<String, ZPoolVdev>,
+    #[serde(default)]
+    logs: BTreeMap<String, ZPoolVdev>,
+    #[serde(default)]
+    l2cache: BTreeMap<String, ZPoolVdev>,
+    #[serde(default)]
+    spares: BTreeMap<String, ZPoolVdev>,
+    #[serde(default)]
+    error_count: ZFSNumber,
+}
 
-    fn get<T>(&self, pool: &str, name: &str, property: &str) -> Result<T>
-    where
-        T: for<'de> serde::Deserialize<'de> + FromStr + Send + Sync + Clone,
-        T::Err: ToString,
-    {
-        let args = vec![
-            "get".to_string(),
-            "-j".to_string(),
-            "--json-int".to_string(),
-            property.to_string(),
-            format!("{}/{}", pool, name),
-        ];
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZPoolVdev {
+    name: String,
+    #[serde(default)]
+    vdev_type: String,
+    #[serde(default)]
+    state: String,
+    path: Option<String>,
+    #[serde(default)]
+    read_errors: ZFSNumber,
+    #[serde(default)]
+    write_errors: ZFSNumber,
+    #[serde(default)]
+    checksum_errors: ZFSNumber,
+    #[serde(default)]
+    vdevs: BTreeMap<String, ZPoolVdev>,
+    // only reported with -t, and not at all when the device can't TRIM
+    trim_state: Option<String>,
+    #[serde(default)]
+    trim_bytes_done: ZFSNumber,
+    #[serde(default)]
+    trim_bytes_est: ZFSNumber,
+}
 
-        let out: ZFSGet<T> = serde_json::from_str(&Self::run("zfs", args)?)?;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZPoolScanStats {
+    function: String,
+    state: String,
+    #[serde(default)]
+    start_time: ZFSNumber,
+    #[serde(default)]
+    end_time: ZFSNumber,
+    #[serde(default)]
+    to_examine: ZFSNumber,
+    #[serde(default)]
+    examined: ZFSNumber,
+    #[serde(default)]
+    issued: ZFSNumber,
+    #[serde(default)]
+    errors: ZFSNumber,
+    // "-" unless the scrub is paused
+    scrub_pause: Option<String>,
+}
 
-        Ok(
-            out.datasets[&format!("{}/{}", pool, name)].properties[property]
-                .value
-                .clone(),
-        )
-    }
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZPoolList {
+    output_version: ZFSOutputInfo,
+    #[serde(default)]
+    pools: HashMap<String, ZPoolListItem>,
+}
 
-    fn mount(&self, pool: &str) -> Result<()> {
-        Self::run(
-            "zfs",
-            vec!["mount", "-R", pool]
-                .iter()
-                .map(|x| x.to_string())
-                .collect::<Vec<String>>(),
-        )?;
-        Ok(())
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZPoolListItem {
+    name: String,
+    properties: ZPoolListItemProperties,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZPoolListItemProperties {
+    size: ZFSValue<ZFSNumber>,
+    allocated: ZFSValue<ZFSNumber>,
+    free: ZFSValue<ZFSNumber>,
+    capacity: ZFSValue<ZFSNumber>,
+    fragmentation: ZFSValue<ZFSNumber>,
+    dedupratio: ZFSValue<ZFSNumber>,
+    health: ZFSValue<String>,
+}
+
+impl From<&str> for PoolHealth {
+    fn from(value: &str) -> Self {
+        match value {
+            "ONLINE" => Self::Online,
+            "DEGRADED" => Self::Degraded,
+            "FAULTED" => Self::Faulted,
+            "OFFLINE" => Self::Offline,
+            "REMOVED" => Self::Removed,
+            "SUSPENDED" => Self::Suspended,
+            "AVAIL" => Self::SpareAvail,
+            "INUSE" => Self::SpareInUse,
+            _ => Self::Unavail,
+        }
     }
+}
 
-    fn unmount(&self, pool: &str, name: &str) -> Result<()> {
-        Self::run(
-            "zfs",
-            vec!["unmount", "-f", &format!("{}/{}", pool, name)]
-                .iter()
-                .map(|x| x.to_string())
-                .collect::<Vec<String>>(),
-        )?;
-        Ok(())
+impl From<&ZPoolVdev> for Vdev {
+    fn from(value: &ZPoolVdev) -> Self {
+        Self {
+            name: value.name.clone(),
+            kind: value.vdev_type.clone(),
+            state: value.state.as_str().into(),
+            read_errors: value.read_errors.as_u64(),
+            write_errors: value.write_errors.as_u64(),
+            checksum_errors: value.checksum_errors.as_u64(),
+            path: value.path.clone(),
+            children: value.vdevs.values().map(Into::into).collect(),
+            trim_state: value
+                .trim_state
+                .as_deref()
+                .filter(|x| *x != "NONE")
+                .map(str::to_lowercase),
+            trim_done: value.trim_bytes_done.as_u64(),
+            trim_total: value.trim_bytes_est.as_u64(),
+        }
     }
+}
 
-    fn create_volume(
-        &self,
-        pool: &str,
-        name: &str,
-        size: u64, // 640k aughta be enough for anybody
-        options: Option<CommandOptions>,
-    ) -> Result<()> {
-        let mut args = vec![
-            "create".to_string(),
-            "-V".to_string(),
-            format!("{}", size),
-            format!("{}/{}", pool, name),
-        ];
+impl From<&ZPoolScanStats> for ScanStatus {
+    fn from(value: &ZPoolScanStats) -> Self {
+        let to_examine = value.to_examine.as_u64();
+        let issued = value.issued.as_u64();
 
-        if let Some(options) = options {
-            args.append(&mut options.to_options())
+        Self {
+            function: value.function.to_lowercase(),
+            state: value.state.to_lowercase(),
+            start_time: value.start_time.as_time(),
+            end_time: value.end_time.as_time(),
+            to_examine,
+            examined: value.examined.as_u64(),
+            issued,
+            errors: value.errors.as_u64(),
+            progress: if to_examine == 0 {
+                0.0
+            } else {
+                (issued as f64 / to_examine as f64 * 100.0).min(100.0)
+            },
+            paused: value.scrub_pause.as_deref().is_some_and(|x| x != "-"),
         }
+    }
+}
 
-        Self::run("zfs", args)?;
-        Ok(())
+impl From<PoolHealth> for ZfsPoolHealth {
+    fn from(value: PoolHealth) -> Self {
+        match value {
+            PoolHealth::Online => Self::Online,
+            PoolHealth::Degraded => Self::Degraded,
+            PoolHealth::Faulted => Self::Faulted,
+            PoolHealth::Offline => Self::Offline,
+            PoolHealth::Unavail => Self::Unavail,
+            PoolHealth::Removed => Self::Removed,
+            PoolHealth::Suspended => Self::Suspended,
+            PoolHealth::SpareAvail => Self::SpareAvail,
+            PoolHealth::SpareInUse => Self::SpareInUse,
+        }
     }
 }
 
-#[cfg(test)]
-#[cfg(feature = "zfs")]
+impl From<ZfsPoolHealth> for PoolHealth {
+    fn from(value: ZfsPoolHealth) -> Self {
+        match value {
+            ZfsPoolHealth::Online => Self::Online,
+            ZfsPoolHealth::Degraded => Self::Degraded,
+            ZfsPoolHealth::Faulted => Self::Faulted,
+            ZfsPoolHealth::Offline => Self::Offline,
+            ZfsPoolHealth::Unavail => Self::Unavail,
+            ZfsPoolHealth::Removed => Self::Removed,
+            ZfsPoolHealth::Suspended => Self::Suspended,
+            ZfsPoolHealth::SpareAvail => Self::SpareAvail,
+            ZfsPoolHealth::SpareInUse => Self::SpareInUse,
+        }
+    }
+}
+
+impl From<Vdev> for ZfsVdev {
+    fn from(value: Vdev) -> Self {
+        Self {
+            name: value.name,
+            kind: value.kind,
+            state: Into::<ZfsPoolHealth>::into(value.state).into(),
+            read_errors: value.read_errors,
+            write_errors: value.write_errors,
+            checksum_errors: value.checksum_errors,
+            path: value.path,
+            children: value.children.into_iter().map(Into::into).collect(),
+            trim_state: value.trim_state,
+            trim_done: value.trim_done,
+            trim_total: value.trim_total,
+        }
+    }
+}
+
+impl From<ZfsVdev> for Vdev {
+    fn from(value: ZfsVdev) -> Self {
+        Self {
+            state: value.state().into(),
+            name: value.name,
+            kind: value.kind,
+            read_errors: value.read_errors,
+            write_errors: value.write_errors,
+            checksum_errors: value.checksum_errors,
+            path: value.path,
+            children: value.children.into_iter().map(Into::into).collect(),
+            trim_state: value.trim_state,
+            trim_done: value.trim_done,
+            trim_total: value.trim_total,
+        }
+    }
+}
+
+impl From<ScanStatus> for ZfsScan {
+    fn from(value: ScanStatus) -> Self {
+        Self {
+            function: value.function,
+            state: value.state,
+            start_time: value.start_time.map(Into::into),
+            end_time: value.end_time.map(Into::into),
+            to_examine: value.to_examine,
+            examined: value.examined,
+            issued: value.issued,
+            errors: value.errors,
+            progress: value.progress,
+            paused: value.paused,
+        }
+    }
+}
+
+impl From<ZfsScan> for ScanStatus {
+    fn from(value: ZfsScan) -> Self {
+        Self {
+            function: value.function,
+            state: value.state,
+            start_time: value
+                .start_time
+                .map(|x| SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(x.seconds as u64)),
+            end_time: value
+                .end_time
+                .map(|x| SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(x.seconds as u64)),
+            to_examine: value.to_examine,
+            examined: value.examined,
+            issued: value.issued,
+            errors: value.errors,
+            progress: value.progress,
+            paused: value.paused,
+        }
+    }
+}
+
+impl From<PoolStatus> for ZfsPoolStatus {
+    fn from(value: PoolStatus) -> Self {
+        Self {
+            name: value.name,
+            health: Into::<ZfsPoolHealth>::into(value.health).into(),
+            status: value.status,
+            action: value.action,
+            vdevs: value.vdevs.into_iter().map(Into::into).collect(),
+            logs: value.logs.into_iter().map(Into::into).collect(),
+            cache: value.cache.into_iter().map(Into::into).collect(),
+            spares: value.spares.into_iter().map(Into::into).collect(),
+            scan: value.scan.map(Into::into),
+            size: value.size,
+            allocated: value.allocated,
+            free: value.free,
+            capacity: value.capacity,
+            fragmentation: value.fragmentation,
+            dedup_ratio: value.dedup_ratio,
+            data_errors: value.data_errors,
+        }
+    }
+}
+
+impl From<ZfsPoolStatus> for PoolStatus {
+    fn from(value: ZfsPoolStatus) -> Self {
+        Self {
+            health: value.health().into(),
+            name: value.name,
+            status: value.status,
+            action: value.action,
+            vdevs: value.vdevs.into_iter().map(Into::into).collect(),
+            logs: value.logs.into_iter().map(Into::into).collect(),
+            cache: value.cache.into_iter().map(Into::into).collect(),
+            spares: value.spares.into_iter().map(Into::into).collect(),
+            scan: value.scan.map(Into::into),
+            size: value.size,
+            allocated: value.allocated,
+            free: value.free,
+            capacity: value.capacity,
+            fragmentation: value.fragmentation,
+            dedup_ratio: value.dedup_ratio,
+            data_errors: value.data_errors,
+        }
+    }
+}
+
+impl From<ModifyVolume> for ZfsModifyVolume {
+    fn from(value: ModifyVolume) -> Self {
+        Self {
+            name: value.name,
+            modifications: Some(value.modifications.into()),
+        }
+    }
+}
+
+impl From<ZfsModifyVolume> for ModifyVolume {
+    fn from(value: ZfsModifyVolume) -> Self {
+        Self {
+            name: value.name,
+            modifications: value.modifications.unwrap_or_default().into(),
+        }
+    }
+}
+
+impl From<ModifyDataset> for ZfsModifyDataset {
+    fn from(value: ModifyDataset) -> Self {
+        Self {
+            name: value.name,
+            modifications: Some(value.modifications.into()),
+        }
+    }
+}
+
+impl From<ZfsModifyDataset> for ModifyDataset {
+    fn from(value: ZfsModifyDataset) -> Self {
+        Self {
+            name: value.name,
+            modifications: value.modifications.unwrap_or_default().into(),
+        }
+    }
+}
+
+impl From<CanMount> for ZfsCanMount {
+    fn from(value: CanMount) -> Self {
+        match value {
+            CanMount::On => Self::On,
+            CanMount::Off => Self::Off,
+            CanMount::NoAuto => Self::NoAuto,
+        }
+    }
+}
+
+impl From<ZfsCanMount> for CanMount {
+    fn from(value: ZfsCanMount) -> Self {
+        match value {
+            ZfsCanMount::On => Self::On,
+            ZfsCanMount::Off => Self::Off,
+            ZfsCanMount::NoAuto => Self::NoAuto,
+        }
+    }
+}
+
+impl From<Dataset> for ZfsDataset {
+    fn from(value: Dataset) -> Self {
+        Self {
+            name: value.name,
+            quota: value.quota,
+            compression: value.compression,
+            recordsize: value.recordsize,
+            reservation: value.reservation,
+            refreservation: value.refreservation,
+            refquota: value.refquota,
+            atime: value.atime,
+            mountpoint: value.mountpoint,
+            canmount: value.canmount.map(|x| Into::<ZfsCanMount>::into(x).into()),
+            properties: value.properties.into_iter().collect(),
+            encryption: value.encryption,
+            keyformat: value.keyformat,
+            keylocation: value.keylocation,
+            key: value.key.map(|k| k.0),
+        }
+    }
+}
+
+impl From<ZfsDataset> for Dataset {
+    fn from(value: ZfsDataset) -> Self {
+        Self {
+            canmount: value
+                .canmount
+                .and_then(|x| ZfsCanMount::try_from(x).ok())
+                .map(Into::into),
+            name: value.name,
+            quota: value.quota,
+            compression: value.compression,
+            recordsize: value.recordsize,
+            reservation: value.reservation,
+            refreservation: value.refreservation,
+            refquota: value.refquota,
+            atime: value.atime,
+            mountpoint: value.mountpoint,
+            properties: value.properties.into_iter().collect(),
+            encryption: value.encryption,
+            keyformat: value.keyformat,
+            keylocation: value.keylocation,
+            key: value.key.map(Key),
+        }
+    }
+}
+
+impl From<Volume> for ZfsVolume {
+    fn from(value: Volume) -> Self {
+        Self {
+            name: value.name,
+            size: value.size,
+            sparse: value.sparse,
+            volblocksize: value.volblocksize,
+            volmode: value.volmode,
+            compression: value.compression,
+            encryption: value.encryption,
+            keyformat: value.keyformat,
+            keylocation: value.keylocation,
+            key: value.key.map(|k| k.0),
+        }
+    }
+}
+
+impl From<ZfsVolume> for Volume {
+    fn from(value: ZfsVolume) -> Self {
+        Self {
+            name: value.name,
+            size: value.size,
+            sparse: value.sparse,
+            volblocksize: value.volblocksize,
+            volmode: value.volmode,
+            compression: value.compression,
+            encryption: value.encryption,
+            keyformat: value.keyformat,
+            keylocation: value.keylocation,
+            key: value.key.map(Key),
+        }
+    }
+}
+
+impl From<LoadKey> for ZfsLoadKey {
+    fn from(value: LoadKey) -> Self {
+        Self {
+            name: value.name,
+            key: value.key.map(|k| k.0),
+        }
+    }
+}
+
+impl From<ZfsLoadKey> for LoadKey {
+    fn from(value: ZfsLoadKey) -> Self {
+        Self {
+            name: value.name,
+            key: value.key.map(Key),
+        }
+    }
+}
+
+impl From<ChangeKey> for ZfsChangeKey {
+    fn from(value: ChangeKey) -> Self {
+        Self {
+            name: value.name,
+            key: value.key.map(|k| k.0),
+            keyformat: value.keyformat,
+            keylocation: value.keylocation,
+        }
+    }
+}
+
+impl From<ZfsChangeKey> for ChangeKey {
+    fn from(value: ZfsChangeKey) -> Self {
+        Self {
+            name: value.name,
+            key: value.key.map(Key),
+            keyformat: value.keyformat,
+            keylocation: value.keylocation,
+        }
+    }
+}
+
+impl From<Snapshot> for ZfsSnapshot {
+    fn from(value: Snapshot) -> Self {
+        Self {
+            dataset: value.dataset,
+            name: value.name,
+            recursive: value.recursive,
+        }
+    }
+}
+
+impl From<ZfsSnapshot> for Snapshot {
+    fn from(value: ZfsSnapshot) -> Self {
+        Self {
+            dataset: value.dataset,
+            name: value.name,
+            recursive: value.recursive,
+        }
+    }
+}
+
+impl From<SnapshotPeriod> for ZfsSnapshotPeriod {
+    fn from(value: SnapshotPeriod) -> Self {
+        match value {
+            SnapshotPeriod::Hourly => Self::Hourly,
+            SnapshotPeriod::Daily => Self::Daily,
+            SnapshotPeriod::Weekly => Self::Weekly,
+            SnapshotPeriod::Monthly => Self::Monthly,
+        }
+    }
+}
+
+impl From<ZfsSnapshotPeriod> for SnapshotPeriod {
+    fn from(value: ZfsSnapshotPeriod) -> Self {
+        match value {
+            ZfsSnapshotPeriod::Hourly => Self::Hourly,
+            ZfsSnapshotPeriod::Daily => Self::Daily,
+            ZfsSnapshotPeriod::Weekly => Self::Weekly,
+            ZfsSnapshotPeriod::Monthly => Self::Monthly,
+        }
+    }
+}
+
+impl From<SnapshotPolicy> for ZfsSnapshotPolicy {
+    fn from(value: SnapshotPolicy) -> Self {
+        Self {
+            dataset: value.dataset,
+            recursive: value.recursive,
+            hourly: value.hourly,
+            daily: value.daily,
+            weekly: value.weekly,
+            monthly: value.monthly,
+        }
+    }
+}
+
+impl From<ZfsSnapshotPolicy> for SnapshotPolicy {
+    fn from(value: ZfsSnapshotPolicy) -> Self {
+        Self {
+            dataset: value.dataset,
+            recursive: value.recursive,
+            hourly: value.hourly,
+            daily: value.daily,
+            weekly: value.weekly,
+            monthly: value.monthly,
+        }
+    }
+}
+
+impl From<PruneFailure> for ZfsPruneFailure {
+    fn from(value: PruneFailure) -> Self {
+        Self {
+            snapshot: value.snapshot,
+            error: value.error,
+        }
+    }
+}
+
+impl From<ZfsPruneFailure> for PruneFailure {
+    fn from(value: ZfsPruneFailure) -> Self {
+        Self {
+            snapshot: value.snapshot,
+            error: value.error,
+        }
+    }
+}
+
+impl From<PolicyRun> for ZfsSnapshotPolicyRun {
+    fn from(value: PolicyRun) -> Self {
+        Self {
+            created: value.created,
+            destroyed: value.destroyed,
+            failed: value.failed.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<ZfsSnapshotPolicyRun> for PolicyRun {
+    fn from(value: ZfsSnapshotPolicyRun) -> Self {
+        Self {
+            created: value.created,
+            destroyed: value.destroyed,
+            failed: value.failed.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<Destroy> for ZfsDestroy {
+    fn from(value: Destroy) -> Self {
+        Self {
+            name: value.name,
+            recursive: value.recursive,
+            recursive_clones: value.clones,
+            dry_run: value.dry_run,
+            force: value.force,
+        }
+    }
+}
+
+impl From<ZfsDestroy> for Destroy {
+    fn from(value: ZfsDestroy) -> Self {
+        Self {
+            name: value.name,
+            recursive: value.recursive,
+            clones: value.recursive_clones,
+            dry_run: value.dry_run,
+            force: value.force,
+        }
+    }
+}
+
+impl From<DestroyReport> for ZfsDestroyed {
+    fn from(value: DestroyReport) -> Self {
+        Self {
+            entries: value.entries,
+            reclaimed: value.reclaimed,
+        }
+    }
+}
+
+impl From<ZfsDestroyed> for DestroyReport {
+    fn from(value: ZfsDestroyed) -> Self {
+        Self {
+            entries: value.entries,
+            reclaimed: value.reclaimed,
+        }
+    }
+}
+
+impl From<CloneSnapshot> for ZfsClone {
+    fn from(value: CloneSnapshot) -> Self {
+        Self {
+            dataset: value.dataset,
+            snapshot: value.snapshot,
+            target: value.target,
+        }
+    }
+}
+
+impl From<ZfsClone> for CloneSnapshot {
+    fn from(value: ZfsClone) -> Self {
+        Self {
+            dataset: value.dataset,
+            snapshot: value.snapshot,
+            target: value.target,
+        }
+    }
+}
+
+impl From<SendSnapshot> for ZfsSend {
+    fn from(value: SendSnapshot) -> Self {
+        Self {
+            dataset: value.dataset,
+            snapshot: value.snapshot,
+            from: value.from,
+        }
+    }
+}
+
+impl From<ZfsSend> for SendSnapshot {
+    fn from(value: ZfsSend) -> Self {
+        Self {
+            dataset: value.dataset,
+            snapshot: value.snapshot,
+            from: value.from,
+        }
+    }
+}
+
+impl From<DiffSnapshot> for ZfsDiff {
+    fn from(value: DiffSnapshot) -> Self {
+        Self {
+            dataset: value.dataset,
+            from: value.from,
+            to: value.to,
+        }
+    }
+}
+
+impl From<ZfsDiff> for DiffSnapshot {
+    fn from(value: ZfsDiff) -> Self {
+        Self {
+            dataset: value.dataset,
+            from: value.from,
+            to: value.to,
+        }
+    }
+}
+
+impl From<DiffChange> for ZfsDiffChange {
+    fn from(value: DiffChange) -> Self {
+        match value {
+            DiffChange::Added => Self::Added,
+            DiffChange::Removed => Self::Deleted,
+            DiffChange::Modified => Self::Modified,
+            DiffChange::Renamed => Self::Renamed,
+        }
+    }
+}
+
+impl From<ZfsDiffChange> for DiffChange {
+    fn from(value: ZfsDiffChange) -> Self {
+        match value {
+            ZfsDiffChange::Added => Self::Added,
+            ZfsDiffChange::Deleted => Self::Removed,
+            ZfsDiffChange::Modified => Self::Modified,
+            ZfsDiffChange::Renamed => Self::Renamed,
+        }
+    }
+}
+
+impl From<FileType> for ZfsFileType {
+    fn from(value: FileType) -> Self {
+        match value {
+            FileType::File => Self::File,
+            FileType::Directory => Self::Directory,
+            FileType::Symlink => Self::Symlink,
+            FileType::Pipe => Self::Pipe,
+            FileType::Socket => Self::Socket,
+            FileType::Door => Self::Door,
+            FileType::BlockDevice => Self::BlockDevice,
+            FileType::CharDevice => Self::CharDevice,
+            FileType::EventPort => Self::EventPort,
+        }
+    }
+}
+
+impl From<ZfsFileType> for FileType {
+    fn from(value: ZfsFileType) -> Self {
+        match value {
+            ZfsFileType::File => Self::File,
+            ZfsFileType::Directory => Self::Directory,
+            ZfsFileType::Symlink => Self::Symlink,
+            ZfsFileType::Pipe => Self::Pipe,
+            ZfsFileType::Socket => Self::Socket,
+            ZfsFileType::Door => Self::Door,
+            ZfsFileType::BlockDevice => Self::BlockDevice,
+            ZfsFileType::CharDevice => Self::CharDevice,
+            ZfsFileType::EventPort => Self::EventPort,
+        }
+    }
+}
+
+impl From<DiffEntry> for ZfsDiffEntry {
+    fn from(value: DiffEntry) -> Self {
+        Self {
+            change: Into::<ZfsDiffChange>::into(value.change).into(),
+            file_type: Into::<ZfsFileType>::into(value.file_type).into(),
+            path: value.path,
+            new_path: value.new_path,
+        }
+    }
+}
+
+impl From<ZfsDiffEntry> for DiffEntry {
+    fn from(value: ZfsDiffEntry) -> Self {
+        Self {
+            change: value.change().into(),
+            file_type: value.file_type().into(),
+            path: value.path,
+            new_path: value.new_path,
+        }
+    }
+}
+
+impl From<ZfsReceive> for ReceiveSnapshot {
+    fn from(value: ZfsReceive) -> Self {
+        Self {
+            dataset: value.dataset,
+            force: value.force,
+        }
+    }
+}
+
+impl From<PropertySource> for ZfsPropertySource {
+    fn from(value: PropertySource) -> Self {
+        match value {
+            PropertySource::Local => Self::Local,
+            PropertySource::Inherited => Self::Inherited,
+            PropertySource::Default => Self::Default,
+            PropertySource::Temporary => Self::Temporary,
+            PropertySource::Received => Self::Received,
+            PropertySource::None => Self::None,
+        }
+    }
+}
+
+impl From<ZfsPropertySource> for PropertySource {
+    fn from(value: ZfsPropertySource) -> Self {
+        match value {
+            ZfsPropertySource::Local => Self::Local,
+            ZfsPropertySource::Inherited => Self::Inherited,
+            ZfsPropertySource::Default => Self::Default,
+            ZfsPropertySource::Temporary => Self::Temporary,
+            ZfsPropertySource::Received => Self::Received,
+            ZfsPropertySource::None => Self::None,
+        }
+    }
+}
+
+impl From<Property> for ZfsProperty {
+    fn from(value: Property) -> Self {
+        Self {
+            name: value.name,
+            value: value.value,
+            source: Into::<ZfsPropertySource>::into(value.source).into(),
+            inherited_from: value.inherited_from,
+        }
+    }
+}
+
+impl From<ZfsProperty> for Property {
+    fn from(value: ZfsProperty) -> Self {
+        Self {
+            source: value.source().into(),
+            name: value.name,
+            value: value.value,
+            inherited_from: value.inherited_from,
+        }
+    }
+}
+
+impl From<ZfsProperties> for Vec<Property> {
+    fn from(value: ZfsProperties) -> Self {
+        let mut list = Self::default();
+        for item in value.properties {
+            list.push(item.into())
+        }
+        list
+    }
+}
+
+impl From<Vec<Property>> for ZfsProperties {
+    fn from(value: Vec<Property>) -> Self {
+        let mut list = Self::default();
+        for item in value {
+            list.properties.push(item.into())
+        }
+        list
+    }
+}
+
+impl From<GetProperties> for ZfsGetProperties {
+    fn from(value: GetProperties) -> Self {
+        Self {
+            name: value.name,
+            properties: value.properties,
+        }
+    }
+}
+
+impl From<ZfsGetProperties> for GetProperties {
+    fn from(value: ZfsGetProperties) -> Self {
+        Self {
+            name: value.name,
+            properties: value.properties,
+        }
+    }
+}
+
+impl From<SetProperties> for ZfsSetProperties {
+    fn from(value: SetProperties) -> Self {
+        Self {
+            name: value.name,
+            properties: value.properties,
+        }
+    }
+}
+
+impl From<ZfsSetProperties> for SetProperties {
+    fn from(value: ZfsSetProperties) -> Self {
+        Self {
+            name: value.name,
+            properties: value.properties,
+        }
+    }
+}
+
+impl From<InheritProperty> for ZfsInheritProperty {
+    fn from(value: InheritProperty) -> Self {
+        Self {
+            name: value.name,
+            property: value.property,
+            recursive: value.recursive,
+        }
+    }
+}
+
+impl From<ZfsInheritProperty> for InheritProperty {
+    fn from(value: ZfsInheritProperty) -> Self {
+        Self {
+            name: value.name,
+            property: value.property,
+            recursive: value.recursive,
+        }
+    }
+}
+
+impl From<Rollback> for ZfsRollback {
+    fn from(value: Rollback) -> Self {
+        Self {
+            dataset: value.dataset,
+            snapshot: value.snapshot,
+            destroy_newer: value.destroy_newer,
+        }
+    }
+}
+
+impl From<ZfsRollback> for Rollback {
+    fn from(value: ZfsRollback) -> Self {
+        Self {
+            dataset: value.dataset,
+            snapshot: value.snapshot,
+            destroy_newer: value.destroy_newer,
+        }
+    }
+}
+
+impl From<SnapshotStat> for ZfsSnapshotEntry {
+    fn from(value: SnapshotStat) -> Self {
+        Self {
+            dataset: value.dataset,
+            name: value.name,
+            full_name: value.full_name,
+            created: Some(value.created.into()),
+            used: value.used,
+            refer: value.refer,
+        }
+    }
+}
+
+impl From<ZfsSnapshotEntry> for SnapshotStat {
+    fn from(value: ZfsSnapshotEntry) -> Self {
+        Self {
+            dataset: value.dataset,
+            name: value.name,
+            full_name: value.full_name,
+            created: SystemTime::UNIX_EPOCH
+                + std::time::Duration::from_secs(value.created.unwrap_or_default().seconds as u64),
+            used: value.used,
+            refer: value.refer,
+        }
+    }
+}
+
+impl From<Hold> for ZfsHold {
+    fn from(value: Hold) -> Self {
+        Self {
+            dataset: value.dataset,
+            snapshot: value.snapshot,
+            tag: value.tag,
+            recursive: value.recursive,
+        }
+    }
+}
+
+impl From<ZfsHold> for Hold {
+    fn from(value: ZfsHold) -> Self {
+        Self {
+            dataset: value.dataset,
+            snapshot: value.snapshot,
+            tag: value.tag,
+            recursive: value.recursive,
+        }
+    }
+}
+
+impl From<HoldStat> for ZfsHoldEntry {
+    fn from(value: HoldStat) -> Self {
+        Self {
+            dataset: value.dataset,
+            snapshot: value.snapshot,
+            tag: value.tag,
+            created: Some(value.created.into()),
+        }
+    }
+}
+
+impl From<ZfsHoldEntry> for HoldStat {
+    fn from(value: ZfsHoldEntry) -> Self {
+        Self {
+            dataset: value.dataset,
+            snapshot: value.snapshot,
+            tag: value.tag,
+            created: SystemTime::UNIX_EPOCH
+                + std::time::Duration::from_secs(value.created.unwrap_or_default().seconds as u64),
+        }
+    }
+}
+
+impl From<Vec<HoldStat>> for ZfsHoldList {
+    fn from(value: Vec<HoldStat>) -> Self {
+        Self {
+            entries: value.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<ZfsHoldList> for Vec<HoldStat> {
+    fn from(value: ZfsHoldList) -> Self {
+        value.entries.into_iter().map(Into::into).collect()
+    }
+}
+
+impl From<SpaceStat> for ZfsSpaceEntry {
+    fn from(value: SpaceStat) -> Self {
+        Self {
+            name: value.name,
+            kind: Into::<ZfsType>::into(value.kind).into(),
+            used: value.used,
+            avail: value.avail,
+            used_by_snapshots: value.used_by_snapshots,
+            used_by_dataset: value.used_by_dataset,
+            used_by_children: value.used_by_children,
+            used_by_refreservation: value.used_by_refreservation,
+            logical_used: value.logical_used,
+            compressratio: value.compressratio,
+        }
+    }
+}
+
+impl From<ZfsSpaceEntry> for SpaceStat {
+    fn from(value: ZfsSpaceEntry) -> Self {
+        Self {
+            kind: value.kind().into(),
+            name: value.name,
+            used: value.used,
+            avail: value.avail,
+            used_by_snapshots: value.used_by_snapshots,
+            used_by_dataset: value.used_by_dataset,
+            used_by_children: value.used_by_children,
+            used_by_refreservation: value.used_by_refreservation,
+            logical_used: value.logical_used,
+            compressratio: value.compressratio,
+        }
+    }
+}
+
+impl From<SpaceUsage> for ZfsSpaceUsage {
+    fn from(value: SpaceUsage) -> Self {
+        Self {
+            pool: Some(value.pool.into()),
+            entries: value.entries.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<ZfsSpaceUsage> for SpaceUsage {
+    fn from(value: ZfsSpaceUsage) -> Self {
+        Self {
+            pool: value.pool.unwrap_or_default().into(),
+            entries: value.entries.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<ShareProtocol> for ZfsShareProtocol {
+    fn from(value: ShareProtocol) -> Self {
+        match value {
+            ShareProtocol::Nfs => Self::Nfs,
+            ShareProtocol::Smb => Self::Smb,
+        }
+    }
+}
+
+impl From<ZfsShareProtocol> for ShareProtocol {
+    fn from(value: ZfsShareProtocol) -> Self {
+        match value {
+            ZfsShareProtocol::Nfs => Self::Nfs,
+            ZfsShareProtocol::Smb => Self::Smb,
+        }
+    }
+}
+
+impl From<Share> for ZfsShare {
+    fn from(value: Share) -> Self {
+        Self {
+            dataset: value.dataset,
+            protocol: Into::<ZfsShareProtocol>::into(value.protocol).into(),
+            networks: value.networks,
+            read_only: value.read_only,
+            root_squash: Some(value.root_squash),
+        }
+    }
+}
+
+impl From<ZfsShare> for Share {
+    fn from(value: ZfsShare) -> Self {
+        Self {
+            protocol: value.protocol().into(),
+            dataset: value.dataset,
+            networks: value.networks,
+            read_only: value.read_only,
+            root_squash: value.root_squash.unwrap_or(true),
+        }
+    }
+}
+
+impl From<Unshare> for ZfsUnshare {
+    fn from(value: Unshare) -> Self {
+        Self {
+            dataset: value.dataset,
+            protocol: Into::<ZfsShareProtocol>::into(value.protocol).into(),
+        }
+    }
+}
+
+impl From<ZfsUnshare> for Unshare {
+    fn from(value: ZfsUnshare) -> Self {
+        Self {
+            protocol: value.protocol().into(),
+            dataset: value.dataset,
+        }
+    }
+}
+
+impl From<ShareStat> for ZfsShareEntry {
+    fn from(value: ShareStat) -> Self {
+        Self {
+            share: Some(value.share.into()),
+            options: value.options,
+        }
+    }
+}
+
+impl From<ZfsShareEntry> for ShareStat {
+    fn from(value: ZfsShareEntry) -> Self {
+        Self {
+            share: value.share.unwrap_or_default().into(),
+            options: value.options,
+        }
+    }
+}
+
+impl From<Vec<ShareStat>> for ZfsShareList {
+    fn from(value: Vec<ShareStat>) -> Self {
+        Self {
+            entries: value.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<ZfsShareList> for Vec<ShareStat> {
+    fn from(value: ZfsShareList) -> Self {
+        value.entries.into_iter().map(Into::into).collect()
+    }
+}
+
+impl From<QuotaKind> for ZfsQuotaType {
+    fn from(value: QuotaKind) -> Self {
+        match value {
+            QuotaKind::User => Self::User,
+            QuotaKind::Group => Self::Group,
+            QuotaKind::Project => Self::Project,
+        }
+    }
+}
+
+impl From<ZfsQuotaType> for QuotaKind {
+    fn from(value: ZfsQuotaType) -> Self {
+        match value {
+            ZfsQuotaType::User => Self::User,
+            ZfsQuotaType::Group => Self::Group,
+            ZfsQuotaType::Project => Self::Project,
+        }
+    }
+}
+
+impl From<Quota> for ZfsQuota {
+    fn from(value: Quota) -> Self {
+        Self {
+            dataset: value.dataset,
+            kind: Into::<ZfsQuotaType>::into(value.kind).into(),
+            id: value.id,
+            quota: value.quota,
+        }
+    }
+}
+
+impl From<ZfsQuota> for Quota {
+    fn from(value: ZfsQuota) -> Self {
+        Self {
+            kind: value.kind().into(),
+            dataset: value.dataset,
+            id: value.id,
+            quota: value.quota,
+        }
+    }
+}
+
+impl From<Vec<Quota>> for ZfsQuotaList {
+    fn from(value: Vec<Quota>) -> Self {
+        Self {
+            quotas: value.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<ZfsQuotaList> for Vec<Quota> {
+    fn from(value: ZfsQuotaList) -> Self {
+        value.quotas.into_iter().map(Into::into).collect()
+    }
+}
+
+impl From<QuotaFilter> for ZfsQuotaFilter {
+    fn from(value: QuotaFilter) -> Self {
+        Self {
+            dataset: value.dataset,
+            kind: Into::<ZfsQuotaType>::into(value.kind).into(),
+        }
+    }
+}
+
+impl From<ZfsQuotaFilter> for QuotaFilter {
+    fn from(value: ZfsQuotaFilter) -> Self {
+        Self {
+            kind: value.kind().into(),
+            dataset: value.dataset,
+        }
+    }
+}
+
+impl From<UserSpaceStat> for ZfsUserSpaceEntry {
+    fn from(value: UserSpaceStat) -> Self {
+        Self {
+            kind: Into::<ZfsQuotaType>::into(value.kind).into(),
+            id: value.id,
+            used: value.used,
+            quota: value.quota,
+            objused: value.objused,
+            objquota: value.objquota,
+        }
+    }
+}
+
+impl From<ZfsUserSpaceEntry> for UserSpaceStat {
+    fn from(value: ZfsUserSpaceEntry) -> Self {
+        Self {
+            kind: value.kind().into(),
+            id: value.id,
+            used: value.used,
+            quota: value.quota,
+            objused: value.objused,
+            objquota: value.objquota,
+        }
+    }
+}
+
+impl From<Vec<UserSpaceStat>> for ZfsUserSpace {
+    fn from(value: Vec<UserSpaceStat>) -> Self {
+        Self {
+            entries: value.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<ZfsUserSpace> for Vec<UserSpaceStat> {
+    fn from(value: ZfsUserSpace) -> Self {
+        value.entries.into_iter().map(Into::into).collect()
+    }
+}
+
+impl From<Bookmark> for ZfsBookmark {
+    fn from(value: Bookmark) -> Self {
+        Self {
+            dataset: value.dataset,
+            name: value.name,
+            snapshot: value.snapshot,
+        }
+    }
+}
+
+impl From<ZfsBookmark> for Bookmark {
+    fn from(value: ZfsBookmark) -> Self {
+        Self {
+            dataset: value.dataset,
+            name: value.name,
+            snapshot: value.snapshot,
+        }
+    }
+}
+
+impl From<BookmarkStat> for ZfsBookmarkEntry {
+    fn from(value: BookmarkStat) -> Self {
+        Self {
+            dataset: value.dataset,
+            name: value.name,
+            full_name: value.full_name,
+            created: Some(value.created.into()),
+        }
+    }
+}
+
+impl From<ZfsBookmarkEntry> for BookmarkStat {
+    fn from(value: ZfsBookmarkEntry) -> Self {
+        Self {
+            dataset: value.dataset,
+            name: value.name,
+            full_name: value.full_name,
+            created: SystemTime::UNIX_EPOCH
+                + std::time::Duration::from_secs(value.created.unwrap_or_default().seconds as u64),
+        }
+    }
+}
+
+impl From<Vec<BookmarkStat>> for ZfsBookmarkList {
+    fn from(value: Vec<BookmarkStat>) -> Self {
+        Self {
+            entries: value.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<ZfsBookmarkList> for Vec<BookmarkStat> {
+    fn from(value: ZfsBookmarkList) -> Self {
+        value.entries.into_iter().map(Into::into).collect()
+    }
+}
+
+impl From<ZfsSnapshotList> for Vec<SnapshotStat> {
+    fn from(value: ZfsSnapshotList) -> Self {
+        let mut list = Self::default();
+        for item in value.entries {
+            list.push(item.into())
+        }
+        list
+    }
+}
+
+impl From<Vec<SnapshotStat>> for ZfsSnapshotList {
+    fn from(value: Vec<SnapshotStat>) -> Self {
+        let mut list = Self::default();
+        for item in value {
+            list.entries.push(item.into())
+        }
+        list
+    }
+}
+
+impl From<ZfsList> for Vec<ZFSStat> {
+    fn from(value: ZfsList) -> Self {
+        let mut list = Self::default();
+        for item in value.entries {
+            list.push(item.into())
+        }
+        list
+    }
+}
+
+impl From<ZFSKind> for ZfsType {
+    fn from(value: ZFSKind) -> Self {
+        match value {
+            ZFSKind::Dataset => Self::Dataset,
+            ZFSKind::Volume => Self::Volume,
+            ZFSKind::Snapshot => Self::Snapshot,
+        }
+    }
+}
+
+impl From<ZfsType> for ZFSKind {
+    fn from(value: ZfsType) -> Self {
+        match value {
+            ZfsType::Dataset => Self::Dataset,
+            ZfsType::Volume => Self::Volume,
+            ZfsType::Snapshot => Self::Snapshot,
+        }
+    }
+}
+
+impl From<ListSort> for ZfsListSort {
+    fn from(value: ListSort) -> Self {
+        match value {
+            ListSort::Name => Self::Name,
+            ListSort::Used => Self::Used,
+            ListSort::Avail => Self::Avail,
+        }
+    }
+}
+
+impl From<ZfsListSort> for ListSort {
+    fn from(value: ZfsListSort) -> Self {
+        match value {
+            ZfsListSort::Name => Self::Name,
+            ZfsListSort::Used => Self::Used,
+            ZfsListSort::Avail => Self::Avail,
+        }
+    }
+}
+
+impl From<ListFilter> for ZfsListFilter {
+    fn from(value: ListFilter) -> Self {
+        Self {
+            filter: value.prefix,
+            fields: value.fields,
+            types: value
+                .kinds
+                .into_iter()
+                .map(|x| Into::<ZfsType>::into(x).into())
+                .collect(),
+            properties: value.properties.into_iter().collect(),
+            depth: value.depth,
+            sort: Into::<ZfsListSort>::into(value.sort).into(),
+            descending: value.descending,
+            page_size: value.page_size,
+            page_token: value.page_token.unwrap_or_default(),
+        }
+    }
+}
+
+impl From<ZfsListFilter> for ListFilter {
+    fn from(value: ZfsListFilter) -> Self {
+        Self {
+            kinds: value.types().map(Into::into).collect(),
+            sort: value.sort().into(),
+            prefix: value.filter,
+            fields: value.fields,
+            properties: value.properties.into_iter().collect(),
+            depth: value.depth,
+            descending: value.descending,
+            page_size: value.page_size,
+            page_token: if value.page_token.is_empty() {
+                None
+            } else {
+                Some(value.page_token)
+            },
+        }
+    }
+}
+
+impl From<ListPage> for ZfsList {
+    fn from(value: ListPage) -> Self {
+        Self {
+            entries: value.entries.into_iter().map(Into::into).collect(),
+            next_page_token: value.next_page_token.unwrap_or_default(),
+        }
+    }
+}
+
+impl From<ZFSNode> for ZfsNode {
+    fn from(value: ZFSNode) -> Self {
+        Self {
+            entry: Some(value.stat.into()),
+            used_by_children: value.used_by_children,
+            children: value.children.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<ZfsNode> for ZFSNode {
+    fn from(value: ZfsNode) -> Self {
+        Self {
+            stat: value.entry.unwrap_or_default().into(),
+            used_by_children: value.used_by_children,
+            children: value.children.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<Vec<ZFSNode>> for ZfsTree {
+    fn from(value: Vec<ZFSNode>) -> Self {
+        Self {
+            nodes: value.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<ZfsTree> for Vec<ZFSNode> {
+    fn from(value: ZfsTree) -> Self {
+        value.nodes.into_iter().map(Into::into).collect()
+    }
+}
+
+impl From<ZfsList> for ListPage {
+    fn from(value: ZfsList) -> Self {
+        Self {
+            entries: value.entries.into_iter().map(Into::into).collect(),
+            next_page_token: if value.next_page_token.is_empty() {
+                None
+            } else {
+                Some(value.next_page_token)
+            },
+        }
+    }
+}
+
+impl From<Vec<ZFSStat>> for ZfsList {
+    fn from(value: Vec<ZFSStat>) -> Self {
+        let mut list = Self::default();
+        for item in value {
+            list.entries.push(item.into())
+        }
+        list
+    }
+}
+
+impl From<ZfsEntry> for ZFSStat {
+    fn from(value: ZfsEntry) -> Self {
+        Self {
+            kind: match value.kind() {
+                ZfsType::Volume => ZFSKind::Volume,
+                ZfsType::Dataset => ZFSKind::Dataset,
+                ZfsType::Snapshot => ZFSKind::Snapshot,
+            },
+            name: value.name,
+            full_name: value.full_name,
+            size: value.size,
+            used: value.used,
+            avail: value.avail,
+            refer: value.refer,
+            mountpoint: value.mountpoint,
+            origin: value.origin,
+            device: value.device,
+            keystatus: value.keystatus,
+            encryption_root: value.encryption_root,
+            quota: value.quota,
+            refquota: value.refquota,
+            reservation: value.reservation,
+            refreservation: value.refreservation,
+            compression: value.compression,
+            compressratio: value.compressratio,
+            creation: value
+                .creation
+                .map(|x| SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(x.seconds as u64)),
+            used_by_snapshots: value.used_by_snapshots,
+            logical_used: value.logical_used,
+            recordsize: value.recordsize,
+            atime: value.atime,
+            canmount: value
+                .canmount
+                .and_then(|x| ZfsCanMount::try_from(x).ok())
+                .map(Into::into),
+            sharenfs: value.sharenfs,
+            sharesmb: value.sharesmb,
+            volblocksize: value.volblocksize,
+        }
+    }
+}
+
+impl From<ZFSStat> for ZfsEntry {
+    fn from(value: ZFSStat) -> Self {
+        Self {
+            kind: match value.kind {
+                ZFSKind::Volume => ZfsType::Volume,
+                ZFSKind::Dataset => ZfsType::Dataset,
+                ZFSKind::Snapshot => ZfsType::Snapshot,
+            }
+            .into(),
+            name: value.name,
+            full_name: value.full_name,
+            size: value.size,
+            used: value.used,
+            avail: value.avail,
+            refer: value.refer,
+            mountpoint: value.mountpoint,
+            origin: value.origin,
+            device: value.device,
+            keystatus: value.keystatus,
+            encryption_root: value.encryption_root,
+            quota: value.quota,
+            refquota: value.refquota,
+            reservation: value.reservation,
+            refreservation: value.refreservation,
+            compression: value.compression,
+            compressratio: value.compressratio,
+            creation: value.creation.map(Into::into),
+            used_by_snapshots: value.used_by_snapshots,
+            logical_used: value.logical_used,
+            recordsize: value.recordsize,
+            atime: value.atime,
+            canmount: value.canmount.map(|x| Into::<ZfsCanMount>::into(x).into()),
+            sharenfs: value.sharenfs,
+            sharesmb: value.sharesmb,
+            volblocksize: value.volblocksize,
+        }
+    }
+}
+
+impl From<VdevClass> for ZpoolVdevClass {
+    fn from(value: VdevClass) -> Self {
+        match value {
+            VdevClass::Data => Self::Data,
+            VdevClass::Log => Self::Log,
+            VdevClass::Cache => Self::Cache,
+            VdevClass::Spare => Self::Spare,
+            VdevClass::Special => Self::Special,
+            VdevClass::Dedup => Self::Dedup,
+        }
+    }
+}
+
+impl From<ZpoolVdevClass> for VdevClass {
+    fn from(value: ZpoolVdevClass) -> Self {
+        match value {
+            ZpoolVdevClass::Data => Self::Data,
+            ZpoolVdevClass::Log => Self::Log,
+            ZpoolVdevClass::Cache => Self::Cache,
+            ZpoolVdevClass::Spare => Self::Spare,
+            ZpoolVdevClass::Special => Self::Special,
+            ZpoolVdevClass::Dedup => Self::Dedup,
+        }
+    }
+}
+
+impl From<VdevLayout> for ZpoolVdevLayout {
+    fn from(value: VdevLayout) -> Self {
+        match value {
+            VdevLayout::Stripe => Self::Stripe,
+            VdevLayout::Mirror => Self::Mirror,
+            VdevLayout::Raidz1 => Self::Raidz1,
+            VdevLayout::Raidz2 => Self::Raidz2,
+            VdevLayout::Raidz3 => Self::Raidz3,
+        }
+    }
+}
+
+impl From<ZpoolVdevLayout> for VdevLayout {
+    fn from(value: ZpoolVdevLayout) -> Self {
+        match value {
+            ZpoolVdevLayout::Stripe => Self::Stripe,
+            ZpoolVdevLayout::Mirror => Self::Mirror,
+            ZpoolVdevLayout::Raidz1 => Self::Raidz1,
+            ZpoolVdevLayout::Raidz2 => Self::Raidz2,
+            ZpoolVdevLayout::Raidz3 => Self::Raidz3,
+        }
+    }
+}
+
+impl From<ImportablePool> for ZpoolImportable {
+    fn from(value: ImportablePool) -> Self {
+        Self {
+            name: value.name,
+            id: value.id,
+            health: Into::<ZfsPoolHealth>::into(value.health).into(),
+            status: value.status,
+            action: value.action,
+        }
+    }
+}
+
+impl From<ZpoolImportable> for ImportablePool {
+    fn from(value: ZpoolImportable) -> Self {
+        Self {
+            health: value.health().into(),
+            name: value.name,
+            id: value.id,
+            status: value.status,
+            action: value.action,
+        }
+    }
+}
+
+impl From<ZpoolImportableList> for Vec<ImportablePool> {
+    fn from(value: ZpoolImportableList) -> Self {
+        value.pools.into_iter().map(Into::into).collect()
+    }
+}
+
+impl From<Vec<ImportablePool>> for ZpoolImportableList {
+    fn from(value: Vec<ImportablePool>) -> Self {
+        Self {
+            pools: value.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<ImportPool> for ZpoolImport {
+    fn from(value: ImportPool) -> Self {
+        Self {
+            name: value.name,
+            new_name: value.new_name,
+            dirs: value.dirs,
+            force: value.force,
+        }
+    }
+}
+
+impl From<ZpoolImport> for ImportPool {
+    fn from(value: ZpoolImport) -> Self {
+        Self {
+            name: value.name,
+            new_name: value.new_name,
+            dirs: value.dirs,
+            force: value.force,
+        }
+    }
+}
+
+impl From<ExportPool> for ZpoolExport {
+    fn from(value: ExportPool) -> Self {
+        Self {
+            name: value.name,
+            force: value.force,
+        }
+    }
+}
+
+impl From<ZpoolExport> for ExportPool {
+    fn from(value: ZpoolExport) -> Self {
+        Self {
+            name: value.name,
+            force: value.force,
+        }
+    }
+}
+
+impl From<AddVdev> for ZpoolAddVdev {
+    fn from(value: AddVdev) -> Self {
+        Self {
+            class: Into::<ZpoolVdevClass>::into(value.class).into(),
+            layout: Into::<ZpoolVdevLayout>::into(value.layout).into(),
+            devices: value.devices,
+            force: value.force,
+        }
+    }
+}
+
+impl From<ZpoolAddVdev> for AddVdev {
+    fn from(value: ZpoolAddVdev) -> Self {
+        Self {
+            class: value.class().into(),
+            layout: value.layout().into(),
+            devices: value.devices,
+            force: value.force,
+        }
+    }
+}
+
+impl From<AttachDevice> for ZpoolAttach {
+    fn from(value: AttachDevice) -> Self {
+        Self {
+            device: value.device,
+            new_device: value.new_device,
+            force: value.force,
+        }
+    }
+}
+
+impl From<ZpoolAttach> for AttachDevice {
+    fn from(value: ZpoolAttach) -> Self {
+        Self {
+            device: value.device,
+            new_device: value.new_device,
+            force: value.force,
+        }
+    }
+}
+
+impl From<ReplaceDevice> for ZpoolReplace {
+    fn from(value: ReplaceDevice) -> Self {
+        Self {
+            device: value.device,
+            new_device: value.new_device,
+            force: value.force,
+        }
+    }
+}
+
+impl From<ZpoolReplace> for ReplaceDevice {
+    fn from(value: ZpoolReplace) -> Self {
+        Self {
+            device: value.device,
+            new_device: value.new_device,
+            force: value.force,
+        }
+    }
+}
+
+impl From<OnlineDevice> for ZpoolOnline {
+    fn from(value: OnlineDevice) -> Self {
+        Self {
+            device: value.device,
+            expand: value.expand,
+        }
+    }
+}
+
+impl From<ZpoolOnline> for OnlineDevice {
+    fn from(value: ZpoolOnline) -> Self {
+        Self {
+            device: value.device,
+            expand: value.expand,
+        }
+    }
+}
+
+impl From<OfflineDevice> for ZpoolOffline {
+    fn from(value: OfflineDevice) -> Self {
+        Self {
+            device: value.device,
+            temporary: value.temporary,
+        }
+    }
+}
+
+impl From<ZpoolOffline> for OfflineDevice {
+    fn from(value: ZpoolOffline) -> Self {
+        Self {
+            device: value.device,
+            temporary: value.temporary,
+        }
+    }
+}
+
+impl Pool {
+    pub fn new(name: &str) -> Self {
+        Self::with_backend(name, DefaultBackend::default())
+    }
+}
+
+impl<B: ZfsBackend> Pool<B> {
+    pub fn with_backend(name: &str, backend: B) -> Self {
+        Self {
+            name: name.to_string(),
+            backend,
+        }
+    }
+
+    pub fn create_dataset(&self, info: &Dataset) -> Result<()> {
+        let name = DatasetName::within(&self.name, &info.name)?;
+        let options = info.options()?;
+
+        if let Err(e) =
+            self.backend
+                .create_dataset(&self.name, &name, Some(options), info.key.as_deref())
+        {
+            error!("Creating dataset: {}", e.to_string());
+            return Err(e);
+        }
+
+        self.backend.mount(&self.name)?;
+
+        Ok(())
+    }
+
+    pub fn create_volume(&self, info: &Volume) -> Result<()> {
+        let name = DatasetName::within(&self.name, &info.name)?;
+        let options = info.options()?;
+
+        if let Err(e) = self.backend.create_volume(
+            &self.name,
+            &name,
+            info.size,
+            info.sparse,
+            Some(options),
+            info.key.as_deref(),
+        ) {
+            error!("Creating volume: {}", e.to_string());
+            return Err(e);
+        }
+
+        self.wait_for_device(&name)
+    }
+
+    fn device_path(&self, name: &str) -> String {
+        format!("{}/{}/{}", ZVOL_DEVICE_ROOT, self.name, name)
+    }
+
+    // udev creates the device node asynchronously after zfs returns; callers expect to be able to
+    // attach the volume as soon as we're done. volmode=none volumes never get one.
+    fn wait_for_device(&self, name: &str) -> Result<()> {
+        if self.backend.get::<String>(&self.name, name, "volmode")? == "none" {
+            return Ok(());
+        }
+
+        let path = self.device_path(name);
+
+        if let Err(e) = self.backend.wait_for_device(&path) {
+            error!("Waiting for device: {}", e.to_string());
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub fn modify_dataset(&self, info: ModifyDataset) -> Result<()> {
+        let name = DatasetName::within(&self.name, &info.name)?;
+        let new_name = match info.modifications.name.as_str() {
+            "" => None,
+            x => Some(DatasetName::within(&self.name, x)?),
+        };
+
+        let options = info.modifications.options()?;
+        let map = options
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.clone()))
+            .collect();
+
+        if let Err(e) = self.backend.set(&self.name, &name, map) {
+            error!("Setting options on dataset: {}", e.to_string());
+            return Err(e);
+        }
+
+        if let Some(new_name) = new_name.filter(|x| *x != name) {
+            self.backend.unmount(&self.name, &name)?;
+
+            if let Err(e) = self.backend.rename(&self.name, &name, &new_name) {
+                error!("Renaming dataset: {}", e.to_string());
+                return Err(e);
+            }
+
+            self.backend.mount(&self.name)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn modify_volume(&self, info: ModifyVolume) -> Result<()> {
+        let name = DatasetName::within(&self.name, &info.name)?;
+        let new_name = match info.modifications.name.as_str() {
+            "" => None,
+            x => Some(DatasetName::within(&self.name, x)?),
+        };
+
+        if info.modifications.volblocksize.is_some() {
+            return Err(anyhow!(
+                "volblocksize can only be set when creating a volume"
+            ));
+        }
+
+        let options = info.modifications.options()?;
+        let mut map: HashMap<&str, String> = options
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.clone()))
+            .collect();
+
+        if info.modifications.size != 0 {
+            map.insert("volsize", format!("{}", info.modifications.size));
+        }
+
+        if let Err(e) = self.backend.set(&self.name, &name, map) {
+            error!("Setting options on volume: {}", e.to_string());
+            return Err(e);
+        }
+
+        if let Some(new_name) = new_name.filter(|x| *x != name) {
+            if let Err(e) = self.backend.rename(&self.name, &name, &new_name) {
+                error!("Renaming volume: {}", e.to_string());
+                return Err(e);
+            }
+
+            self.wait_for_device(&new_name)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn destroy(&self, name: String) -> Result<()> {
+        self.destroy_with(&Destroy {
+            name,
+            ..Default::default()
+        })?;
+        Ok(())
+    }
+
+    pub fn destroy_with(&self, info: &Destroy) -> Result<DestroyReport> {
+        let name = DatasetName::within(&self.name, &info.name)?;
+
+        if !info.dry_run {
+            if let Err(e) =
+                self.backend
+                    .destroy(&self.name, &name, info.recursive, info.clones, info.force)
+            {
+                error!("Destroying dataset: {}", e.to_string());
+                return Err(e);
+            }
+
+            return Ok(DestroyReport::default());
+        }
+
+        let out = match self
+            .backend
+            .destroy_dry_run(&self.name, &name, info.recursive, info.clones)
+        {
+            Ok(x) => x,
+            Err(e) => {
+                error!("Previewing destroy: {}", e.to_string());
+                return Err(e);
+            }
+        };
+
+        let prefix = format!("{}/", self.name);
+        let mut report = DestroyReport::default();
+
+        // zfs destroy -nvp: a destroy line per entry, then one reclaim line with the total
+        for line in out.lines() {
+            match line.split_once('\t') {
+                Some(("destroy", name)) => report
+                    .entries
+                    .push(name.strip_prefix(&prefix).unwrap_or(name).to_string()),
+                Some(("reclaim", bytes)) => report.reclaimed = bytes.parse().unwrap_or_default(),
+                _ => {}
+            }
+        }
+
+        Ok(report)
+    }
+
+    pub fn create_snapshot(&self, info: &Snapshot) -> Result<()> {
+        let dataset = info.check(&self.name)?;
+
+        if let Err(e) = self
+            .backend
+            .snapshot(&self.name, &dataset, &info.name, info.recursive)
+        {
+            error!("Creating snapshot: {}", e.to_string());
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub fn destroy_snapshot(&self, info: &Snapshot) -> Result<()> {
+        let dataset = info.check(&self.name)?;
+
+        if let Err(e) =
+            self.backend
+                .destroy_snapshot(&self.name, &dataset, &info.name, info.recursive)
+        {
+            error!("Destroying snapshot: {}", e.to_string());
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub fn hold(&self, info: &Hold) -> Result<()> {
+        let dataset = info.check(&self.name)?;
+
+        if let Err(e) = self.backend.hold(
+            &self.name,
+            &dataset,
+            &info.snapshot,
+            &info.tag,
+            info.recursive,
+        ) {
+            error!("Holding snapshot: {}", e.to_string());
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub fn release(&self, info: &Hold) -> Result<()> {
+        let dataset = info.check(&self.name)?;
+
+        if let Err(e) = self.backend.release(
+            &self.name,
+            &dataset,
+            &info.snapshot,
+            &info.tag,
+            info.recursive,
+        ) {
+            error!("Releasing snapshot: {}", e.to_string());
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub fn list_holds(&self, info: &Snapshot) -> Result<Vec<HoldStat>> {
+        let dataset = info.check(&self.name)?;
+        let out = match self
+            .backend
+            .holds(&self.name, &dataset, &info.name, info.recursive)
+        {
+            Ok(x) => x,
+            Err(e) => {
+                error!("Listing holds: {}", e.to_string());
+                return Err(e);
+            }
+        };
+
+        let prefix = format!("{}/", self.name);
+        let mut ret = Vec::new();
+
+        // zfs holds -H -p: snapshot, tag and when it was placed, in seconds
+        for line in out.lines() {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let [name, tag, created] = fields.as_slice() else {
+                return Err(anyhow!("unexpected zfs holds output '{}'", line));
+            };
+
+            let Some((dataset, snapshot)) =
+                name.strip_prefix(&prefix).and_then(|x| x.split_once('@'))
+            else {
+                continue;
+            };
+
+            ret.push(HoldStat {
+                dataset: dataset.to_string(),
+                snapshot: snapshot.to_string(),
+                tag: tag.to_string(),
+                created: SystemTime::UNIX_EPOCH
+                    + std::time::Duration::from_secs(created.parse().unwrap_or_default()),
+            });
+        }
+
+        Ok(ret)
+    }
+
+    pub fn create_bookmark(&self, info: &Bookmark) -> Result<()> {
+        let dataset = info.check(&self.name)?;
+        DatasetName::check_component("snapshot", &info.snapshot)?;
+
+        if let Err(e) = self
+            .backend
+            .bookmark(&self.name, &dataset, &info.snapshot, &info.name)
+        {
+            error!("Creating bookmark: {}", e.to_string());
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub fn destroy_bookmark(&self, info: &Bookmark) -> Result<()> {
+        let dataset = info.check(&self.name)?;
+
+        if let Err(e) = self
+            .backend
+            .destroy_bookmark(&self.name, &dataset, &info.name)
+        {
+            error!("Destroying bookmark: {}", e.to_string());
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub fn list_bookmarks(&self, filter: Option<String>) -> Result<Vec<BookmarkStat>> {
+        let list =
+            match self
+                .backend
+                .list(&self.name, None, &["bookmark"], &["creation".to_string()])
+            {
+                Ok(x) => x,
+                Err(e) => {
+                    error!("Listing bookmarks: {}", e.to_string());
+                    return Err(e);
+                }
+            };
+
+        let mut ret = Vec::new();
+        for (name, item) in list.datasets {
+            if filter
+                .as_ref()
+                .is_some_and(|filter| !name.starts_with(&format!("{}/{}", self.name, filter)))
+            {
+                continue;
+            }
+
+            let Some((dataset, bookmark)) = name
+                .strip_prefix(&format!("{}/", self.name))
+                .and_then(|x| x.split_once('#'))
+            else {
+                continue;
+            };
+
+            ret.push(BookmarkStat {
+                dataset: dataset.to_string(),
+                name: bookmark.to_string(),
+                full_name: name.clone(),
+                created: SystemTime::UNIX_EPOCH
+                    + std::time::Duration::from_secs(item.number("creation").unwrap_or_default()),
+            });
+        }
+
+        ret.sort_by_key(|x| x.created);
+        Ok(ret)
+    }
+
+    pub fn clone_snapshot(&self, info: &CloneSnapshot) -> Result<()> {
+        let dataset = DatasetName::within(&self.name, &info.dataset)?;
+        DatasetName::check_component("snapshot", &info.snapshot)?;
+        let target = DatasetName::within(&self.name, &info.target)?;
+
+        if let Err(e) = self
+            .backend
+            .clone_snapshot(&self.name, &dataset, &info.snapshot, &target)
+        {
+            error!("Cloning snapshot: {}", e.to_string());
+            return Err(e);
+        }
+
+        self.backend.mount(&self.name)?;
+
+        Ok(())
+    }
+
+    pub fn promote(&self, name: String) -> Result<()> {
+        let name = DatasetName::within(&self.name, &name)?;
+
+        if let Err(e) = self.backend.promote(&self.name, &name) {
+            error!("Promoting clone: {}", e.to_string());
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub fn send(&self, info: &SendSnapshot) -> Result<ZFSPipe> {
+        let dataset = DatasetName::within(&self.name, &info.dataset)?;
+        DatasetName::check_component("snapshot", &info.snapshot)?;
+
+        match info.from.as_deref().map(|x| x.strip_prefix('#').ok_or(x)) {
+            Some(Ok(bookmark)) => DatasetName::check_component("bookmark", bookmark)?,
+            Some(Err(snapshot)) => DatasetName::check_component("snapshot", snapshot)?,
+            None => {}
+        }
+
+        match self
+            .backend
+            .send(&self.name, &dataset, &info.snapshot, info.from.as_deref())
+        {
+            Ok(x) => Ok(x),
+            Err(e) => {
+                error!("Sending snapshot: {}", e.to_string());
+                Err(e)
+            }
+        }
+    }
+
+    pub fn receive(&self, info: &ReceiveSnapshot) -> Result<ZFSPipe> {
+        let dataset = DatasetName::within(&self.name, &info.dataset)?;
+
+        match self.backend.receive(&self.name, &dataset, info.force) {
+            Ok(x) => Ok(x),
+            Err(e) => {
+                error!("Receiving snapshot: {}", e.to_string());
+                Err(e)
+            }
+        }
+    }
+
+    pub fn diff(&self, info: &DiffSnapshot) -> Result<ZFSDiff> {
+        let dataset = DatasetName::within(&self.name, &info.dataset)?;
+        for snapshot in std::iter::once(&info.from).chain(&info.to) {
+            DatasetName::check_component("snapshot", snapshot)?;
+        }
+
+        match self
+            .backend
+            .diff(&self.name, &dataset, &info.from, info.to.as_deref())
+        {
+            Ok(pipe) => Ok(ZFSDiff {
+                reader: std::io::BufReader::new(pipe),
+            }),
+            Err(e) => {
+                error!("Diffing snapshot: {}", e.to_string());
+                Err(e)
+            }
+        }
+    }
+
+    pub fn get_properties(&self, info: &GetProperties) -> Result<Vec<Property>> {
+        let name = DatasetName::within(&self.name, &info.name)?;
+
+        for property in &info.properties {
+            check_property_name(property)?;
+        }
+        let out = match self.backend.get_all(&self.name, &name, &info.properties) {
+            Ok(x) => x,
+            Err(e) => {
+                error!("Getting properties: {}", e.to_string());
+                return Err(e);
+            }
+        };
+
+        let full_name = format!("{}/{}", self.name, info.name);
+        let Some(item) = out.datasets.get(&full_name) else {
+            return Err(anyhow!("no properties returned for {}", info.name));
+        };
+
+        let mut ret = Vec::new();
+
+        for (name, property) in &item.properties {
+            let value = match &property.value {
+                serde_json::Value::String(s) => s.clone(),
+                value => value.to_string(),
+            };
+
+            let source = match property.source.typ.as_str() {
+                "LOCAL" => PropertySource::Local,
+                "INHERITED" => PropertySource::Inherited,
+                "DEFAULT" => PropertySource::Default,
+                "TEMPORARY" => PropertySource::Temporary,
+                "RECEIVED" => PropertySource::Received,
+                _ => PropertySource::None,
+            };
+
+            let inherited_from = if source == PropertySource::Inherited {
+                Some(
+                    property
+                        .source
+                        .data
+                        .strip_prefix(&format!("{}/", self.name))
+                        .unwrap_or(&property.source.data)
+                        .to_owned(),
+                )
+            } else {
+                None
+            };
+
+            ret.push(Property {
+                name: name.clone(),
+                value,
+                source,
+                inherited_from,
+            })
+        }
+
+        ret.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(ret)
+    }
+
+    pub fn set_properties(&self, info: &SetProperties) -> Result<()> {
+        let name = DatasetName::within(&self.name, &info.name)?;
+
+        for key in info.properties.keys() {
+            check_property_name(key)?;
+        }
+
+        let map = info
+            .properties
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.clone()))
+            .collect();
+
+        if let Err(e) = self.backend.set(&self.name, &name, map) {
+            error!("Setting properties: {}", e.to_string());
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub fn space_usage(&self, filter: Option<String>) -> Result<SpaceUsage> {
+        let properties: Vec<String> = SPACE_PROPERTIES.iter().map(|x| x.to_string()).collect();
+        let list = match self
+            .backend
+            .list(&self.name, None, &["filesystem", "volume"], &properties)
+        {
+            Ok(x) => x,
+            Err(e) => {
+                error!("Listing space usage: {}", e.to_string());
+                return Err(e);
+            }
+        };
+
+        let stat = |name: &str, item: &ZFSListItem| SpaceStat {
+            name: name.to_string(),
+            kind: if item.typ == "VOLUME" {
+                ZFSKind::Volume
+            } else {
+                ZFSKind::Dataset
+            },
+            used: item.number("used").unwrap_or_default(),
+            avail: item.number("available").unwrap_or_default(),
+            used_by_snapshots: item.number("usedbysnapshots").unwrap_or_default(),
+            used_by_dataset: item.number("usedbydataset").unwrap_or_default(),
+            used_by_children: item.number("usedbychildren").unwrap_or_default(),
+            used_by_refreservation: item.number("usedbyrefreservation").unwrap_or_default(),
+            logical_used: item.number("logicalused").unwrap_or_default(),
+            compressratio: item.ratio("compressratio").unwrap_or(1.0),
+        };
+
+        let mut pool = None;
+        let mut entries = Vec::new();
+        for (name, item) in &list.datasets {
+            if *name == self.name {
+                pool = Some(stat(name, item));
+                continue;
+            }
+
+            if filter
+                .as_ref()
+                .is_some_and(|filter| !name.starts_with(&format!("{}/{}", self.name, filter)))
+            {
+                continue;
+            }
+
+            if let Some(short_name) = name.strip_prefix(&format!("{}/", self.name)) {
+                entries.push(stat(short_name, item));
+            }
+        }
+
+        let Some(pool) = pool else {
+            return Err(anyhow!("no space usage returned for {}", self.name));
+        };
+
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(SpaceUsage { pool, entries })
+    }
+
+    pub fn share(&self, info: &Share) -> Result<()> {
+        let dataset = DatasetName::within(&self.name, &info.dataset)?;
+        let value = info.property_value()?;
+        let mut map = HashMap::new();
+        map.insert(info.protocol.property(), value);
+
+        if let Err(e) = self.backend.set(&self.name, &dataset, map) {
+            error!("Sharing dataset: {}", e.to_string());
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub fn unshare(&self, info: &Unshare) -> Result<()> {
+        let dataset = DatasetName::within(&self.name, &info.dataset)?;
+        let mut map = HashMap::new();
+        map.insert(info.protocol.property(), "off".to_string());
+
+        if let Err(e) = self.backend.set(&self.name, &dataset, map) {
+            error!("Unsharing dataset: {}", e.to_string());
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub fn list_shares(&self, filter: Option<String>) -> Result<Vec<ShareStat>> {
+        let properties: Vec<String> = ShareProtocol::ALL
+            .iter()
+            .map(|x| x.property().to_string())
+            .collect();
+        let list = match self
+            .backend
+            .list(&self.name, None, &["filesystem"], &properties)
+        {
+            Ok(x) => x,
+            Err(e) => {
+                error!("Listing shares: {}", e.to_string());
+                return Err(e);
+            }
+        };
+
+        let mut ret = Vec::new();
+        for (name, item) in list.datasets {
+            if filter
+                .as_ref()
+                .is_some_and(|filter| !name.starts_with(&format!("{}/{}", self.name, filter)))
+            {
+                continue;
+            }
+
+            // the pool itself isn't something we hand out
+            let Some(dataset) = name.strip_prefix(&format!("{}/", self.name)) else {
+                continue;
+            };
+
+            for protocol in ShareProtocol::ALL {
+                match item.string(protocol.property()) {
+                    Some(options) if options != "off" && options != "-" => ret.push(ShareStat {
+                        share: Share::parse(dataset, protocol, &options),
+                        options,
+                    }),
+                    _ => {}
+                }
+            }
+        }
+
+        ret.sort();
+        Ok(ret)
+    }
+
+    pub fn set_quota(&self, info: &Quota) -> Result<()> {
+        let dataset = DatasetName::within(&self.name, &info.dataset)?;
+
+        if info.id.is_empty() || info.id.contains(|c: char| c.is_whitespace() || c == '=') {
+            return Err(InvalidName(format!("invalid quota id '{}'", info.id)).into());
+        }
+
+        let property = format!("{}@{}", info.kind.property(), info.id);
+        let value = match info.quota {
+            Some(x) => x.to_string(),
+            None => "none".to_string(),
+        };
+
+        let mut map = HashMap::new();
+        map.insert(property.as_str(), value);
+
+        if let Err(e) = self.backend.set(&self.name, &dataset, map) {
+            error!("Setting quota: {}", e.to_string());
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub fn list_quotas(&self, info: &QuotaFilter) -> Result<Vec<Quota>> {
+        Ok(self
+            .user_space(info)?
+            .into_iter()
+            .filter(|x| x.quota.is_some())
+            .map(|x| Quota {
+                dataset: info.dataset.clone(),
+                kind: x.kind,
+                id: x.id,
+                quota: x.quota,
+            })
+            .collect())
+    }
+
+    pub fn user_space(&self, info: &QuotaFilter) -> Result<Vec<UserSpaceStat>> {
+        let dataset = DatasetName::within(&self.name, &info.dataset)?;
+        let out = match self.backend.user_space(&self.name, &dataset, info.kind) {
+            Ok(x) => x,
+            Err(e) => {
+                error!("Listing user space: {}", e.to_string());
+                return Err(e);
+            }
+        };
+
+        // unset quotas come back as none, and object counts as - where zfs doesn't track them
+        let number = |x: &str| x.parse::<u64>().ok();
+        let mut ret = Vec::new();
+
+        // name, used, quota, objused, objquota
+        for line in out.lines() {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let [id, used, quota, objused, objquota] = fields.as_slice() else {
+                return Err(anyhow!("unexpected zfs userspace output '{}'", line));
+            };
+
+            ret.push(UserSpaceStat {
+                kind: info.kind,
+                id: id.to_string(),
+                used: number(used).unwrap_or_default(),
+                quota: number(quota),
+                objused: number(objused).unwrap_or_default(),
+                objquota: number(objquota),
+            });
+        }
+
+        Ok(ret)
+    }
+
+    pub fn inherit_property(&self, info: &InheritProperty) -> Result<()> {
+        let name = DatasetName::within(&self.name, &info.name)?;
+        check_property_name(&info.property)?;
+
+        if let Err(e) = self
+            .backend
+            .inherit(&self.name, &name, &info.property, info.recursive)
+        {
+            error!("Inheriting property: {}", e.to_string());
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub fn load_key(&self, info: &LoadKey) -> Result<()> {
+        let name = DatasetName::within(&self.name, &info.name)?;
+
+        if let Err(e) = self
+            .backend
+            .load_key(&self.name, &name, info.key.as_deref())
+        {
+            error!("Loading key: {}", e.to_string());
+            return Err(e);
+        }
+
+        self.backend.mount(&self.name)?;
+
+        Ok(())
+    }
+
+    pub fn unload_key(&self, name: String) -> Result<()> {
+        let name = DatasetName::within(&self.name, &name)?;
+
+        // volumes have nothing to unmount
+        let mounted = self.backend.get::<String>(&self.name, &name, "type")? == "filesystem";
+
+        if mounted {
+            self.backend.unmount(&self.name, &name)?;
+        }
+
+        if let Err(e) = self.backend.unload_key(&self.name, &name) {
+            error!("Unloading key: {}", e.to_string());
+
+            // the key is still loaded, so put the dataset back where it was
+            if mounted {
+                self.backend.mount(&self.name)?;
+            }
+
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub fn change_key(&self, info: &ChangeKey) -> Result<()> {
+        let name = DatasetName::within(&self.name, &info.name)?;
+        let mut options = CommandOptions::default();
+
+        if let Some(keyformat) = &info.keyformat {
+            options.insert("keyformat".to_string(), keyformat.clone());
+        }
+
+        if let Some(keylocation) = &info.keylocation {
+            options.insert("keylocation".to_string(), keylocation.clone());
+        }
+
+        validate_encryption(None, &mut options, info.key.is_some())?;
+
+        if let Err(e) = self
+            .backend
+            .change_key(&self.name, &name, options, info.key.as_deref())
+        {
+            error!("Changing key: {}", e.to_string());
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub fn rollback(&self, info: &Rollback) -> Result<()> {
+        let dataset = DatasetName::within(&self.name, &info.dataset)?;
+        DatasetName::check_component("snapshot", &info.snapshot)?;
+
+        // volumes have nothing to unmount
+        let mounted = self.backend.get::<String>(&self.name, &dataset, "type")? == "filesystem";
+
+        if mounted {
+            self.backend.unmount(&self.name, &dataset)?;
+        }
+
+        let res = self
+            .backend
+            .rollback(&self.name, &dataset, &info.snapshot, info.destroy_newer);
+
+        // remount even if the rollback failed, so the dataset isn't left unavailable
+        let remount = if mounted {
+            self.backend.mount(&self.name)
+        } else {
+            Ok(())
+        };
+
+        if let Err(e) = &remount {
+            error!("Remounting after rollback: {}", e.to_string());
+        }
+
+        // the rollback's own failure is what the caller needs to hear about
+        if let Err(e) = res {
+            error!("Rolling back dataset: {}", e.to_string());
+            return Err(e);
+        }
+
+        remount
+    }
+
+    pub fn list_snapshots(&self, filter: Option<String>) -> Result<Vec<SnapshotStat>> {
+        // with the txg each was taken in, to order snapshots taken within the same second
+        let mut ret: Vec<(u64, SnapshotStat)> = Vec::new();
+        let list = match self.backend.list_snapshots() {
+            Ok(x) => x,
+            Err(e) => {
+                error!("Listing snapshots: {}", e.to_string());
+                return Err(e);
+            }
+        };
+
+        for (name, item) in list.datasets {
+            if filter
+                .as_ref()
+                .is_some_and(|filter| !item.name.starts_with(&format!("{}/{}", self.name, filter)))
+            {
+                continue;
+            }
+
+            let Some(short_name) = name.strip_prefix(&format!("{}/", self.name)) else {
+                // snapshots of the pool's root dataset are not ours to manage
+                continue;
+            };
+
+            let Some((dataset, snapshot)) = short_name.split_once('@') else {
+                continue;
+            };
+
+            ret.push((
+                item.createtxg,
+                SnapshotStat {
+                    dataset: dataset.to_owned(),
+                    name: snapshot.to_owned(),
+                    full_name: name.clone(),
+                    created: SystemTime::UNIX_EPOCH
+                        + std::time::Duration::from_secs(item.properties.creation.value),
+                    used: item.properties.used.value,
+                    refer: item.properties.referenced.value,
+                },
+            ))
+        }
+
+        ret.sort_by_key(|(txg, s)| (s.created, *txg));
+        Ok(ret.into_iter().map(|(_, s)| s).collect())
+    }
+
+    // the snapshots of the policy's dataset, oldest first
+    fn policy_snapshots(&self, policy: &SnapshotPolicy) -> Result<Vec<SnapshotStat>> {
+        Ok(self
+            .list_snapshots(Some(policy.dataset.clone()))?
+            .into_iter()
+            .filter(|x| x.dataset == policy.dataset)
+            .collect())
+    }
+
+    // Takes whatever snapshots the policy has due, or one of the given period regardless, then
+    // prunes each period down to what the policy keeps. Periods the policy doesn't keep are left
+    // alone, so turning one off doesn't destroy what it already took.
+    pub fn run_snapshot_policy(
+        &self,
+        policy: &SnapshotPolicy,
+        period: Option<SnapshotPeriod>,
+        now: SystemTime,
+    ) -> Result<PolicyRun> {
+        match period {
+            Some(period) if policy.keep(period) == 0 => {
+                return Err(InvalidName(format!(
+                    "the policy for '{}' keeps no {} snapshots",
+                    policy.dataset,
+                    period.label()
+                ))
+                .into());
+            }
+            _ => {}
+        }
+
+        let snapshots = self.policy_snapshots(policy)?;
+        let mut run = PolicyRun::default();
+
+        for p in SnapshotPeriod::ALL {
+            let keep = policy.keep(p) as usize;
+            if keep == 0 {
+                continue;
+            }
+
+            let prefix = p.prefix();
+            let taken: Vec<&SnapshotStat> = snapshots
+                .iter()
+                .filter(|x| x.name.starts_with(&prefix))
+                .collect();
+            let mut names: Vec<String> = taken.iter().map(|x| x.name.clone()).collect();
+
+            let due = match period {
+                Some(period) => period == p,
+                None => taken.last().is_none_or(|last| {
+                    now.duration_since(last.created).unwrap_or_default() >= p.interval()
+                }),
+            };
+
+            if due {
+                // names only go down to the second; a second run within it gets a suffix
+                let stamp = format!("{}{}", prefix, snapshot_timestamp(now));
+                let name = std::iter::once(stamp.clone())
+                    .chain((1..).map(|n| format!("{}-{}", stamp, n)))
+                    .find(|x| !names.contains(x))
+                    .unwrap();
+                self.create_snapshot(&Snapshot {
+                    dataset: policy.dataset.clone(),
+                    name: name.clone(),
+                    recursive: policy.recursive,
+                })?;
+                run.created.push(format!("{}@{}", policy.dataset, name));
+                names.push(name);
+            }
+
+            // a snapshot that can't go, most likely held for a backup, stays until a later run;
+            // it mustn't keep the rest of the policy from running
+            let excess = names.len().saturating_sub(keep);
+            for name in names.drain(..excess) {
+                let snapshot = format!("{}@{}", policy.dataset, name);
+
+                match self.destroy_snapshot(&Snapshot {
+                    dataset: policy.dataset.clone(),
+                    name,
+                    recursive: policy.recursive,
+                }) {
+                    Ok(()) => run.destroyed.push(snapshot),
+                    Err(e) => {
+                        error!("Pruning {}: {}", snapshot, e.to_string());
+                        run.failed.push(PruneFailure {
+                            snapshot,
+                            error: e.to_string(),
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(run)
+    }
+
+    // how long until the policy has a snapshot due; None if it keeps none at all
+    pub fn next_snapshot_policy_run(
+        &self,
+        policy: &SnapshotPolicy,
+        now: SystemTime,
+    ) -> Result<Option<std::time::Duration>> {
+        let snapshots = self.policy_snapshots(policy)?;
+
+        Ok(SnapshotPeriod::ALL
+            .into_iter()
+            .filter(|p| policy.keep(*p) != 0)
+            .map(|p| {
+                let prefix = p.prefix();
+                match snapshots.iter().rfind(|x| x.name.starts_with(&prefix)) {
+                    Some(last) => p
+                        .interval()
+                        .saturating_sub(now.duration_since(last.created).unwrap_or_default()),
+                    None => std::time::Duration::ZERO,
+                }
+            })
+            .min())
+    }
+
+    pub fn list(&self, filter: Option<String>) -> Result<Vec<ZFSStat>> {
+        Ok(self
+            .list_filtered(&ListFilter {
+                prefix: filter,
+                ..Default::default()
+            })?
+            .entries)
+    }
+
+    // Lists entries with one zfs list, filling in only the optional ZFSStat fields asked for,
+    // then filters, sorts and pages through them.
+    pub fn list_filtered(&self, filter: &ListFilter) -> Result<ListPage> {
+        let fields = &filter.fields;
+        let mut properties: Vec<String> = LIST_PROPERTIES.iter().map(|x| x.to_string()).collect();
+
+        for field in fields {
+            if BASE_LIST_FIELDS.contains(&field.as_str()) {
+                continue;
+            }
+
+            if !LIST_FIELDS.iter().any(|(name, _)| name == field) {
+                return Err(anyhow!("unknown field '{}'", field));
+            }
+        }
+
+        let wanted = |field: &str| fields.is_empty() || fields.iter().any(|x| x == field);
+
+        for (field, property) in LIST_FIELDS {
+            if wanted(field) && !properties.iter().any(|x| x == property) {
+                properties.push(property.to_string());
+            }
+        }
+
+        for property in filter.properties.keys() {
+            if !properties.contains(property) {
+                properties.push(property.clone());
+            }
+        }
+
+        let offset = match &filter.page_token {
+            Some(token) => token
+                .parse::<usize>()
+                .map_err(|_| anyhow!("invalid page token '{}'", token))?,
+            None => 0,
+        };
+
+        let types: Vec<&str> = if filter.kinds.is_empty() {
+            vec!["filesystem", "volume"]
+        } else {
+            filter.kinds.iter().map(|x| x.zfs_type()).collect()
+        };
+
+        let mut ret = Vec::new();
+        let list = match self
+            .backend
+            .list(&self.name, filter.depth, &types, &properties)
+        {
+            Ok(x) => x,
+            Err(e) => {
+                error!("Listing datasets: {}", e.to_string());
+                return Err(e);
+            }
+        };
+
+        for (name, item) in list.datasets {
+            if let Some(prefix) = &filter.prefix {
+                if !item.name.starts_with(&format!("{}/{}", self.name, prefix)) {
+                    continue;
+                }
+            }
+
+            if !name.starts_with(&self.name) {
+                continue;
+            }
+
+            if name.split('@').next() == Some(&self.name) {
+                // skip root-level datasets since they correspond to pools
+                continue;
+            }
+
+            if filter
+                .properties
+                .iter()
+                .any(|(property, value)| item.string(property).as_ref() != Some(value))
+            {
+                continue;
+            }
+
+            let short_name = name
+                .strip_prefix(&format!("{}/", self.name))
+                .unwrap_or_else(|| &name)
+                .to_owned();
+
+            let kind = match item.typ.as_str() {
+                "VOLUME" => ZFSKind::Volume,
+                "SNAPSHOT" => ZFSKind::Snapshot,
+                _ => ZFSKind::Dataset,
+            };
+            let volume = kind == ZFSKind::Volume;
+            let strip_pool = |value: String| {
+                value
+                    .strip_prefix(&format!("{}/", self.name))
+                    .map(|x| x.to_owned())
+                    .unwrap_or(value)
+            };
+            let quota = item.number("quota").unwrap_or_default();
+
+            ret.push(ZFSStat {
+                // volumes don't have a mountpath, '-' is indicated
+                // FIXME relying on datasets being mounted is a thing we're doing right now, it'll
+                //       probably have to change eventually, but zfs handles all the mounting for
+                //       us at create and destroy time.
+                full_name: name.clone(),
+                name: short_name.clone(), // strip the pool
+                used: item.number("used").unwrap_or_default(),
+                avail: item.number("available").unwrap_or_default(),
+                // this is just easier to use in places
+                size: if volume {
+                    item.number("volsize").unwrap_or_default()
+                } else if kind == ZFSKind::Snapshot {
+                    item.number("referenced").unwrap_or_default()
+                } else if quota != 0 {
+                    quota
+                } else {
+                    item.number("available").unwrap_or_default()
+                },
+                refer: item.number("referenced").unwrap_or_default(),
+                mountpoint: item.string("mountpoint"),
+                keystatus: item.string("keystatus"),
+                encryption_root: item.string("encryptionroot").map(strip_pool),
+                device: if volume {
+                    Some(self.device_path(&short_name))
+                } else {
+                    None
+                },
+                origin: item.string("origin").map(strip_pool),
+                // always listed, since size comes from it
+                quota: item.number("quota").filter(|_| wanted("quota")),
+                refquota: item.number("refquota"),
+                reservation: item.number("reservation"),
+                refreservation: item.number("refreservation"),
+                compression: item.string("compression"),
+                compressratio: item.ratio("compressratio"),
+                creation: item
+                    .number("creation")
+                    .map(|x| SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(x)),
+                used_by_snapshots: item.number("usedbysnapshots"),
+                logical_used: item.number("logicalused"),
+                recordsize: item.number("recordsize"),
+                atime: item.string("atime").map(|x| x == "on"),
+                canmount: item.string("canmount").and_then(|x| x.parse().ok()),
+                sharenfs: item.string("sharenfs"),
+                sharesmb: item.string("sharesmb"),
+                volblocksize: item.number("volblocksize"),
+                kind,
+            })
+        }
+
+        ret.sort_by(|a, b| {
+            let order = match filter.sort {
+                ListSort::Name => std::cmp::Ordering::Equal,
+                ListSort::Used => a.used.cmp(&b.used),
+                ListSort::Avail => a.avail.cmp(&b.avail),
+            }
+            .then_with(|| a.full_name.cmp(&b.full_name));
+
+            if filter.descending {
+                order.reverse()
+            } else {
+                order
+            }
+        });
+
+        // the token is just where the next page starts
+        let mut entries: Vec<ZFSStat> = ret.into_iter().skip(offset).collect();
+        let mut next_page_token = None;
+
+        if filter.page_size != 0 && entries.len() > filter.page_size as usize {
+            entries.truncate(filter.page_size as usize);
+            next_page_token = Some((offset + entries.len()).to_string());
+        }
+
+        Ok(ListPage {
+            entries,
+            next_page_token,
+        })
+    }
+
+    // The same entries as list_filtered, without paging, nested under their nearest listed
+    // ancestor; snapshots hang off their dataset.
+    pub fn tree(&self, filter: &ListFilter) -> Result<Vec<ZFSNode>> {
+        let entries = self
+            .list_filtered(&ListFilter {
+                page_size: 0,
+                page_token: None,
+                ..filter.clone()
+            })?
+            .entries;
+
+        let index: HashMap<&str, usize> = entries
+            .iter()
+            .enumerate()
+            .map(|(i, x)| (x.full_name.as_str(), i))
+            .collect();
+        let mut children = vec![Vec::new(); entries.len()];
+        let mut roots = Vec::new();
+
+        for (i, entry) in entries.iter().enumerate() {
+            let mut name = entry.full_name.as_str();
+            let parent = loop {
+                let next = match name.split_once('@') {
+                    Some((dataset, _)) => dataset,
+                    None => match name.rsplit_once('/') {
+                        Some((parent, _)) => parent,
+                        None => break None,
+                    },
+                };
+
+                if let Some(parent) = index.get(next) {
+                    break Some(*parent);
+                }
+                name = next;
+            };
+
+            match parent {
+                Some(parent) => children[parent].push(i),
+                None => roots.push(i),
+            }
+        }
+
+        fn build(i: usize, entries: &mut [Option<ZFSStat>], children: &[Vec<usize>]) -> ZFSNode {
+            let nodes: Vec<ZFSNode> = children[i]
+                .iter()
+                .map(|child| build(*child, entries, children))
+                .collect();
+
+            ZFSNode {
+                // snapshot space is usedbysnapshots, not the children's
+                used_by_children: nodes
+                    .iter()
+                    .filter(|x| x.stat.kind != ZFSKind::Snapshot)
+                    .map(|x| x.stat.used)
+                    .sum(),
+                stat: entries[i].take().unwrap(),
+                children: nodes,
+            }
+        }
+
+        let mut entries: Vec<Option<ZFSStat>> = entries.into_iter().map(Some).collect();
+        Ok(roots
+            .into_iter()
+            .map(|i| build(i, &mut entries, &children))
+            .collect())
+    }
+
+    pub fn status(&self) -> Result<PoolStatus> {
+        let status = match self.backend.pool_status(&self.name) {
+            Ok(x) => x,
+            Err(e) => {
+                error!("Retrieving pool status: {}", e.to_string());
+                return Err(e);
+            }
+        };
+
+        let list = match self.backend.pool_list(&self.name) {
+            Ok(x) => x,
+            Err(e) => {
+                error!("Retrieving pool capacity: {}", e.to_string());
+                return Err(e);
+            }
+        };
+
+        let (Some(status), Some(list)) = (status.pools.get(&self.name), list.pools.get(&self.name))
+        else {
+            return Err(anyhow!("no status returned for pool {}", self.name));
+        };
+
+        // the top of the vdev tree is the pool itself; callers care about what's under it
+        let vdevs = status
+            .vdevs
+            .values()
+            .flat_map(|root| root.vdevs.values())
+            .map(Into::into)
+            .collect();
+
+        Ok(PoolStatus {
+            name: self.name.clone(),
+            health: list.properties.health.value.as_str().into(),
+            status: status.status.clone(),
+            action: status.action.clone(),
+            vdevs,
+            logs: status.logs.values().map(Into::into).collect(),
+            cache: status.l2cache.values().map(Into::into).collect(),
+            spares: status.spares.values().map(Into::into).collect(),
+            scan: status
+                .scan_stats
+                .as_ref()
+                .filter(|x| x.function != "NONE")
+                .map(Into::into),
+            size: list.properties.size.value.as_u64(),
+            allocated: list.properties.allocated.value.as_u64(),
+            free: list.properties.free.value.as_u64(),
+            capacity: list.properties.capacity.value.as_u64(),
+            fragmentation: list.properties.fragmentation.value.as_u64(),
+            dedup_ratio: list.properties.dedupratio.value.as_f64(),
+            data_errors: status.error_count.as_u64(),
+        })
+    }
+
+    pub fn start_scrub(&self) -> Result<()> {
+        if let Err(e) = self.backend.scrub(&self.name, None) {
+            error!("Starting scrub: {}", e.to_string());
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub fn pause_scrub(&self) -> Result<()> {
+        if let Err(e) = self.backend.scrub(&self.name, Some("-p")) {
+            error!("Pausing scrub: {}", e.to_string());
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub fn cancel_scrub(&self) -> Result<()> {
+        if let Err(e) = self.backend.scrub(&self.name, Some("-s")) {
+            error!("Cancelling scrub: {}", e.to_string());
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub fn start_trim(&self) -> Result<()> {
+        if let Err(e) = self.backend.trim(&self.name, None) {
+            error!("Starting trim: {}", e.to_string());
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub fn pause_trim(&self) -> Result<()> {
+        if let Err(e) = self.backend.trim(&self.name, Some("-s")) {
+            error!("Pausing trim: {}", e.to_string());
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub fn cancel_trim(&self) -> Result<()> {
+        if let Err(e) = self.backend.trim(&self.name, Some("-c")) {
+            error!("Cancelling trim: {}", e.to_string());
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    // how long until the pool is due for a scrub. This is worked out from the pool's own scan
+    // history rather than kept in memory, so restarting buckled doesn't reset the clock. Returns
+    // None while a scrub or resilver is running.
+    pub fn next_scrub(&self, interval: std::time::Duration) -> Result<Option<std::time::Duration>> {
+        let Some(scan) = self.status()?.scan else {
+            return Ok(Some(std::time::Duration::ZERO));
+        };
+
+        if scan.state == "scanning" {
+            return Ok(None);
+        }
+
+        // zpool status only has the last scan; a resilver since, after replacing a disk say, only
+        // read what went to the new one and leaves no trace of when the last scrub was
+        if scan.function != "scrub" {
+            return Ok(Some(std::time::Duration::ZERO));
+        }
+
+        // canceled scans count too, otherwise cancelling a scrub would just have it started again
+        let Some(last) = scan.end_time.or(scan.start_time) else {
+            return Ok(Some(std::time::Duration::ZERO));
+        };
+
+        Ok(Some(interval.saturating_sub(
+            SystemTime::now().duration_since(last).unwrap_or_default(),
+        )))
+    }
+
+    // import and export work on any pool, not just the one this Pool manages
+    pub fn importable(&self, dirs: &[String]) -> Result<Vec<ImportablePool>> {
+        match self.backend.importable(dirs) {
+            Ok(out) => Ok(parse_importable(&out)),
+            Err(e) => {
+                error!("Listing importable pools: {}", e.to_string());
+                Err(e)
+            }
+        }
+    }
+
+    pub fn import(&self, info: &ImportPool) -> Result<()> {
+        // pools can be imported by the numeric id importable() reports
+        if info.name.is_empty() || !info.name.bytes().all(|b| b.is_ascii_digit()) {
+            check_pool_name(&info.name)?;
+        }
+
+        if let Some(new_name) = &info.new_name {
+            check_pool_name(new_name)?;
+        }
+
+        if let Err(e) =
+            self.backend
+                .import(&info.name, info.new_name.as_deref(), &info.dirs, info.force)
+        {
+            error!("Importing pool: {}", e.to_string());
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub fn export(&self, info: &ExportPool) -> Result<()> {
+        check_pool_name(&info.name)?;
+
+        if let Err(e) = self.backend.export(&info.name, info.force) {
+            error!("Exporting pool: {}", e.to_string());
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub fn add_vdev(&self, info: &AddVdev) -> Result<()> {
+        for device in &info.devices {
+            check_device(device)?;
+        }
+
+        let args = info.args()?;
+
+        if let Err(e) = self
+            .backend
+            .add_vdev(&self.name, args, &info.devices, info.force)
+        {
+            error!("Adding vdev: {}", e.to_string());
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub fn attach(&self, info: &AttachDevice) -> Result<()> {
+        check_device(&info.device)?;
+        check_device(&info.new_device)?;
+
+        if let Err(e) = self
+            .backend
+            .attach(&self.name, &info.device, &info.new_device, info.force)
+        {
+            error!("Attaching device: {}", e.to_string());
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub fn detach(&self, device: String) -> Result<()> {
+        check_device(&device)?;
+
+        if let Err(e) = self.backend.detach(&self.name, &device) {
+            error!("Detaching device: {}", e.to_string());
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub fn replace(&self, info: &ReplaceDevice) -> Result<()> {
+        check_device(&info.device)?;
+
+        if let Some(new_device) = &info.new_device {
+            check_device(new_device)?;
+        }
+
+        if let Err(e) = self.backend.replace(
+            &self.name,
+            &info.device,
+            info.new_device.as_deref(),
+            info.force,
+        ) {
+            error!("Replacing device: {}", e.to_string());
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub fn online(&self, info: &OnlineDevice) -> Result<()> {
+        check_device(&info.device)?;
+
+        if let Err(e) = self.backend.online(&self.name, &info.device, info.expand) {
+            error!("Onlining device: {}", e.to_string());
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub fn offline(&self, info: &OfflineDevice) -> Result<()> {
+        check_device(&info.device)?;
+
+        if let Err(e) = self
+            .backend
+            .offline(&self.name, &info.device, info.temporary)
+        {
+            error!("Offlining device: {}", e.to_string());
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub fn clear(&self, device: Option<String>) -> Result<()> {
+        if let Some(device) = &device {
+            check_device(device)?;
+        }
+
+        if let Err(e) = self.backend.clear(&self.name, device.as_deref()) {
+            error!("Clearing errors: {}", e.to_string());
+            return Err(e);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CommandOptions(HashMap<String, String>);
+
+// -r or -R, for zfs destroy
+fn destroy_recursion(recursive: bool, clones: bool) -> Vec<String> {
+    if clones {
+        vec!["-R".to_string()]
+    } else if recursive {
+        vec!["-r".to_string()]
+    } else {
+        Vec::new()
+    }
+}
+
+// where udev links zvols, as /dev/zvol/<pool>/<name>
+const ZVOL_DEVICE_ROOT: &str = "/dev/zvol";
+const DEVICE_WAIT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+// listed for every entry; ZFSStat's fields up to encryption_root come from these
+const LIST_PROPERTIES: &[&str] = &[
+    "used",
+    "available",
+    "referenced",
+    "mountpoint",
+    "origin",
+    "keystatus",
+    "encryptionroot",
+    "volsize",
+    "quota",
+];
+
+const BASE_LIST_FIELDS: &[&str] = &[
+    "kind",
+    "name",
+    "full_name",
+    "size",
+    "used",
+    "avail",
+    "refer",
+    "mountpoint",
+    "origin",
+    "device",
+    "keystatus",
+    "encryption_root",
+];
+
+// the optional ZFSStat fields a listing can ask for, and the properties behind them
+const LIST_FIELDS: &[(&str, &str)] = &[
+    ("quota", "quota"),
+    ("refquota", "refquota"),
+    ("reservation", "reservation"),
+    ("refreservation", "refreservation"),
+    ("compression", "compression"),
+    ("compressratio", "compressratio"),
+    ("creation", "creation"),
+    ("used_by_snapshots", "usedbysnapshots"),
+    ("logical_used", "logicalused"),
+    ("recordsize", "recordsize"),
+    ("atime", "atime"),
+    ("canmount", "canmount"),
+    ("sharenfs", "sharenfs"),
+    ("sharesmb", "sharesmb"),
+    ("volblocksize", "volblocksize"),
+];
+
+// what SpaceStat is made of
+const SPACE_PROPERTIES: &[&str] = &[
+    "used",
+    "available",
+    "usedbysnapshots",
+    "usedbydataset",
+    "usedbychildren",
+    "usedbyrefreservation",
+    "logicalused",
+    "compressratio",
+];
+
+// properties Dataset exposes as fields; they can't also be passed as extra properties
+const DATASET_PROPERTIES: &[&str] = &[
+    "quota",
+    "compression",
+    "recordsize",
+    "reservation",
+    "refreservation",
+    "refquota",
+    "atime",
+    "mountpoint",
+    "canmount",
+    "encryption",
+    "keyformat",
+    "keylocation",
+];
+
+impl Snapshot {
+    fn check(&self, pool: &str) -> Result<DatasetName> {
+        DatasetName::check_component("snapshot", &self.name)?;
+        DatasetName::within(pool, &self.dataset)
+    }
+}
+
+impl Hold {
+    fn check(&self, pool: &str) -> Result<DatasetName> {
+        DatasetName::check_component("snapshot", &self.snapshot)?;
+        DatasetName::check_component("hold tag", &self.tag)?;
+        DatasetName::within(pool, &self.dataset)
+    }
+}
+
+impl Bookmark {
+    fn check(&self, pool: &str) -> Result<DatasetName> {
+        DatasetName::check_component("bookmark", &self.name)?;
+        DatasetName::within(pool, &self.dataset)
+    }
+}
+
+impl Dataset {
+    fn options(&self) -> Result<CommandOptions> {
+        let mut options = CommandOptions::default();
+
+        if let Some(quota) = &self.quota {
+            options.insert("quota".to_string(), format!("{}", quota));
+        }
+
+        if let Some(compression) = &self.compression {
+            if !valid_compression(compression) {
+                return Err(anyhow!("invalid compression '{}'", compression));
+            }
+            options.insert("compression".to_string(), compression.clone());
+        }
+
+        if let Some(recordsize) = &self.recordsize {
+            // zfs allows powers of two from 512 bytes up to 16M
+            if !recordsize.is_power_of_two() || !(512..=16 * 1024 * 1024).contains(recordsize) {
+                return Err(anyhow!("invalid recordsize '{}'", recordsize));
+            }
+            options.insert("recordsize".to_string(), format!("{}", recordsize));
+        }
+
+        if let Some(reservation) = &self.reservation {
+            options.insert("reservation".to_string(), format!("{}", reservation));
+        }
+
+        if let Some(refreservation) = &self.refreservation {
+            options.insert("refreservation".to_string(), format!("{}", refreservation));
+        }
+
+        if let Some(refquota) = &self.refquota {
+            options.insert("refquota".to_string(), format!("{}", refquota));
+        }
+
+        if let Some(atime) = &self.atime {
+            options.insert(
+                "atime".to_string(),
+                if *atime { "on" } else { "off" }.to_string(),
+            );
+        }
+
+        if let Some(mountpoint) = &self.mountpoint {
+            if !mountpoint.starts_with('/') && mountpoint != "none" && mountpoint != "legacy" {
+                return Err(anyhow!("invalid mountpoint '{}'", mountpoint));
+            }
+            options.insert("mountpoint".to_string(), mountpoint.clone());
+        }
+
+        if let Some(canmount) = &self.canmount {
+            options.insert("canmount".to_string(), canmount.to_string());
+        }
+
+        for (key, value) in &self.properties {
+            if DATASET_PROPERTIES.contains(&key.as_str()) {
+                return Err(anyhow!("property '{}' must be set by its own field", key));
+            }
+
+            check_property_name(key)?;
+            options.insert(key.clone(), value.clone());
+        }
+
+        if let Some(keyformat) = &self.keyformat {
+            options.insert("keyformat".to_string(), keyformat.clone());
+        }
+
+        if let Some(keylocation) = &self.keylocation {
+            options.insert("keylocation".to_string(), keylocation.clone());
+        }
+
+        validate_encryption(self.encryption.as_deref(), &mut options, self.key.is_some())?;
+
+        Ok(options)
+    }
+}
+
+impl Volume {
+    fn options(&self) -> Result<CommandOptions> {
+        let mut options = CommandOptions::default();
+
+        if let Some(volblocksize) = &self.volblocksize {
+            if !volblocksize.is_power_of_two() || !(512..=16 * 1024 * 1024).contains(volblocksize) {
+                return Err(anyhow!("invalid volblocksize '{}'", volblocksize));
+            }
+            options.insert("volblocksize".to_string(), format!("{}", volblocksize));
+        }
+
+        if let Some(volmode) = &self.volmode {
+            if !matches!(
+                volmode.as_str(),
+                "default" | "full" | "geom" | "dev" | "none"
+            ) {
+                return Err(anyhow!("invalid volmode '{}'", volmode));
+            }
+            options.insert("volmode".to_string(), volmode.clone());
+        }
+
+        if let Some(compression) = &self.compression {
+            if !valid_compression(compression) {
+                return Err(anyhow!("invalid compression '{}'", compression));
+            }
+            options.insert("compression".to_string(), compression.clone());
+        }
+
+        if let Some(keyformat) = &self.keyformat {
+            options.insert("keyformat".to_string(), keyformat.clone());
+        }
+
+        if let Some(keylocation) = &self.keylocation {
+            options.insert("keylocation".to_string(), keylocation.clone());
+        }
+
+        validate_encryption(self.encryption.as_deref(), &mut options, self.key.is_some())?;
+
+        Ok(options)
+    }
+}
+
+impl AddVdev {
+    // everything between the pool name and the devices on the zpool add command line
+    fn args(&self) -> Result<Vec<String>> {
+        if self.devices.is_empty() {
+            return Err(anyhow!("at least one device is required"));
+        }
+
+        let mut args = Vec::new();
+
+        match self.class {
+            VdevClass::Data => {}
+            VdevClass::Log => args.push("log".to_string()),
+            VdevClass::Cache => args.push("cache".to_string()),
+            VdevClass::Spare => args.push("spare".to_string()),
+            VdevClass::Special => args.push("special".to_string()),
+            VdevClass::Dedup => args.push("dedup".to_string()),
+        }
+
+        // the minimum is one more device than the redundancy the layout provides
+        let (layout, minimum) = match self.layout {
+            VdevLayout::Stripe => return Ok(args),
+            VdevLayout::Mirror => ("mirror", 2),
+            VdevLayout::Raidz1 => ("raidz1", 2),
+            VdevLayout::Raidz2 => ("raidz2", 3),
+            VdevLayout::Raidz3 => ("raidz3", 4),
+        };
+
+        if matches!(self.class, VdevClass::Cache | VdevClass::Spare) {
+            return Err(anyhow!(
+                "cache and spare devices cannot be grouped into a {}",
+                layout
+            ));
+        }
+
+        if self.devices.len() < minimum {
+            return Err(anyhow!(
+                "{} requires at least {} devices, {} given",
+                layout,
+                minimum,
+                self.devices.len()
+            ));
+        }
+
+        args.push(layout.to_string());
+        Ok(args)
+    }
+}
+
+// zpool import has no json output; this picks the summary fields out of each pool's stanza and
+// ignores the config tree beneath them.
+// when a policy snapshot was taken, in UTC: 2006-01-02-150405
+fn snapshot_timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (days, secs) = ((secs / 86400) as i64, secs % 86400);
+
+    // days since the epoch to a civil date, from Howard Hinnant's date algorithms
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}-{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+// one line of zfs diff -H -F: change, file type, path and, for renames, the new path
+fn parse_diff_line(line: &[u8]) -> Result<DiffEntry> {
+    let line = String::from_utf8_lossy(line.trim_ascii_end()).to_string();
+    let fields: Vec<&str> = line.split('\t').collect();
+
+    let (change, file_type, path, new_path) = match fields.as_slice() {
+        [change, file_type, path] => (change, file_type, path, None),
+        [change, file_type, path, new_path] => (change, file_type, path, Some(new_path)),
+        _ => return Err(anyhow!("unexpected zfs diff output '{}'", line)),
+    };
+
+    Ok(DiffEntry {
+        change: match *change {
+            "+" => DiffChange::Added,
+            "-" => DiffChange::Removed,
+            "M" => DiffChange::Modified,
+            "R" => DiffChange::Renamed,
+            x => return Err(anyhow!("unknown change '{}' in zfs diff output", x)),
+        },
+        file_type: match *file_type {
+            "F" => FileType::File,
+            "/" => FileType::Directory,
+            "@" => FileType::Symlink,
+            "|" => FileType::Pipe,
+            "=" => FileType::Socket,
+            ">" => FileType::Door,
+            "B" => FileType::BlockDevice,
+            "C" => FileType::CharDevice,
+            "P" => FileType::EventPort,
+            x => return Err(anyhow!("unknown file type '{}' in zfs diff output", x)),
+        },
+        path: unescape_diff_path(path),
+        new_path: new_path.map(|x| unescape_diff_path(x)),
+    })
+}
+
+// zfs diff writes spaces, backslashes and anything unprintable in paths as \ and four octal
+// digits
+fn unescape_diff_path(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let escape = bytes
+            .get(i + 1..i + 5)
+            .filter(|x| bytes[i] == b'\\' && x.iter().all(|b| (b'0'..=b'7').contains(b)));
+
+        match escape.and_then(|x| u8::from_str_radix(std::str::from_utf8(x).ok()?, 8).ok()) {
+            Some(b) => {
+                out.push(b);
+                i += 5;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&out).to_string()
+}
+
+fn parse_importable(out: &str) -> Vec<ImportablePool> {
+    let mut ret = Vec::new();
+    let mut fields: Vec<(String, String)> = Vec::new();
+
+    let mut finish = |fields: &mut Vec<(String, String)>| {
+        let get = |key: &str| {
+            fields
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.clone())
+        };
+
+        if let (Some(name), Some(id)) = (get("pool"), get("id")) {
+            ret.push(ImportablePool {
+                name,
+                id,
+                health: get("state").unwrap_or_default().as_str().into(),
+                status: get("status"),
+                action: get("action"),
+            });
+        }
+
+        fields.clear();
+    };
+
+    let mut in_config = false;
+
+    for line in out.lines() {
+        let trimmed = line.trim();
+
+        match trimmed.split_once(':') {
+            Some((key, value))
+                if !key.is_empty()
+                    && key.chars().all(|c| c.is_ascii_lowercase())
+                    && (value.is_empty() || value.starts_with(' '))
+                    && !line.starts_with('\t') =>
+            {
+                if key == "pool" {
+                    finish(&mut fields);
+                    in_config = false;
+                }
+
+                in_config = in_config || key == "config";
+                if !in_config {
+                    fields.push((key.to_string(), value.trim().to_string()));
+                }
+            }
+            _ => {
+                if in_config || trimmed.is_empty() {
+                    continue;
+                }
+
+                // long messages wrap onto indented lines of their own
+                if let Some((_, value)) = fields.last_mut() {
+                    value.push(' ');
+                    value.push_str(trimmed);
+                }
+            }
+        }
+    }
+
+    finish(&mut fields);
+    ret
+}
+
+// checks the encryption settings already placed in options, adding the cipher, and pointing
+// keylocation at stdin when the key is supplied by the caller.
+fn validate_encryption(
+    encryption: Option<&str>,
+    options: &mut CommandOptions,
+    has_key: bool,
+) -> Result<()> {
+    if let Some(encryption) = encryption {
+        if !matches!(
+            encryption,
+            "on" | "off"
+                | "aes-128-ccm"
+                | "aes-192-ccm"
+                | "aes-256-ccm"
+                | "aes-128-gcm"
+                | "aes-192-gcm"
+                | "aes-256-gcm"
+        ) {
+            return Err(anyhow!("invalid encryption '{}'", encryption));
+        }
+        options.insert("encryption".to_string(), encryption.to_string());
+    }
+
+    if let Some(keyformat) = options
+        .get("keyformat")
+        .filter(|x| !matches!(x.as_str(), "raw" | "hex" | "passphrase"))
+    {
+        return Err(anyhow!("invalid keyformat '{}'", keyformat));
+    }
+
+    match options.get("keylocation") {
+        Some(keylocation) if keylocation == "prompt" => {}
+        Some(keylocation) if has_key => {
+            return Err(anyhow!(
+                "keylocation must be 'prompt' when a key is supplied, not '{}'",
+                keylocation
+            ));
+        }
+        Some(keylocation)
+            if !keylocation.starts_with("file:///")
+                && !keylocation.starts_with("https://")
+                && !keylocation.starts_with("http://") =>
+        {
+            return Err(anyhow!("invalid keylocation '{}'", keylocation));
+        }
+        Some(_) => {}
+        None if has_key => {
+            options.insert("keylocation".to_string(), "prompt".to_string());
+        }
+        None => {}
+    }
+
+    Ok(())
+}
+
+fn valid_compression(compression: &str) -> bool {
+    match compression.split_once('-') {
+        None => matches!(
+            compression,
+            "on" | "off" | "lz4" | "lzjb" | "gzip" | "zle" | "zstd" | "zstd-fast"
+        ),
+        Some(("gzip", level)) => level.parse::<u8>().is_ok_and(|l| (1..=9).contains(&l)),
+        Some(("zstd", "fast")) => true,
+        Some(("zstd", level)) => match level.strip_prefix("fast-") {
+            Some(level) => level.parse::<u16>().is_ok_and(|l| (1..=1000).contains(&l)),
+            None => level.parse::<u8>().is_ok_and(|l| (1..=19).contains(&l)),
+        },
+        Some(_) => false,
+    }
+}
+
+impl CommandOptions {
+    fn to_options(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        for (key, value) in &self.0 {
+            args.push("-o".to_string());
+            args.push(format!("{}={}", key, value));
+        }
+        args
+    }
+}
+
+impl std::ops::Deref for CommandOptions {
+    type Target = HashMap<String, String>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl std::ops::DerefMut for CommandOptions {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+// size of each read from a zfs send stream
+pub(crate) const STREAM_CHUNK_SIZE: usize = 128 * 1024;
+
+/// A running `zfs send` or `zfs receive`. Read from it for the former, write to it for the
+/// latter, and call `finish` to collect the exit status. Dropping it early kills the process.
+#[derive(Debug)]
+pub struct ZFSPipe {
+    child: Option<std::process::Child>,
+    command: String,
+    // read as the command runs, so it never blocks writing to a full pipe mid-stream
+    stderr: Option<std::thread::JoinHandle<std::io::Result<Vec<u8>>>>,
+}
+
+// stderr is only wanted for the error at the end, so a pipe keeps no more than this of it
+const PIPE_STDERR_TAIL: usize = 64 * 1024;
+
+fn drain_stderr_tail(
+    mut pipe: std::process::ChildStderr,
+) -> std::thread::JoinHandle<std::io::Result<Vec<u8>>> {
+    std::thread::spawn(move || {
+        let mut tail = Vec::new();
+        let mut buf = [0u8; 8192];
+        loop {
+            let n = pipe.read(&mut buf)?;
+            if n == 0 {
+                return Ok(tail);
+            }
+
+            tail.extend_from_slice(&buf[..n]);
+            if tail.len() > PIPE_STDERR_TAIL {
+                tail.drain(..tail.len() - PIPE_STDERR_TAIL);
+            }
+        }
+    })
+}
+
+impl ZFSPipe {
+    pub fn finish(mut self) -> Result<()> {
+        let Some(mut child) = self.child.take() else {
+            return Ok(());
+        };
+
+        // close stdin so receive sees the end of the stream
+        drop(child.stdin.take());
+
+        let out = child.wait_with_output()?;
+        let stderr = match self.stderr.take() {
+            Some(handle) => handle
+                .join()
+                .map_err(|_| anyhow!("reading command output"))??,
+            None => out.stderr,
+        };
+
+        if out.status.success() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Error: {}",
+                String::from_utf8_lossy(stderr.trim_ascii())
+            ))
+        }
+    }
+}
+
+impl Read for ZFSPipe {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self.child.as_mut().and_then(|c| c.stdout.as_mut()) {
+            Some(stdout) => stdout.read(buf),
+            None => Ok(0),
+        }
+    }
+}
+
+impl Write for ZFSPipe {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self.child.as_mut().and_then(|c| c.stdin.as_mut()) {
+            Some(stdin) => stdin.write(buf),
+            None => Err(std::io::ErrorKind::BrokenPipe.into()),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self.child.as_mut().and_then(|c| c.stdin.as_mut()) {
+            Some(stdin) => stdin.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A running `zfs diff`, read an entry at a time. Call `finish` once it runs out to collect the
+/// exit status.
+#[derive(Debug)]
+pub struct ZFSDiff {
+    reader: std::io::BufReader<ZFSPipe>,
+}
+
+impl ZFSDiff {
+    pub fn finish(self) -> Result<()> {
+        self.reader.into_inner().finish()
+    }
+}
+
+impl Iterator for ZFSDiff {
+    type Item = Result<DiffEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = Vec::new();
+        match self.reader.read_until(b'\n', &mut line) {
+            Ok(0) => None,
+            Ok(_) => Some(parse_diff_line(&line)),
+            Err(e) => Some(Err(e.into())),
+        }
+    }
+}
+
+impl Drop for ZFSPipe {
+    fn drop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            debug!("Killing unfinished command: [{}]", self.command);
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+// Everything Pool needs from ZFS. Controller drives the real zfs and zpool commands; the
+// in-memory backend in the mock module stands in for it where there are no pools to work against.
+pub trait ZfsBackend: std::fmt::Debug + Clone + Send + Sync + 'static {
+    // everything under root of the given zfs types, down to depth levels below it
+    fn list(
+        &self,
+        root: &str,
+        depth: Option<u32>,
+        types: &[&str],
+        properties: &[String],
+    ) -> Result<ZFSList>;
+    // recursive is -r, clones is -R, force is -f
+    fn destroy(
+        &self,
+        pool: &str,
+        name: &str,
+        recursive: bool,
+        clones: bool,
+        force: bool,
+    ) -> Result<()>;
+    // zfs destroy -nvp output
+    fn destroy_dry_run(
+        &self,
+        pool: &str,
+        name: &str,
+        recursive: bool,
+        clones: bool,
+    ) -> Result<String>;
+    fn list_snapshots(&self) -> Result<ZFSSnapshotList>;
+    fn snapshot(&self, pool: &str, dataset: &str, name: &str, recursive: bool) -> Result<()>;
+    fn destroy_snapshot(
+        &self,
+        pool: &str,
+        dataset: &str,
+        name: &str,
+        recursive: bool,
+    ) -> Result<()>;
+    fn hold(&self, pool: &str, dataset: &str, name: &str, tag: &str, recursive: bool)
+        -> Result<()>;
+    fn release(
+        &self,
+        pool: &str,
+        dataset: &str,
+        name: &str,
+        tag: &str,
+        recursive: bool,
+    ) -> Result<()>;
+    // zfs holds -H -p output
+    fn holds(&self, pool: &str, dataset: &str, name: &str, recursive: bool) -> Result<String>;
+    fn bookmark(&self, pool: &str, dataset: &str, snapshot: &str, name: &str) -> Result<()>;
+    // zfs {user,group,project}space -H -p output: name, used, quota, objused, objquota
+    fn user_space(&self, pool: &str, name: &str, kind: QuotaKind) -> Result<String>;
+    fn destroy_bookmark(&self, pool: &str, dataset: &str, name: &str) -> Result<()>;
+    fn clone_snapshot(&self, pool: &str, dataset: &str, name: &str, target: &str) -> Result<()>;
+    fn promote(&self, pool: &str, name: &str) -> Result<()>;
+    fn send(&self, pool: &str, dataset: &str, name: &str, from: Option<&str>) -> Result<ZFSPipe>;
+    fn receive(&self, pool: &str, name: &str, force: bool) -> Result<ZFSPipe>;
+    // runs zfs diff -H -F from a snapshot to a later one, or to the live dataset
+    fn diff(&self, pool: &str, dataset: &str, from: &str, to: Option<&str>) -> Result<ZFSPipe>;
+    fn rollback(&self, pool: &str, dataset: &str, name: &str, destroy_newer: bool) -> Result<()>;
+    fn create_dataset(
+        &self,
+        pool: &str,
+        name: &str,
+        options: Option<CommandOptions>,
+        key: Option<&[u8]>,
+    ) -> Result<()>;
+    fn load_key(&self, pool: &str, name: &str, key: Option<&[u8]>) -> Result<()>;
+    fn unload_key(&self, pool: &str, name: &str) -> Result<()>;
+    fn change_key(
+        &self,
+        pool: &str,
+        name: &str,
+        options: CommandOptions,
+        key: Option<&[u8]>,
+    ) -> Result<()>;
+    fn rename(&self, pool: &str, orig: &str, new: &str) -> Result<()>;
+    fn set(&self, pool: &str, name: &str, properties: HashMap<&str, String>) -> Result<()>;
+    fn get<T>(&self, pool: &str, name: &str, property: &str) -> Result<T>
+    where
+        T: for<'de> serde::Deserialize<'de> + FromStr + Send + Sync + Clone,
+        T::Err: ToString;
+    fn get_all(
+        &self,
+        pool: &str,
+        name: &str,
+        properties: &[String],
+    ) -> Result<ZFSGet<serde_json::Value>>;
+    fn inherit(&self, pool: &str, name: &str, property: &str, recursive: bool) -> Result<()>;
+    fn mount(&self, pool: &str) -> Result<()>;
+    fn unmount(&self, pool: &str, name: &str) -> Result<()>;
+    fn create_volume(
+        &self,
+        pool: &str,
+        name: &str,
+        size: u64,
+        sparse: bool,
+        options: Option<CommandOptions>,
+        key: Option<&[u8]>,
+    ) -> Result<()>;
+    fn wait_for_device(&self, path: &str) -> Result<()>;
+    fn pool_status(&self, pool: &str) -> Result<ZPoolStatus>;
+    fn pool_list(&self, pool: &str) -> Result<ZPoolList>;
+    fn scrub(&self, pool: &str, flag: Option<&str>) -> Result<()>;
+    fn trim(&self, pool: &str, flag: Option<&str>) -> Result<()>;
+    fn importable(&self, dirs: &[String]) -> Result<String>;
+    fn import(
+        &self,
+        name: &str,
+        new_name: Option<&str>,
+        dirs: &[String],
+        force: bool,
+    ) -> Result<()>;
+    fn export(&self, name: &str, force: bool) -> Result<()>;
+    fn add_vdev(
+        &self,
+        pool: &str,
+        vdev: Vec<String>,
+        devices: &[String],
+        force: bool,
+    ) -> Result<()>;
+    fn attach(&self, pool: &str, device: &str, new_device: &str, force: bool) -> Result<()>;
+    fn detach(&self, pool: &str, device: &str) -> Result<()>;
+    fn replace(
+        &self,
+        pool: &str,
+        device: &str,
+        new_device: Option<&str>,
+        force: bool,
+    ) -> Result<()>;
+    fn online(&self, pool: &str, device: &str, expand: bool) -> Result<()>;
+    fn offline(&self, pool: &str, device: &str, temporary: bool) -> Result<()>;
+    fn clear(&self, pool: &str, device: Option<&str>) -> Result<()>;
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Controller {
+    // commands still running after this long are killed
+    timeout: Option<std::time::Duration>,
+}
+
+// how often a command with a timeout is checked on
+const COMMAND_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(10);
+
+impl Controller {
+    pub fn with_timeout(timeout: Option<std::time::Duration>) -> Self {
+        Self { timeout }
+    }
+
+    fn run(&self, command: &str, args: Vec<String>) -> Result<String> {
+        self.run_with_input(command, args, None)
+    }
+
+    // input is written to the command's stdin; this is how key material reaches zfs, so it must
+    // never be logged.
+    fn run_with_input(
+        &self,
+        command: &str,
+        args: Vec<String>,
+        input: Option<&[u8]>,
+    ) -> Result<String> {
+        debug!("Running command: [{}, {}]", command, args.join(", "));
+        let time = std::time::Instant::now();
+
+        let out = match std::process::Command::new(command)
+            .args(args.clone())
+            .stdin(if input.is_some() {
+                std::process::Stdio::piped()
+            } else {
+                std::process::Stdio::null()
+            })
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .and_then(|mut child| {
+                if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+                    stdin.write_all(input)?;
+                }
+                self.wait(child)
+            }) {
+            Ok(x) => x,
+            Err(e) => {
+                error!(
+                    "Error running command: [{}, {}]: {}",
+                    command,
+                    args.join(", "),
+                    e.to_string()
+                );
+                return Err(e.into());
+            }
+        };
+
+        trace!(
+            "ZFS command took {}",
+            (std::time::Instant::now() - time).fancy_duration()
+        );
+
+        if out.status.success() {
+            Ok(String::from_utf8(out.stdout.trim_ascii().to_vec())?)
+        } else {
+            Err(anyhow!(
+                "Error: {}",
+                String::from_utf8(out.stderr.trim_ascii().to_vec())?.as_str()
+            ))
+        }
+    }
+
+    // wait_with_output, killing the child if it outlives the timeout
+    fn wait(&self, mut child: std::process::Child) -> std::io::Result<std::process::Output> {
+        let Some(timeout) = self.timeout else {
+            return child.wait_with_output();
+        };
+
+        // drain the pipes as we go, or a chatty command blocks on a full pipe and never exits
+        let drain = |pipe: Option<Box<dyn Read + Send>>| {
+            std::thread::spawn(move || -> std::io::Result<Vec<u8>> {
+                let mut buf = Vec::new();
+                if let Some(mut pipe) = pipe {
+                    pipe.read_to_end(&mut buf)?;
+                }
+                Ok(buf)
+            })
+        };
+        let stdout = drain(child.stdout.take().map(|x| Box::new(x) as _));
+        let stderr = drain(child.stderr.take().map(|x| Box::new(x) as _));
+
+        let start = std::time::Instant::now();
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+
+            if start.elapsed() >= timeout {
+                child.kill()?;
+                child.wait()?;
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    format!("timed out after {}", timeout.fancy_duration()),
+                ));
+            }
+
+            std::thread::sleep(COMMAND_POLL_INTERVAL);
+        };
+
+        let join = |handle: std::thread::JoinHandle<std::io::Result<Vec<u8>>>| {
+            handle
+                .join()
+                .map_err(|_| std::io::Error::other("reading command output"))?
+        };
+
+        Ok(std::process::Output {
+            status,
+            stdout: join(stdout)?,
+            stderr: join(stderr)?,
+        })
+    }
+
+    // like run, but hands back the process with its stdin and stdout attached for streaming
+    fn spawn(command: &str, args: Vec<String>) -> Result<ZFSPipe> {
+        debug!("Spawning command: [{}, {}]", command, args.join(", "));
+
+        let mut child = match std::process::Command::new(command)
+            .args(args.clone())
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+        {
+            Ok(x) => x,
+            Err(e) => {
+                error!(
+                    "Error spawning command: [{}, {}]: {}",
+                    command,
+                    args.join(", "),
+                    e.to_string()
+                );
+                return Err(e.into());
+            }
+        };
+
+        let stderr = child.stderr.take().map(drain_stderr_tail);
+
+        Ok(ZFSPipe {
+            child: Some(child),
+            command: format!("{}, {}", command, args.join(", ")),
+            stderr,
+        })
+    }
+}
+
+impl ZfsBackend for Controller {
+    fn list(
+        &self,
+        root: &str,
+        depth: Option<u32>,
+        types: &[&str],
+        properties: &[String],
+    ) -> Result<ZFSList> {
+        let mut args = vec![
+            "list".to_string(),
+            "-j".to_string(),
+            "--json-int".to_string(),
+            "-t".to_string(),
+            types.join(","),
+            "-o".to_string(),
+            format!("name,{}", properties.join(",")),
+            "-r".to_string(),
+        ];
+
+        if let Some(depth) = depth {
+            args.push("-d".to_string());
+            args.push(depth.to_string());
+        }
+
+        args.push(root.to_string());
+
+        Ok(serde_json::from_str(&self.run("zfs", args)?)?)
+    }
+
+    fn destroy(
+        &self,
+        pool: &str,
+        name: &str,
+        recursive: bool,
+        clones: bool,
+        force: bool,
+    ) -> Result<()> {
+        let mut args = vec!["destroy".to_string()];
+        if force {
+            args.push("-f".to_string());
+        }
+        args.append(&mut destroy_recursion(recursive, clones));
+        args.push(format!("{}/{}", pool, name));
+
+        self.run("zfs", args)?;
+        Ok(())
+    }
+
+    fn destroy_dry_run(
+        &self,
+        pool: &str,
+        name: &str,
+        recursive: bool,
+        clones: bool,
+    ) -> Result<String> {
+        let mut args = vec![
+            "destroy".to_string(),
+            "-n".to_string(),
+            "-v".to_string(),
+            "-p".to_string(),
+        ];
+        args.append(&mut destroy_recursion(recursive, clones));
+        args.push(format!("{}/{}", pool, name));
+
+        self.run("zfs", args)
+    }
+
+    fn list_snapshots(&self) -> Result<ZFSSnapshotList> {
+        Ok(serde_json::from_str(
+            &self.run(
+                "zfs",
+                [
+                    "list",
+                    "-j",
+                    "--json-int",
+                    "-t",
+                    "snapshot",
+                    "-o",
+                    "name,used,referenced,creation",
+                ]
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<String>>(),
+            )?,
+        )?)
+    }
+
+    fn snapshot(&self, pool: &str, dataset: &str, name: &str, recursive: bool) -> Result<()> {
+        let mut args = vec!["snapshot".to_string()];
+
+        if recursive {
+            args.push("-r".to_string());
+        }
+
+        args.push(format!("{}/{}@{}", pool, dataset, name));
+
+        self.run("zfs", args)?;
+        Ok(())
+    }
+
+    fn destroy_snapshot(
+        &self,
+        pool: &str,
+        dataset: &str,
+        name: &str,
+        recursive: bool,
+    ) -> Result<()> {
+        let mut args = vec!["destroy".to_string()];
+
+        if recursive {
+            args.push("-r".to_string());
+        }
+
+        args.push(format!("{}/{}@{}", pool, dataset, name));
+
+        self.run("zfs", args)?;
+        Ok(())
+    }
+
+    fn hold(
+        &self,
+        pool: &str,
+        dataset: &str,
+        name: &str,
+        tag: &str,
+        recursive: bool,
+    ) -> Result<()> {
+        let mut args = vec!["hold".to_string()];
+
+        if recursive {
+            args.push("-r".to_string());
+        }
+
+        args.push(tag.to_string());
+        args.push(format!("{}/{}@{}", pool, dataset, name));
+
+        self.run("zfs", args)?;
+        Ok(())
+    }
+
+    fn release(
+        &self,
+        pool: &str,
+        dataset: &str,
+        name: &str,
+        tag: &str,
+        recursive: bool,
+    ) -> Result<()> {
+        let mut args = vec!["release".to_string()];
+
+        if recursive {
+            args.push("-r".to_string());
+        }
+
+        args.push(tag.to_string());
+        args.push(format!("{}/{}@{}", pool, dataset, name));
+
+        self.run("zfs", args)?;
+        Ok(())
+    }
+
+    fn holds(&self, pool: &str, dataset: &str, name: &str, recursive: bool) -> Result<String> {
+        let mut args = vec!["holds".to_string(), "-H".to_string(), "-p".to_string()];
+
+        if recursive {
+            args.push("-r".to_string());
+        }
+
+        args.push(format!("{}/{}@{}", pool, dataset, name));
+
+        self.run("zfs", args)
+    }
+
+    fn bookmark(&self, pool: &str, dataset: &str, snapshot: &str, name: &str) -> Result<()> {
+        self.run(
+            "zfs",
+            vec![
+                "bookmark".to_string(),
+                format!("{}/{}@{}", pool, dataset, snapshot),
+                format!("{}/{}#{}", pool, dataset, name),
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn user_space(&self, pool: &str, name: &str, kind: QuotaKind) -> Result<String> {
+        // there's no json output for these, so ask for tab separated exact numbers instead
+        let mut args = match kind {
+            QuotaKind::User => vec!["userspace", "-n", "-t", "posixuser"],
+            QuotaKind::Group => vec!["groupspace", "-n", "-t", "posixgroup"],
+            QuotaKind::Project => vec!["projectspace"],
+        }
+        .into_iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+
+        args.append(&mut vec![
+            "-H".to_string(),
+            "-p".to_string(),
+            "-o".to_string(),
+            "name,used,quota,objused,objquota".to_string(),
+            format!("{}/{}", pool, name),
+        ]);
+
+        self.run("zfs", args)
+    }
+
+    fn destroy_bookmark(&self, pool: &str, dataset: &str, name: &str) -> Result<()> {
+        self.run(
+            "zfs",
+            vec![
+                "destroy".to_string(),
+                format!("{}/{}#{}", pool, dataset, name),
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn clone_snapshot(&self, pool: &str, dataset: &str, name: &str, target: &str) -> Result<()> {
+        self.run(
+            "zfs",
+            [
+                "clone",
+                "-p",
+                &format!("{}/{}@{}", pool, dataset, name),
+                &format!("{}/{}", pool, target),
+            ]
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<String>>(),
+        )?;
+        Ok(())
+    }
+
+    fn promote(&self, pool: &str, name: &str) -> Result<()> {
+        self.run(
+            "zfs",
+            vec!["promote".to_string(), format!("{}/{}", pool, name)],
+        )?;
+        Ok(())
+    }
+
+    fn send(&self, pool: &str, dataset: &str, name: &str, from: Option<&str>) -> Result<ZFSPipe> {
+        let mut args = vec!["send".to_string()];
+
+        if let Some(from) = from {
+            args.push("-i".to_string());
+            // bookmarks are passed with their # already on
+            if from.starts_with('#') {
+                args.push(format!("{}/{}{}", pool, dataset, from));
+            } else {
+                args.push(format!("{}/{}@{}", pool, dataset, from));
+            }
+        }
+
+        args.push(format!("{}/{}@{}", pool, dataset, name));
+
+        Self::spawn("zfs", args)
+    }
+
+    fn receive(&self, pool: &str, name: &str, force: bool) -> Result<ZFSPipe> {
+        let mut args = vec!["receive".to_string()];
+
+        if force {
+            args.push("-F".to_string());
+        }
+
+        args.push(format!("{}/{}", pool, name));
+
+        Self::spawn("zfs", args)
+    }
+
+    fn diff(&self, pool: &str, dataset: &str, from: &str, to: Option<&str>) -> Result<ZFSPipe> {
+        let full = format!("{}/{}", pool, dataset);
+
+        Self::spawn(
+            "zfs",
+            vec![
+                "diff".to_string(),
+                "-H".to_string(),
+                "-F".to_string(),
+                format!("{}@{}", full, from),
+                match to {
+                    Some(to) => format!("{}@{}", full, to),
+                    None => full.clone(),
+                },
+            ],
+        )
+    }
+
+    fn rollback(&self, pool: &str, dataset: &str, name: &str, destroy_newer: bool) -> Result<()> {
+        let mut args = vec!["rollback".to_string()];
+
+        if destroy_newer {
+            args.push("-r".to_string());
+        }
+
+        args.push(format!("{}/{}@{}", pool, dataset, name));
+
+        self.run("zfs", args)?;
+        Ok(())
+    }
+
+    fn create_dataset(
+        &self,
+        pool: &str,
+        name: &str,
+        options: Option<CommandOptions>,
+        key: Option<&[u8]>,
+    ) -> Result<()> {
+        let mut args = vec!["create".to_string(), format!("{}/{}", pool, name)];
+
+        if let Some(options) = options {
+            args.append(&mut options.to_options())
+        }
+
+        self.run_with_input("zfs", args, key)?;
+        Ok(())
+    }
+
+    fn load_key(&self, pool: &str, name: &str, key: Option<&[u8]>) -> Result<()> {
+        let mut args = vec!["load-key".to_string()];
+
+        if key.is_some() {
+            args.push("-L".to_string());
+            args.push("prompt".to_string());
+        }
+
+        args.push(format!("{}/{}", pool, name));
+
+        self.run_with_input("zfs", args, key)?;
+        Ok(())
+    }
+
+    fn unload_key(&self, pool: &str, name: &str) -> Result<()> {
+        self.run(
+            "zfs",
+            vec!["unload-key".to_string(), format!("{}/{}", pool, name)],
+        )?;
+        Ok(())
+    }
+
+    fn change_key(
+        &self,
+        pool: &str,
+        name: &str,
+        options: CommandOptions,
+        key: Option<&[u8]>,
+    ) -> Result<()> {
+        let mut args = vec!["change-key".to_string()];
+        args.append(&mut options.to_options());
+        args.push(format!("{}/{}", pool, name));
+
+        self.run_with_input("zfs", args, key)?;
+        Ok(())
+    }
+
+    fn rename(&self, pool: &str, orig: &str, new: &str) -> Result<()> {
+        let args = vec![
+            "rename",
+            "-p",
+            &format!("{}/{}", pool, orig),
+            &format!("{}/{}", pool, new),
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+
+        self.run("zfs", args)?;
+        Ok(())
+    }
+
+    fn set(&self, pool: &str, name: &str, properties: HashMap<&str, String>) -> Result<()> {
+        if properties.is_empty() {
+            return Ok(());
+        }
+
+        let mut args = vec!["set".to_string()];
+
+        for (key, value) in &properties {
+            args.push(format!("{}={}", key, value));
+        }
+
+        args.push(format!("{}/{}", pool, name));
+
+        self.run("zfs", args)?;
+        Ok(())
+    }
+
+    fn get<T>(&self, pool: &str, name: &str, property: &str) -> Result<T>
+    where
+        T: for<'de> serde::Deserialize<'de> + FromStr + Send + Sync + Clone,
+        T::Err: ToString,
+    {
+        let args = vec![
+            "get".to_string(),
+            "-j".to_string(),
+            "--json-int".to_string(),
+            property.to_string(),
+            format!("{}/{}", pool, name),
+        ];
+
+        let out: ZFSGet<T> = serde_json::from_str(&self.run("zfs", args)?)?;
+
+        Ok(
+            out.datasets[&format!("{}/{}", pool, name)].properties[property]
+                .value
+                .clone(),
+        )
+    }
+
+    // property values come back as strings or integers depending on the property
+    fn get_all(
+        &self,
+        pool: &str,
+        name: &str,
+        properties: &[String],
+    ) -> Result<ZFSGet<serde_json::Value>> {
+        let properties = if properties.is_empty() {
+            "all".to_string()
+        } else {
+            properties.join(",")
+        };
+
+        let args = vec![
+            "get".to_string(),
+            "-j".to_string(),
+            "--json-int".to_string(),
+            properties,
+            format!("{}/{}", pool, name),
+        ];
+
+        Ok(serde_json::from_str(&self.run("zfs", args)?)?)
+    }
+
+    fn inherit(&self, pool: &str, name: &str, property: &str, recursive: bool) -> Result<()> {
+        let mut args = vec!["inherit".to_string()];
+
+        if recursive {
+            args.push("-r".to_string());
+        }
+
+        args.push(property.to_string());
+        args.push(format!("{}/{}", pool, name));
+
+        self.run("zfs", args)?;
+        Ok(())
+    }
+
+    fn mount(&self, pool: &str) -> Result<()> {
+        self.run(
+            "zfs",
+            vec!["mount", "-R", pool]
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<String>>(),
+        )?;
+        Ok(())
+    }
+
+    fn unmount(&self, pool: &str, name: &str) -> Result<()> {
+        self.run(
+            "zfs",
+            vec!["unmount", "-f", &format!("{}/{}", pool, name)]
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<String>>(),
+        )?;
+        Ok(())
+    }
+
+    fn create_volume(
+        &self,
+        pool: &str,
+        name: &str,
+        size: u64, // 640k aughta be enough for anybody
+        sparse: bool,
+        options: Option<CommandOptions>,
+        key: Option<&[u8]>,
+    ) -> Result<()> {
+        let mut args = vec!["create".to_string()];
+
+        if sparse {
+            args.push("-s".to_string());
+        }
+
+        args.append(&mut vec![
+            "-V".to_string(),
+            format!("{}", size),
+            format!("{}/{}", pool, name),
+        ]);
+
+        if let Some(options) = options {
+            args.append(&mut options.to_options())
+        }
+
+        self.run_with_input("zfs", args, key)?;
+        Ok(())
+    }
+
+    fn wait_for_device(&self, path: &str) -> Result<()> {
+        let start = std::time::Instant::now();
+
+        while !std::fs::exists(path)? {
+            if start.elapsed() > DEVICE_WAIT_TIMEOUT {
+                return Err(anyhow!("timed out waiting for device {}", path));
+            }
+
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        }
+
+        Ok(())
+    }
+
+    fn pool_status(&self, pool: &str) -> Result<ZPoolStatus> {
+        Ok(serde_json::from_str(&self.run(
+            "zpool",
+            vec![
+                "status".to_string(),
+                "-j".to_string(),
+                "--json-int".to_string(),
+                "-t".to_string(),
+                pool.to_string(),
+            ],
+        )?)?)
+    }
+
+    fn pool_list(&self, pool: &str) -> Result<ZPoolList> {
+        Ok(serde_json::from_str(&self.run(
+            "zpool",
+            vec![
+                "list".to_string(),
+                "-j".to_string(),
+                "--json-int".to_string(),
+                "-o".to_string(),
+                "size,allocated,free,capacity,fragmentation,dedupratio,health".to_string(),
+                pool.to_string(),
+            ],
+        )?)?)
+    }
+
+    // flag is passed straight through: -p pauses and -s stops a scrub, none starts or resumes one
+    fn scrub(&self, pool: &str, flag: Option<&str>) -> Result<()> {
+        let mut args = vec!["scrub".to_string()];
+        if let Some(flag) = flag {
+            args.push(flag.to_string());
+        }
+        args.push(pool.to_string());
+
+        self.run("zpool", args)?;
+        Ok(())
+    }
+
+    // -s suspends and -c cancels a TRIM, none starts or resumes one
+    fn trim(&self, pool: &str, flag: Option<&str>) -> Result<()> {
+        let mut args = vec!["trim".to_string()];
+        if let Some(flag) = flag {
+            args.push(flag.to_string());
+        }
+        args.push(pool.to_string());
+
+        self.run("zpool", args)?;
+        Ok(())
+    }
+
+    fn importable(&self, dirs: &[String]) -> Result<String> {
+        let mut args = vec!["import".to_string()];
+        for dir in dirs {
+            args.append(&mut vec!["-d".to_string(), dir.to_string()]);
+        }
+
+        match self.run("zpool", args) {
+            Ok(out) => Ok(out),
+            // not finding anything is reported as a failure
+            Err(e) if e.to_string().contains("no pools available to import") => Ok(String::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn import(
+        &self,
+        name: &str,
+        new_name: Option<&str>,
+        dirs: &[String],
+        force: bool,
+    ) -> Result<()> {
+        let mut args = vec!["import".to_string()];
+        for dir in dirs {
+            args.append(&mut vec!["-d".to_string(), dir.to_string()]);
+        }
+
+        if force {
+            args.push("-f".to_string());
+        }
+
+        args.push(name.to_string());
+
+        if let Some(new_name) = new_name {
+            args.push(new_name.to_string());
+        }
+
+        self.run("zpool", args)?;
+        Ok(())
+    }
+
+    fn export(&self, name: &str, force: bool) -> Result<()> {
+        let mut args = vec!["export".to_string()];
+        if force {
+            args.push("-f".to_string());
+        }
+        args.push(name.to_string());
+
+        self.run("zpool", args)?;
+        Ok(())
+    }
+
+    fn add_vdev(
+        &self,
+        pool: &str,
+        mut vdev: Vec<String>,
+        devices: &[String],
+        force: bool,
+    ) -> Result<()> {
+        let mut args = vec!["add".to_string()];
+        if force {
+            args.push("-f".to_string());
+        }
+        args.push(pool.to_string());
+        args.append(&mut vdev);
+        args.extend(devices.iter().cloned());
+
+        self.run("zpool", args)?;
+        Ok(())
+    }
+
+    fn attach(&self, pool: &str, device: &str, new_device: &str, force: bool) -> Result<()> {
+        let mut args = vec!["attach".to_string()];
+        if force {
+            args.push("-f".to_string());
+        }
+        args.append(&mut vec![
+            pool.to_string(),
+            device.to_string(),
+            new_device.to_string(),
+        ]);
+
+        self.run("zpool", args)?;
+        Ok(())
+    }
+
+    fn detach(&self, pool: &str, device: &str) -> Result<()> {
+        self.run(
+            "zpool",
+            vec!["detach".to_string(), pool.to_string(), device.to_string()],
+        )?;
+        Ok(())
+    }
+
+    fn replace(
+        &self,
+        pool: &str,
+        device: &str,
+        new_device: Option<&str>,
+        force: bool,
+    ) -> Result<()> {
+        let mut args = vec!["replace".to_string()];
+        if force {
+            args.push("-f".to_string());
+        }
+        args.append(&mut vec![pool.to_string(), device.to_string()]);
+
+        if let Some(new_device) = new_device {
+            args.push(new_device.to_string());
+        }
+
+        self.run("zpool", args)?;
+        Ok(())
+    }
+
+    fn online(&self, pool: &str, device: &str, expand: bool) -> Result<()> {
+        let mut args = vec!["online".to_string()];
+        if expand {
+            args.push("-e".to_string());
+        }
+        args.append(&mut vec![pool.to_string(), device.to_string()]);
+
+        self.run("zpool", args)?;
+        Ok(())
+    }
+
+    fn offline(&self, pool: &str, device: &str, temporary: bool) -> Result<()> {
+        let mut args = vec!["offline".to_string()];
+        if temporary {
+            args.push("-t".to_string());
+        }
+        args.append(&mut vec![pool.to_string(), device.to_string()]);
+
+        self.run("zpool", args)?;
+        Ok(())
+    }
+
+    fn clear(&self, pool: &str, device: Option<&str>) -> Result<()> {
+        let mut args = vec!["clear".to_string(), pool.to_string()];
+        if let Some(device) = device {
+            args.push(device.to_string());
+        }
+
+        self.run("zpool", args)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
 mod tests {
+    // runs without zfs; only the commands are real
+    mod command {
+        use super::super::Controller;
+        use std::io::Read;
+
+        #[test]
+        fn test_controller_timeout() {
+            let controller = Controller::with_timeout(Some(std::time::Duration::from_millis(200)));
+            assert_eq!(
+                controller.run("echo", vec!["quick".to_string()]).unwrap(),
+                "quick"
+            );
+
+            let start = std::time::Instant::now();
+            let err = controller.run("sleep", vec!["5".to_string()]).unwrap_err();
+            assert!(err.to_string().contains("timed out"));
+            assert!(start.elapsed() < std::time::Duration::from_secs(2));
+        }
+
+        #[test]
+        fn test_pipe_stderr() {
+            // far more stderr than a pipe holds, written before any output
+            let script = "head -c 1000000 /dev/zero | tr '\\0' x >&2; echo done";
+            let (tx, rx) = std::sync::mpsc::channel();
+            std::thread::spawn(move || {
+                let mut pipe =
+                    Controller::spawn("sh", vec!["-c".to_string(), script.to_string()]).unwrap();
+                let mut out = String::new();
+                pipe.read_to_string(&mut out).unwrap();
+                let _ = tx.send((out, pipe.finish().is_ok()));
+            });
+            let (out, ok) = rx
+                .recv_timeout(std::time::Duration::from_secs(10))
+                .expect("command blocked on its stderr");
+            assert_eq!(out, "done\n");
+            assert!(ok);
+
+            let pipe = Controller::spawn(
+                "sh",
+                vec!["-c".to_string(), "echo oops >&2; exit 1".to_string()],
+            )
+            .unwrap();
+            let err = pipe.finish().unwrap_err();
+            assert_eq!(err.to_string(), "Error: oops");
+        }
+    }
+
+    #[cfg(feature = "zfs")]
     mod controller {
-        use super::super::Pool;
+        use super::super::{parse_diff_line, Pool};
+        use std::io::Read;
+
         use crate::{
-            testutil::{create_zpool, destroy_zpool, BUCKLE_TEST_ZPOOL_PREFIX},
-            zfs::{Dataset, ModifyDataset, ModifyVolume, Volume, ZFSKind},
+            testutil::{create_vdev_file, create_zpool, destroy_zpool, BUCKLE_TEST_ZPOOL_PREFIX},
+            zfs::{
+                AddVdev, AttachDevice, Bookmark, CanMount, ChangeKey, CloneSnapshot, Dataset,
+                Destroy, DiffChange, DiffEntry, DiffSnapshot, ExportPool, FileType, GetProperties,
+                Hold, ImportPool, InheritProperty, Key, LoadKey, ModifyDataset, ModifyVolume,
+                OfflineDevice, OnlineDevice, PoolHealth, PropertySource, Quota, QuotaFilter,
+                QuotaKind, ReplaceDevice, Rollback, SendSnapshot, SetProperties, Snapshot,
+                VdevClass, VdevLayout, Volume, ZFSKind,
+            },
         };
         #[test]
-        fn test_controller_zfs_lifecycle() {
-            let _ = destroy_zpool("controller-list", None);
-            let file = create_zpool("controller-list").unwrap();
-            let pool = Pool::new(&format!("{}-controller-list", BUCKLE_TEST_ZPOOL_PREFIX));
-            let list = pool.list(None).unwrap();
-            assert_eq!(list.len(), 0);
-            pool.create_dataset(&crate::zfs::Dataset {
+        fn test_controller_zfs_lifecycle() {
+            let _ = destroy_zpool("controller-list", None);
+            let file = create_zpool("controller-list").unwrap();
+            let pool = Pool::new(&format!("{}-controller-list", BUCKLE_TEST_ZPOOL_PREFIX));
+            let list = pool.list(None).unwrap();
+            assert_eq!(list.len(), 0);
+            pool.create_dataset(&crate::zfs::Dataset {
+                name: "dataset".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+            let list = pool.list(None).unwrap();
+            assert_eq!(list.len(), 1);
+            assert_eq!(list[0].kind, ZFSKind::Dataset);
+            assert_eq!(list[0].name, "dataset");
+            assert_eq!(
+                list[0].full_name,
+                format!("{}-controller-list/dataset", BUCKLE_TEST_ZPOOL_PREFIX),
+            );
+            assert_ne!(list[0].size, 0);
+            assert!(list[0].creation.is_some());
+            assert!(list[0].compression.is_some());
+            assert!(list[0].recordsize.is_some());
+            assert!(list[0].volblocksize.is_none());
+            assert_ne!(list[0].used, 0);
+            assert_ne!(list[0].refer, 0);
+            assert_ne!(list[0].avail, 0);
+            assert_eq!(
+                list[0].mountpoint,
+                Some(format!(
+                    "/{}-controller-list/dataset",
+                    BUCKLE_TEST_ZPOOL_PREFIX
+                ))
+            );
+            pool.create_volume(&crate::zfs::Volume {
+                name: "volume".to_string(),
+                size: 100 * 1024 * 1024,
+                ..Default::default()
+            })
+            .unwrap();
+            let list = pool.list(None).unwrap();
+            assert_eq!(list.len(), 2);
+
+            let list = pool.list(Some("volume".to_string())).unwrap();
+            assert_eq!(list.len(), 1);
+            assert_eq!(list[0].kind, ZFSKind::Volume);
+            assert_eq!(list[0].name, "volume");
+            assert_eq!(
+                list[0].full_name,
+                format!("{}-controller-list/volume", BUCKLE_TEST_ZPOOL_PREFIX),
+            );
+            assert_ne!(list[0].size, 0);
+            assert_ne!(list[0].used, 0);
+            assert_ne!(list[0].refer, 0);
+            assert_ne!(list[0].avail, 0);
+            assert_eq!(list[0].mountpoint, None);
+
+            pool.modify_volume(ModifyVolume {
+                name: "volume".into(),
+                modifications: Volume {
+                    name: "volume2".into(),
+                    size: 150 * 1024 * 1024,
+                    ..Default::default()
+                },
+            })
+            .unwrap();
+
+            let list = pool.list(Some("volume2".to_string())).unwrap();
+            assert_eq!(list.len(), 1);
+            assert_eq!(list[0].kind, ZFSKind::Volume);
+            assert_eq!(list[0].name, "volume2");
+            assert_eq!(
+                list[0].full_name,
+                format!("{}-controller-list/volume2", BUCKLE_TEST_ZPOOL_PREFIX),
+            );
+            assert_ne!(list[0].size, 0);
+            assert!(
+                list[0].size < 151 * 1024 * 1024 && list[0].size > 149 * 1024 * 1024,
+                "{}",
+                list[0].size
+            );
+            assert_ne!(list[0].used, 0);
+            assert_ne!(list[0].refer, 0);
+            assert_ne!(list[0].avail, 0);
+            assert_eq!(list[0].mountpoint, None);
+
+            let list = pool.list(Some("dataset".to_string())).unwrap();
+            assert_eq!(list.len(), 1);
+            assert_eq!(list[0].kind, ZFSKind::Dataset);
+            assert_eq!(list[0].name, "dataset");
+            assert_eq!(
+                list[0].full_name,
+                format!("{}-controller-list/dataset", BUCKLE_TEST_ZPOOL_PREFIX),
+            );
+            assert_ne!(list[0].size, 0);
+            assert_ne!(list[0].used, 0);
+            assert_ne!(list[0].refer, 0);
+            assert_ne!(list[0].avail, 0);
+            assert_eq!(
+                list[0].mountpoint,
+                Some(format!(
+                    "/{}-controller-list/dataset",
+                    BUCKLE_TEST_ZPOOL_PREFIX
+                ))
+            );
+
+            pool.modify_dataset(ModifyDataset {
+                name: "dataset".into(),
+                modifications: Dataset {
+                    name: "dataset2".into(),
+                    quota: Some(5 * 1024 * 1024),
+                    ..Default::default()
+                },
+            })
+            .unwrap();
+
+            let list = pool.list(Some("dataset2".to_string())).unwrap();
+            assert_eq!(list.len(), 1);
+            assert_eq!(list[0].kind, ZFSKind::Dataset);
+            assert_eq!(list[0].name, "dataset2");
+            assert_eq!(
+                list[0].full_name,
+                format!("{}-controller-list/dataset2", BUCKLE_TEST_ZPOOL_PREFIX),
+            );
+            assert_ne!(list[0].size, 0);
+            assert_ne!(list[0].used, 0);
+            assert_ne!(list[0].refer, 0);
+            assert_ne!(list[0].avail, 0);
+            assert_eq!(
+                list[0].mountpoint,
+                Some(format!(
+                    "/{}-controller-list/dataset2",
+                    BUCKLE_TEST_ZPOOL_PREFIX
+                ))
+            );
+
+            pool.destroy("dataset2".to_string()).unwrap();
+            let list = pool.list(Some("dataset2".to_string())).unwrap();
+            assert_eq!(list.len(), 0);
+            let list = pool.list(None).unwrap();
+            assert_eq!(list.len(), 1);
+            pool.destroy("volume2".to_string()).unwrap();
+            let list = pool.list(Some("volume2".to_string())).unwrap();
+            assert_eq!(list.len(), 0);
+            let list = pool.list(None).unwrap();
+            assert_eq!(list.len(), 0);
+            destroy_zpool("controller-list", Some(&file)).unwrap();
+        }
+
+        #[test]
+        fn test_controller_snapshot_lifecycle() {
+            let _ = destroy_zpool("controller-snapshot", None);
+            let file = create_zpool("controller-snapshot").unwrap();
+            let pool = Pool::new(&format!("{}-controller-snapshot", BUCKLE_TEST_ZPOOL_PREFIX));
+            pool.create_dataset(&Dataset {
+                name: "dataset".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+            pool.create_dataset(&Dataset {
+                name: "dataset/child".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+            assert_eq!(pool.list_snapshots(None).unwrap().len(), 0);
+
+            pool.create_snapshot(&Snapshot {
+                dataset: "dataset".to_string(),
+                name: "single".to_string(),
+                recursive: false,
+            })
+            .unwrap();
+            let list = pool.list_snapshots(None).unwrap();
+            assert_eq!(list.len(), 1);
+            assert_eq!(list[0].dataset, "dataset");
+            assert_eq!(list[0].name, "single");
+            assert_eq!(
+                list[0].full_name,
+                format!(
+                    "{}-controller-snapshot/dataset@single",
+                    BUCKLE_TEST_ZPOOL_PREFIX
+                )
+            );
+            assert_ne!(list[0].refer, 0);
+            assert!(list[0].created > std::time::SystemTime::UNIX_EPOCH);
+
+            pool.create_snapshot(&Snapshot {
+                dataset: "dataset".to_string(),
+                name: "recursive".to_string(),
+                recursive: true,
+            })
+            .unwrap();
+            assert_eq!(pool.list_snapshots(None).unwrap().len(), 3);
+            assert_eq!(
+                pool.list_snapshots(Some("dataset/child".to_string()))
+                    .unwrap()
+                    .len(),
+                1
+            );
+
+            pool.destroy_snapshot(&Snapshot {
+                dataset: "dataset".to_string(),
+                name: "recursive".to_string(),
+                recursive: true,
+            })
+            .unwrap();
+            assert_eq!(pool.list_snapshots(None).unwrap().len(), 1);
+            pool.destroy_snapshot(&Snapshot {
+                dataset: "dataset".to_string(),
+                name: "single".to_string(),
+                recursive: false,
+            })
+            .unwrap();
+            assert_eq!(pool.list_snapshots(None).unwrap().len(), 0);
+            destroy_zpool("controller-snapshot", Some(&file)).unwrap();
+        }
+
+        #[test]
+        fn test_parse_diff_line() {
+            let entry = parse_diff_line(b"R\tF\t/pool/a\\0040file\t/pool/renamed\n").unwrap();
+            assert_eq!(entry.change, DiffChange::Renamed);
+            assert_eq!(entry.file_type, FileType::File);
+            assert_eq!(entry.path, "/pool/a file");
+            assert_eq!(entry.new_path.as_deref(), Some("/pool/renamed"));
+
+            let entry = parse_diff_line(b"M\t/\t/pool/dir\\0134x").unwrap();
+            assert_eq!(entry.change, DiffChange::Modified);
+            assert_eq!(entry.file_type, FileType::Directory);
+            assert_eq!(entry.path, "/pool/dir\\x");
+            assert!(entry.new_path.is_none());
+
+            assert!(parse_diff_line(b"M\t/pool/dir").is_err());
+            assert!(parse_diff_line(b"X\tF\t/pool/file").is_err());
+        }
+
+        #[test]
+        fn test_controller_diff() {
+            let _ = destroy_zpool("controller-diff", None);
+            let file = create_zpool("controller-diff").unwrap();
+            let pool = Pool::new(&format!("{}-controller-diff", BUCKLE_TEST_ZPOOL_PREFIX));
+            pool.create_dataset(&Dataset {
+                name: "dataset".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+            let mountpoint = pool.list(None).unwrap()[0].mountpoint.clone().unwrap();
+
+            for name in ["before", "after"] {
+                if name == "after" {
+                    std::fs::write(format!("{}/a file", mountpoint), "data").unwrap();
+                }
+
+                pool.create_snapshot(&Snapshot {
+                    dataset: "dataset".to_string(),
+                    name: name.to_string(),
+                    recursive: false,
+                })
+                .unwrap();
+            }
+
+            std::fs::rename(
+                format!("{}/a file", mountpoint),
+                format!("{}/renamed", mountpoint),
+            )
+            .unwrap();
+
+            let info = DiffSnapshot {
+                dataset: "dataset".to_string(),
+                from: "before".to_string(),
+                to: Some("after".to_string()),
+            };
+            let mut diff = pool.diff(&info).unwrap();
+            let entries: Vec<DiffEntry> = diff.by_ref().map(|x| x.unwrap()).collect();
+            diff.finish().unwrap();
+            assert!(entries.contains(&DiffEntry {
+                change: DiffChange::Added,
+                file_type: FileType::File,
+                path: format!("{}/a file", mountpoint),
+                new_path: None,
+            }));
+
+            // against the live dataset
+            let mut diff = pool
+                .diff(&DiffSnapshot {
+                    from: "after".to_string(),
+                    to: None,
+                    ..info.clone()
+                })
+                .unwrap();
+            let entries: Vec<DiffEntry> = diff.by_ref().map(|x| x.unwrap()).collect();
+            diff.finish().unwrap();
+            assert!(entries.contains(&DiffEntry {
+                change: DiffChange::Renamed,
+                file_type: FileType::File,
+                path: format!("{}/a file", mountpoint),
+                new_path: Some(format!("{}/renamed", mountpoint)),
+            }));
+
+            let mut diff = pool
+                .diff(&DiffSnapshot {
+                    from: "missing".to_string(),
+                    ..info
+                })
+                .unwrap();
+            assert!(diff.by_ref().next().is_none());
+            assert!(diff.finish().is_err());
+
+            destroy_zpool("controller-diff", Some(&file)).unwrap();
+        }
+
+        #[test]
+        fn test_controller_holds_bookmarks() {
+            let _ = destroy_zpool("controller-holds", None);
+            let file = create_zpool("controller-holds").unwrap();
+            let pool = Pool::new(&format!("{}-controller-holds", BUCKLE_TEST_ZPOOL_PREFIX));
+            pool.create_dataset(&Dataset {
+                name: "dataset".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+            for name in ["first", "second"] {
+                pool.create_snapshot(&Snapshot {
+                    dataset: "dataset".to_string(),
+                    name: name.to_string(),
+                    recursive: false,
+                })
+                .unwrap();
+            }
+
+            let first = Snapshot {
+                dataset: "dataset".to_string(),
+                name: "first".to_string(),
+                recursive: false,
+            };
+            let hold = Hold {
+                dataset: "dataset".to_string(),
+                snapshot: "first".to_string(),
+                tag: "backup".to_string(),
+                recursive: false,
+            };
+            pool.hold(&hold).unwrap();
+            let holds = pool.list_holds(&first).unwrap();
+            assert_eq!(holds.len(), 1);
+            assert_eq!(holds[0].dataset, "dataset");
+            assert_eq!(holds[0].tag, "backup");
+            assert!(pool.destroy_snapshot(&first).is_err());
+
+            pool.create_bookmark(&Bookmark {
+                dataset: "dataset".to_string(),
+                name: "first".to_string(),
+                snapshot: "first".to_string(),
+            })
+            .unwrap();
+            pool.release(&hold).unwrap();
+            assert!(pool.list_holds(&first).unwrap().is_empty());
+            pool.destroy_snapshot(&first).unwrap();
+
+            let bookmarks = pool.list_bookmarks(None).unwrap();
+            assert_eq!(bookmarks.len(), 1);
+            assert_eq!(bookmarks[0].name, "first");
+
+            // incremental sends still work from the bookmark
+            let mut pipe = pool
+                .send(&SendSnapshot {
+                    dataset: "dataset".to_string(),
+                    snapshot: "second".to_string(),
+                    from: Some("#first".to_string()),
+                })
+                .unwrap();
+            let mut stream = Vec::new();
+            pipe.read_to_end(&mut stream).unwrap();
+            pipe.finish().unwrap();
+            assert!(!stream.is_empty());
+
+            pool.destroy_bookmark(&Bookmark {
+                dataset: "dataset".to_string(),
+                name: "first".to_string(),
+                snapshot: String::new(),
+            })
+            .unwrap();
+            assert!(pool.list_bookmarks(None).unwrap().is_empty());
+            destroy_zpool("controller-holds", Some(&file)).unwrap();
+        }
+
+        #[test]
+        fn test_controller_quotas() {
+            let _ = destroy_zpool("controller-quotas", None);
+            let file = create_zpool("controller-quotas").unwrap();
+            let pool = Pool::new(&format!("{}-controller-quotas", BUCKLE_TEST_ZPOOL_PREFIX));
+            pool.create_dataset(&Dataset {
+                name: "dataset".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+            let mut quota = Quota {
+                dataset: "dataset".to_string(),
+                kind: QuotaKind::User,
+                id: "1000".to_string(),
+                quota: Some(10 * 1024 * 1024),
+            };
+            pool.set_quota(&quota).unwrap();
+
+            let filter = QuotaFilter {
+                dataset: "dataset".to_string(),
+                kind: QuotaKind::User,
+            };
+            assert_eq!(pool.list_quotas(&filter).unwrap(), vec![quota.clone()]);
+
+            let space = pool.user_space(&filter).unwrap();
+            let entry = space.iter().find(|x| x.id == "1000").unwrap();
+            assert_eq!(entry.used, 0);
+            assert_eq!(entry.quota, Some(10 * 1024 * 1024));
+
+            quota.quota = None;
+            pool.set_quota(&quota).unwrap();
+            assert!(pool.list_quotas(&filter).unwrap().is_empty());
+            destroy_zpool("controller-quotas", Some(&file)).unwrap();
+        }
+
+        #[test]
+        fn test_controller_destroy() {
+            let _ = destroy_zpool("controller-destroy", None);
+            let file = create_zpool("controller-destroy").unwrap();
+            let pool = Pool::new(&format!("{}-controller-destroy", BUCKLE_TEST_ZPOOL_PREFIX));
+
+            for name in ["dataset", "dataset/child"] {
+                pool.create_dataset(&Dataset {
+                    name: name.to_string(),
+                    ..Default::default()
+                })
+                .unwrap();
+            }
+
+            let mut destroy = Destroy {
+                name: "dataset".to_string(),
+                dry_run: true,
+                ..Default::default()
+            };
+            assert!(pool.destroy_with(&destroy).is_err());
+
+            destroy.recursive = true;
+            let report = pool.destroy_with(&destroy).unwrap();
+            assert!(report.entries.contains(&"dataset".to_string()));
+            assert!(report.entries.contains(&"dataset/child".to_string()));
+            assert!(report.reclaimed > 0);
+            assert_eq!(pool.list(Some("dataset".to_string())).unwrap().len(), 2);
+
+            destroy.dry_run = false;
+            pool.destroy_with(&destroy).unwrap();
+            assert!(pool.list(Some("dataset".to_string())).unwrap().is_empty());
+            destroy_zpool("controller-destroy", Some(&file)).unwrap();
+        }
+
+        #[test]
+        fn test_controller_rollback() {
+            let _ = destroy_zpool("controller-rollback", None);
+            let file = create_zpool("controller-rollback").unwrap();
+            let pool = Pool::new(&format!("{}-controller-rollback", BUCKLE_TEST_ZPOOL_PREFIX));
+            pool.create_dataset(&Dataset {
+                name: "dataset".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+            let mountpoint = pool.list(Some("dataset".to_string())).unwrap()[0]
+                .mountpoint
+                .clone()
+                .unwrap();
+            let path = std::path::PathBuf::from(mountpoint);
+
+            std::fs::write(path.join("first"), "first").unwrap();
+            pool.create_snapshot(&Snapshot {
+                dataset: "dataset".to_string(),
+                name: "first".to_string(),
+                recursive: false,
+            })
+            .unwrap();
+            std::fs::write(path.join("second"), "second").unwrap();
+            pool.create_snapshot(&Snapshot {
+                dataset: "dataset".to_string(),
+                name: "second".to_string(),
+                recursive: false,
+            })
+            .unwrap();
+            std::fs::write(path.join("third"), "third").unwrap();
+
+            pool.rollback(&Rollback {
+                dataset: "dataset".to_string(),
+                snapshot: "second".to_string(),
+                destroy_newer: false,
+            })
+            .unwrap();
+            assert!(std::fs::exists(path.join("second")).unwrap());
+            assert!(!std::fs::exists(path.join("third")).unwrap());
+
+            // a newer snapshot exists, so this must be asked for explicitly
+            assert!(pool
+                .rollback(&Rollback {
+                    dataset: "dataset".to_string(),
+                    snapshot: "first".to_string(),
+                    destroy_newer: false,
+                })
+                .is_err());
+            assert!(std::fs::exists(path.join("second")).unwrap());
+
+            pool.rollback(&Rollback {
+                dataset: "dataset".to_string(),
+                snapshot: "first".to_string(),
+                destroy_newer: true,
+            })
+            .unwrap();
+            assert!(std::fs::exists(path.join("first")).unwrap());
+            assert!(!std::fs::exists(path.join("second")).unwrap());
+            assert_eq!(pool.list_snapshots(None).unwrap().len(), 1);
+            destroy_zpool("controller-rollback", Some(&file)).unwrap();
+        }
+
+        #[test]
+        fn test_controller_clone_promote() {
+            let _ = destroy_zpool("controller-clone", None);
+            let file = create_zpool("controller-clone").unwrap();
+            let pool = Pool::new(&format!("{}-controller-clone", BUCKLE_TEST_ZPOOL_PREFIX));
+            pool.create_dataset(&Dataset {
                 name: "dataset".to_string(),
-                quota: None,
+                ..Default::default()
             })
             .unwrap();
-            let list = pool.list(None).unwrap();
+            pool.create_snapshot(&Snapshot {
+                dataset: "dataset".to_string(),
+                name: "base".to_string(),
+                recursive: false,
+            })
+            .unwrap();
+            pool.clone_snapshot(&CloneSnapshot {
+                dataset: "dataset".to_string(),
+                snapshot: "base".to_string(),
+                target: "clone".to_string(),
+            })
+            .unwrap();
+
+            let list = pool.list(Some("clone".to_string())).unwrap();
             assert_eq!(list.len(), 1);
-            assert_eq!(list[0].kind, ZFSKind::Dataset);
-            assert_eq!(list[0].name, "dataset");
-            assert_eq!(
-                list[0].full_name,
-                format!("{}-controller-list/dataset", BUCKLE_TEST_ZPOOL_PREFIX),
-            );
-            assert_ne!(list[0].size, 0);
-            assert_ne!(list[0].used, 0);
-            assert_ne!(list[0].refer, 0);
-            assert_ne!(list[0].avail, 0);
+            assert_eq!(list[0].origin, Some("dataset@base".to_string()));
             assert_eq!(
                 list[0].mountpoint,
                 Some(format!(
-                    "/{}-controller-list/dataset",
+                    "/{}-controller-clone/clone",
                     BUCKLE_TEST_ZPOOL_PREFIX
                 ))
             );
-            pool.create_volume(&crate::zfs::Volume {
-                name: "volume".to_string(),
-                size: 100 * 1024 * 1024,
+            let list = pool.list(Some("dataset".to_string())).unwrap();
+            assert_eq!(list[0].origin, None);
+
+            pool.promote("clone".to_string()).unwrap();
+
+            let list = pool.list(Some("clone".to_string())).unwrap();
+            assert_eq!(list[0].origin, None);
+            let list = pool.list(Some("dataset".to_string())).unwrap();
+            assert_eq!(list[0].origin, Some("clone@base".to_string()));
+            let list = pool.list_snapshots(None).unwrap();
+            assert_eq!(list.len(), 1);
+            assert_eq!(list[0].dataset, "clone");
+
+            pool.destroy("dataset".to_string()).unwrap();
+            pool.destroy_snapshot(&Snapshot {
+                dataset: "clone".to_string(),
+                name: "base".to_string(),
+                recursive: false,
             })
             .unwrap();
-            let list = pool.list(None).unwrap();
-            assert_eq!(list.len(), 2);
+            pool.destroy("clone".to_string()).unwrap();
+            assert_eq!(pool.list(None).unwrap().len(), 0);
+            destroy_zpool("controller-clone", Some(&file)).unwrap();
+        }
 
-            let list = pool.list(Some("volume".to_string())).unwrap();
-            assert_eq!(list.len(), 1);
-            assert_eq!(list[0].kind, ZFSKind::Volume);
-            assert_eq!(list[0].name, "volume");
-            assert_eq!(
-                list[0].full_name,
-                format!("{}-controller-list/volume", BUCKLE_TEST_ZPOOL_PREFIX),
-            );
-            assert_ne!(list[0].size, 0);
-            assert_ne!(list[0].used, 0);
-            assert_ne!(list[0].refer, 0);
-            assert_ne!(list[0].avail, 0);
-            assert_eq!(list[0].mountpoint, None);
+        #[test]
+        fn test_controller_properties() {
+            let _ = destroy_zpool("controller-properties", None);
+            let file = create_zpool("controller-properties").unwrap();
+            let pool = Pool::new(&format!(
+                "{}-controller-properties",
+                BUCKLE_TEST_ZPOOL_PREFIX
+            ));
+            pool.create_dataset(&Dataset {
+                name: "dataset".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+            pool.create_dataset(&Dataset {
+                name: "dataset/child".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
 
-            pool.modify_volume(ModifyVolume {
-                name: "volume".into(),
-                modifications: Volume {
-                    name: "volume2".into(),
-                    size: 150 * 1024 * 1024,
+            let props = pool
+                .get_properties(&GetProperties {
+                    name: "dataset".to_string(),
+                    properties: Vec::new(),
+                })
+                .unwrap();
+            assert!(props.len() > 10);
+            assert!(props.iter().any(|p| p.name == "compression"));
+
+            pool.set_properties(&SetProperties {
+                name: "dataset".to_string(),
+                properties: [
+                    ("compression".to_string(), "zstd".to_string()),
+                    ("atime".to_string(), "off".to_string()),
+                ]
+                .into(),
+            })
+            .unwrap();
+
+            let props = pool
+                .get_properties(&GetProperties {
+                    name: "dataset".to_string(),
+                    properties: vec!["atime".to_string(), "compression".to_string()],
+                })
+                .unwrap();
+            assert_eq!(props.len(), 2);
+            assert_eq!(props[0].name, "atime");
+            assert_eq!(props[0].value, "off");
+            assert_eq!(props[0].source, PropertySource::Local);
+            assert_eq!(props[1].name, "compression");
+            assert_eq!(props[1].value, "zstd");
+            assert_eq!(props[1].inherited_from, None);
+
+            let props = pool
+                .get_properties(&GetProperties {
+                    name: "dataset/child".to_string(),
+                    properties: vec!["compression".to_string(), "recordsize".to_string()],
+                })
+                .unwrap();
+            assert_eq!(props[0].value, "zstd");
+            assert_eq!(props[0].source, PropertySource::Inherited);
+            assert_eq!(props[0].inherited_from, Some("dataset".to_string()));
+            assert_eq!(props[1].value, "131072");
+            assert_eq!(props[1].source, PropertySource::Default);
+
+            pool.inherit_property(&InheritProperty {
+                name: "dataset".to_string(),
+                property: "compression".to_string(),
+                recursive: true,
+            })
+            .unwrap();
+
+            let props = pool
+                .get_properties(&GetProperties {
+                    name: "dataset/child".to_string(),
+                    properties: vec!["compression".to_string()],
+                })
+                .unwrap();
+            assert_ne!(props[0].value, "zstd");
+            assert_ne!(props[0].inherited_from, Some("dataset".to_string()));
+            destroy_zpool("controller-properties", Some(&file)).unwrap();
+        }
+
+        #[test]
+        fn test_controller_dataset_options() {
+            let _ = destroy_zpool("controller-options", None);
+            let file = create_zpool("controller-options").unwrap();
+            let pool = Pool::new(&format!("{}-controller-options", BUCKLE_TEST_ZPOOL_PREFIX));
+
+            for invalid in [
+                Dataset {
+                    name: "invalid".to_string(),
+                    recordsize: Some(1000),
+                    ..Default::default()
+                },
+                Dataset {
+                    name: "invalid".to_string(),
+                    compression: Some("gzip-12".to_string()),
+                    ..Default::default()
                 },
+                Dataset {
+                    name: "invalid".to_string(),
+                    mountpoint: Some("relative/path".to_string()),
+                    ..Default::default()
+                },
+                Dataset {
+                    name: "invalid".to_string(),
+                    properties: [("quota".to_string(), "1G".to_string())].into(),
+                    ..Default::default()
+                },
+            ] {
+                assert!(pool.create_dataset(&invalid).is_err(), "{:?}", invalid);
+            }
+            assert_eq!(pool.list(None).unwrap().len(), 0);
+
+            let mountpoint = format!("/tmp/{}-controller-options", BUCKLE_TEST_ZPOOL_PREFIX);
+            pool.create_dataset(&Dataset {
+                name: "dataset".to_string(),
+                compression: Some("zstd-3".to_string()),
+                recordsize: Some(1024 * 1024),
+                reservation: Some(10 * 1024 * 1024),
+                refquota: Some(100 * 1024 * 1024),
+                atime: Some(false),
+                mountpoint: Some(mountpoint.clone()),
+                canmount: Some(CanMount::On),
+                properties: [("xattr".to_string(), "sa".to_string())].into(),
+                ..Default::default()
             })
             .unwrap();
 
-            let list = pool.list(Some("volume2".to_string())).unwrap();
+            let list = pool.list(None).unwrap();
             assert_eq!(list.len(), 1);
-            assert_eq!(list[0].kind, ZFSKind::Volume);
-            assert_eq!(list[0].name, "volume2");
+            assert_eq!(list[0].mountpoint, Some(mountpoint));
+
+            let props = pool
+                .get_properties(&GetProperties {
+                    name: "dataset".to_string(),
+                    properties: vec![
+                        "atime".to_string(),
+                        "canmount".to_string(),
+                        "compression".to_string(),
+                        "recordsize".to_string(),
+                        "refquota".to_string(),
+                        "reservation".to_string(),
+                        "xattr".to_string(),
+                    ],
+                })
+                .unwrap()
+                .into_iter()
+                .map(|p| p.value)
+                .collect::<Vec<_>>();
             assert_eq!(
-                list[0].full_name,
-                format!("{}-controller-list/volume2", BUCKLE_TEST_ZPOOL_PREFIX),
+                props,
+                vec![
+                    "off".to_string(),
+                    "on".to_string(),
+                    "zstd-3".to_string(),
+                    (1024 * 1024).to_string(),
+                    (100 * 1024 * 1024).to_string(),
+                    (10 * 1024 * 1024).to_string(),
+                    "sa".to_string(),
+                ]
             );
-            assert_ne!(list[0].size, 0);
-            assert!(
-                list[0].size < 151 * 1024 * 1024 && list[0].size > 149 * 1024 * 1024,
-                "{}",
-                list[0].size
-            );
-            assert_ne!(list[0].used, 0);
-            assert_ne!(list[0].refer, 0);
-            assert_ne!(list[0].avail, 0);
-            assert_eq!(list[0].mountpoint, None);
 
-            let list = pool.list(Some("dataset".to_string())).unwrap();
+            pool.modify_dataset(ModifyDataset {
+                name: "dataset".to_string(),
+                modifications: Dataset {
+                    compression: Some("lz4".to_string()),
+                    atime: Some(true),
+                    ..Default::default()
+                },
+            })
+            .unwrap();
+
+            let props = pool
+                .get_properties(&GetProperties {
+                    name: "dataset".to_string(),
+                    properties: vec!["atime".to_string(), "compression".to_string()],
+                })
+                .unwrap();
+            assert_eq!(props[0].value, "on");
+            assert_eq!(props[1].value, "lz4");
+
+            // nothing to mount, set directly or inherited
+            for mountpoint in ["none", "legacy"] {
+                pool.create_dataset(&Dataset {
+                    name: mountpoint.to_string(),
+                    mountpoint: Some(mountpoint.to_string()),
+                    ..Default::default()
+                })
+                .unwrap();
+                pool.create_dataset(&Dataset {
+                    name: format!("{}/child", mountpoint),
+                    ..Default::default()
+                })
+                .unwrap();
+                pool.destroy_with(&Destroy {
+                    name: mountpoint.to_string(),
+                    recursive: true,
+                    ..Default::default()
+                })
+                .unwrap();
+            }
+
+            pool.create_dataset(&Dataset {
+                name: "user".to_string(),
+                properties: [("buckle:generation".to_string(), "42".to_string())].into(),
+                ..Default::default()
+            })
+            .unwrap();
+            let props = pool
+                .get_properties(&GetProperties {
+                    name: "user".to_string(),
+                    properties: vec!["buckle:generation".to_string()],
+                })
+                .unwrap();
+            assert_eq!(props[0].value, "42");
+            pool.destroy("user".to_string()).unwrap();
+
+            pool.destroy("dataset".to_string()).unwrap();
+            destroy_zpool("controller-options", Some(&file)).unwrap();
+        }
+
+        #[test]
+        fn test_controller_volume_options() {
+            let _ = destroy_zpool("controller-volume", None);
+            let file = create_zpool("controller-volume").unwrap();
+            let pool = Pool::new(&format!("{}-controller-volume", BUCKLE_TEST_ZPOOL_PREFIX));
+
+            assert!(pool
+                .create_volume(&Volume {
+                    name: "invalid".to_string(),
+                    size: 10 * 1024 * 1024,
+                    volmode: Some("block".to_string()),
+                    ..Default::default()
+                })
+                .is_err());
+
+            pool.create_volume(&Volume {
+                name: "thick".to_string(),
+                size: 10 * 1024 * 1024,
+                ..Default::default()
+            })
+            .unwrap();
+            pool.create_volume(&Volume {
+                name: "sparse".to_string(),
+                size: 10 * 1024 * 1024,
+                sparse: true,
+                volblocksize: Some(16 * 1024),
+                volmode: Some("dev".to_string()),
+                compression: Some("lz4".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+
+            let list = pool.list(Some("sparse".to_string())).unwrap();
             assert_eq!(list.len(), 1);
-            assert_eq!(list[0].kind, ZFSKind::Dataset);
-            assert_eq!(list[0].name, "dataset");
+            let device = list[0].device.clone().unwrap();
             assert_eq!(
-                list[0].full_name,
-                format!("{}-controller-list/dataset", BUCKLE_TEST_ZPOOL_PREFIX),
+                device,
+                format!(
+                    "/dev/zvol/{}-controller-volume/sparse",
+                    BUCKLE_TEST_ZPOOL_PREFIX
+                )
             );
-            assert_ne!(list[0].size, 0);
-            assert_ne!(list[0].used, 0);
-            assert_ne!(list[0].refer, 0);
-            assert_ne!(list[0].avail, 0);
+            assert!(std::fs::exists(&device).unwrap());
+
+            let props = pool
+                .get_properties(&GetProperties {
+                    name: "sparse".to_string(),
+                    properties: vec![
+                        "compression".to_string(),
+                        "refreservation".to_string(),
+                        "volblocksize".to_string(),
+                        "volmode".to_string(),
+                    ],
+                })
+                .unwrap()
+                .into_iter()
+                .map(|p| p.value)
+                .collect::<Vec<_>>();
             assert_eq!(
-                list[0].mountpoint,
-                Some(format!(
-                    "/{}-controller-list/dataset",
-                    BUCKLE_TEST_ZPOOL_PREFIX
-                ))
+                props,
+                vec![
+                    "lz4".to_string(),
+                    "0".to_string(),
+                    (16 * 1024).to_string(),
+                    "dev".to_string(),
+                ]
             );
 
-            pool.modify_dataset(ModifyDataset {
-                name: "dataset".into(),
-                modifications: Dataset {
-                    name: "dataset2".into(),
-                    quota: Some(5 * 1024 * 1024),
+            let props = pool
+                .get_properties(&GetProperties {
+                    name: "thick".to_string(),
+                    properties: vec!["refreservation".to_string()],
+                })
+                .unwrap();
+            assert_ne!(props[0].value, "0");
+
+            assert!(pool
+                .modify_volume(ModifyVolume {
+                    name: "sparse".to_string(),
+                    modifications: Volume {
+                        volblocksize: Some(8 * 1024),
+                        ..Default::default()
+                    },
+                })
+                .is_err());
+
+            pool.modify_volume(ModifyVolume {
+                name: "sparse".to_string(),
+                modifications: Volume {
+                    name: "renamed".to_string(),
+                    ..Default::default()
                 },
             })
             .unwrap();
+            assert!(std::fs::exists(format!(
+                "/dev/zvol/{}-controller-volume/renamed",
+                BUCKLE_TEST_ZPOOL_PREFIX
+            ))
+            .unwrap());
+
+            let list = pool.list(Some("thick".to_string())).unwrap();
+            assert!(list[0].device.is_some());
+            pool.create_dataset(&Dataset {
+                name: "dataset".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+            let list = pool.list(Some("dataset".to_string())).unwrap();
+            assert_eq!(list[0].device, None);
+
+            destroy_zpool("controller-volume", Some(&file)).unwrap();
+        }
+
+        #[test]
+        fn test_controller_encryption() {
+            let _ = destroy_zpool("controller-encryption", None);
+            let file = create_zpool("controller-encryption").unwrap();
+            let pool = Pool::new(&format!(
+                "{}-controller-encryption",
+                BUCKLE_TEST_ZPOOL_PREFIX
+            ));
+
+            // a supplied key can only be read from stdin
+            assert!(pool
+                .create_dataset(&Dataset {
+                    name: "invalid".to_string(),
+                    encryption: Some("on".to_string()),
+                    keyformat: Some("passphrase".to_string()),
+                    keylocation: Some("file:///nonexistent".to_string()),
+                    key: Some(Key::new("correct horse battery staple")),
+                    ..Default::default()
+                })
+                .is_err());
+
+            pool.create_dataset(&Dataset {
+                name: "secret".to_string(),
+                encryption: Some("aes-256-gcm".to_string()),
+                keyformat: Some("passphrase".to_string()),
+                key: Some(Key::new("correct horse battery staple")),
+                ..Default::default()
+            })
+            .unwrap();
+            pool.create_dataset(&Dataset {
+                name: "secret/child".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+            pool.create_volume(&Volume {
+                name: "secret-volume".to_string(),
+                size: 10 * 1024 * 1024,
+                encryption: Some("on".to_string()),
+                keyformat: Some("hex".to_string()),
+                key: Some(Key::new("ab".repeat(32))),
+                ..Default::default()
+            })
+            .unwrap();
+
+            let list = pool.list(Some("secret".to_string())).unwrap();
+            assert_eq!(list.len(), 3);
+            for item in &list {
+                assert_eq!(item.keystatus, Some("available".to_string()));
+            }
+            let child = list.iter().find(|i| i.name == "secret/child").unwrap();
+            assert_eq!(child.encryption_root, Some("secret".to_string()));
+            let volume = list.iter().find(|i| i.name == "secret-volume").unwrap();
+            assert_eq!(volume.encryption_root, Some("secret-volume".to_string()));
+
+            pool.create_dataset(&Dataset {
+                name: "plain".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+            let list = pool.list(Some("plain".to_string())).unwrap();
+            assert_eq!(list[0].keystatus, None);
+            assert_eq!(list[0].encryption_root, None);
+
+            pool.destroy("secret/child".to_string()).unwrap();
+            pool.unload_key("secret".to_string()).unwrap();
+            let list = pool.list(Some("secret".to_string())).unwrap();
+            let secret = list.iter().find(|i| i.name == "secret").unwrap();
+            assert_eq!(secret.keystatus, Some("unavailable".to_string()));
+
+            assert!(pool
+                .load_key(&LoadKey {
+                    name: "secret".to_string(),
+                    key: Some(Key::new("wrong passphrase")),
+                })
+                .is_err());
+            pool.load_key(&LoadKey {
+                name: "secret".to_string(),
+                key: Some(Key::new("correct horse battery staple")),
+            })
+            .unwrap();
+            let list = pool.list(Some("secret".to_string())).unwrap();
+            let secret = list.iter().find(|i| i.name == "secret").unwrap();
+            assert_eq!(secret.keystatus, Some("available".to_string()));
+            assert!(secret.mountpoint.is_some());
+
+            pool.change_key(&ChangeKey {
+                name: "secret".to_string(),
+                key: Some(Key::new("a different passphrase")),
+                keyformat: None,
+                keylocation: None,
+            })
+            .unwrap();
+            pool.unload_key("secret".to_string()).unwrap();
+            assert!(pool
+                .load_key(&LoadKey {
+                    name: "secret".to_string(),
+                    key: Some(Key::new("correct horse battery staple")),
+                })
+                .is_err());
+            pool.load_key(&LoadKey {
+                name: "secret".to_string(),
+                key: Some(Key::new("a different passphrase")),
+            })
+            .unwrap();
 
-            let list = pool.list(Some("dataset2".to_string())).unwrap();
-            assert_eq!(list.len(), 1);
-            assert_eq!(list[0].kind, ZFSKind::Dataset);
-            assert_eq!(list[0].name, "dataset2");
             assert_eq!(
-                list[0].full_name,
-                format!("{}-controller-list/dataset2", BUCKLE_TEST_ZPOOL_PREFIX),
+                format!("{:?}", Key::new("a different passphrase")),
+                "Key(<redacted>)"
             );
-            assert_ne!(list[0].size, 0);
-            assert_ne!(list[0].used, 0);
-            assert_ne!(list[0].refer, 0);
-            assert_ne!(list[0].avail, 0);
+            destroy_zpool("controller-encryption", Some(&file)).unwrap();
+        }
+
+        #[test]
+        fn test_controller_pool_status() {
+            let _ = destroy_zpool("controller-status", None);
+            let file = create_zpool("controller-status").unwrap();
+            let pool = Pool::new(&format!("{}-controller-status", BUCKLE_TEST_ZPOOL_PREFIX));
+
+            let status = pool.status().unwrap();
             assert_eq!(
-                list[0].mountpoint,
-                Some(format!(
-                    "/{}-controller-list/dataset2",
-                    BUCKLE_TEST_ZPOOL_PREFIX
-                ))
+                status.name,
+                format!("{}-controller-status", BUCKLE_TEST_ZPOOL_PREFIX)
             );
+            assert_eq!(status.health, PoolHealth::Online);
+            assert_eq!(status.vdevs.len(), 1);
+            assert_eq!(status.vdevs[0].kind, "file");
+            assert_eq!(status.vdevs[0].state, PoolHealth::Online);
+            assert!(status.vdevs[0]
+                .path
+                .as_ref()
+                .is_some_and(|path| path.ends_with(file.trim_start_matches("tmp/"))));
+            assert_eq!(status.vdevs[0].read_errors, 0);
+            assert_eq!(status.vdevs[0].write_errors, 0);
+            assert_eq!(status.vdevs[0].checksum_errors, 0);
+            assert!(status.vdevs[0].children.is_empty());
+            assert!(status.logs.is_empty());
+            assert!(status.cache.is_empty());
+            assert!(status.spares.is_empty());
+            assert!(status.scan.is_none());
+            assert_ne!(status.size, 0);
+            assert_eq!(status.size, status.allocated + status.free);
+            assert!(status.capacity <= 100);
+            assert_eq!(status.dedup_ratio, 1.0);
+            assert_eq!(status.data_errors, 0);
 
-            pool.destroy("dataset2".to_string()).unwrap();
-            let list = pool.list(Some("dataset2".to_string())).unwrap();
-            assert_eq!(list.len(), 0);
-            let list = pool.list(None).unwrap();
-            assert_eq!(list.len(), 1);
-            pool.destroy("volume2".to_string()).unwrap();
-            let list = pool.list(Some("volume2".to_string())).unwrap();
-            assert_eq!(list.len(), 0);
-            let list = pool.list(None).unwrap();
-            assert_eq!(list.len(), 0);
-            destroy_zpool("controller-list", Some(&file)).unwrap();
+            destroy_zpool("controller-status", Some(&file)).unwrap();
+        }
+
+        #[test]
+        fn test_controller_scrub_trim() {
+            let _ = destroy_zpool("controller-scrub", None);
+            let file = create_zpool("controller-scrub").unwrap();
+            let pool = Pool::new(&format!("{}-controller-scrub", BUCKLE_TEST_ZPOOL_PREFIX));
+
+            let day = std::time::Duration::from_secs(24 * 60 * 60);
+            assert_eq!(
+                pool.next_scrub(day).unwrap(),
+                Some(std::time::Duration::ZERO)
+            );
+            // nothing to cancel yet
+            assert!(pool.cancel_scrub().is_err());
+
+            pool.start_scrub().unwrap();
+            // an empty pool scrubs in no time at all
+            let start = std::time::Instant::now();
+            let scan = loop {
+                let scan = pool.status().unwrap().scan.unwrap();
+                if scan.state != "scanning" || start.elapsed().as_secs() > 30 {
+                    break scan;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(100));
+            };
+            assert_eq!(scan.function, "scrub");
+            assert_eq!(scan.state, "finished");
+            assert_eq!(scan.errors, 0);
+            assert!(scan.end_time.is_some());
+
+            let next = pool.next_scrub(day).unwrap().unwrap();
+            assert!(next > std::time::Duration::ZERO && next <= day);
+
+            pool.start_trim().unwrap();
+            destroy_zpool("controller-scrub", Some(&file)).unwrap();
+        }
+
+        #[test]
+        fn test_controller_vdevs() {
+            let _ = destroy_zpool("controller-vdevs", None);
+            let file = create_zpool("controller-vdevs").unwrap();
+            let pool = Pool::new(&format!("{}-controller-vdevs", BUCKLE_TEST_ZPOOL_PREFIX));
+            // devices have to be absolute paths
+            let file = std::fs::canonicalize(file)
+                .unwrap()
+                .to_string_lossy()
+                .to_string();
+            let vdev = || {
+                create_vdev_file()
+                    .unwrap()
+                    .canonicalize()
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string()
+            };
+            let mirror = vdev();
+            let log = vdev();
+            let replacement = vdev();
+
+            let wait_for_resilver = || {
+                let start = std::time::Instant::now();
+                while pool
+                    .status()
+                    .unwrap()
+                    .scan
+                    .is_some_and(|scan| scan.state == "scanning")
+                {
+                    assert!(start.elapsed().as_secs() < 30, "resilver took too long");
+                    std::thread::sleep(std::time::Duration::from_millis(100));
+                }
+            };
+
+            pool.attach(&AttachDevice {
+                device: file.clone(),
+                new_device: mirror.clone(),
+                force: false,
+            })
+            .unwrap();
+            wait_for_resilver();
+            let status = pool.status().unwrap();
+            assert_eq!(status.vdevs.len(), 1);
+            assert_eq!(status.vdevs[0].kind, "mirror");
+            assert_eq!(status.vdevs[0].children.len(), 2);
+
+            pool.offline(&OfflineDevice {
+                device: mirror.clone(),
+                temporary: true,
+            })
+            .unwrap();
+            let status = pool.status().unwrap();
+            assert_eq!(status.health, PoolHealth::Degraded);
+            let device = status.vdevs[0]
+                .children
+                .iter()
+                .find(|x| x.path.as_ref() == Some(&mirror))
+                .unwrap();
+            assert_eq!(device.state, PoolHealth::Offline);
+
+            pool.online(&OnlineDevice {
+                device: mirror.clone(),
+                expand: false,
+            })
+            .unwrap();
+            wait_for_resilver();
+            pool.clear(None).unwrap();
+            let status = pool.status().unwrap();
+            assert_eq!(status.health, PoolHealth::Online);
+
+            pool.detach(mirror.clone()).unwrap();
+            let status = pool.status().unwrap();
+            assert_eq!(status.vdevs.len(), 1);
+            assert_eq!(status.vdevs[0].kind, "file");
+
+            pool.replace(&ReplaceDevice {
+                device: file.clone(),
+                new_device: Some(replacement.clone()),
+                force: false,
+            })
+            .unwrap();
+            wait_for_resilver();
+            let status = pool.status().unwrap();
+            assert_eq!(status.vdevs.len(), 1);
+            assert_eq!(status.vdevs[0].path, Some(replacement.clone()));
+
+            assert!(pool
+                .add_vdev(&AddVdev {
+                    class: VdevClass::Cache,
+                    layout: VdevLayout::Mirror,
+                    devices: vec![log.clone(), mirror.clone()],
+                    force: false,
+                })
+                .is_err());
+            assert!(pool
+                .add_vdev(&AddVdev {
+                    layout: VdevLayout::Mirror,
+                    devices: vec![log.clone()],
+                    ..Default::default()
+                })
+                .is_err());
+            assert!(pool.add_vdev(&AddVdev::default()).is_err());
+
+            pool.add_vdev(&AddVdev {
+                class: VdevClass::Log,
+                devices: vec![log.clone()],
+                ..Default::default()
+            })
+            .unwrap();
+            let status = pool.status().unwrap();
+            assert_eq!(status.logs.len(), 1);
+            assert_eq!(status.logs[0].path, Some(log.clone()));
+
+            destroy_zpool("controller-vdevs", Some(&replacement)).unwrap();
+            for file in [file, mirror, log] {
+                std::fs::remove_file(file).unwrap();
+            }
+        }
+
+        #[test]
+        fn test_controller_import_export() {
+            let _ = destroy_zpool("controller-import", None);
+            let _ = destroy_zpool("controller-imported", None);
+            let file = create_zpool("controller-import").unwrap();
+            let name = format!("{}-controller-import", BUCKLE_TEST_ZPOOL_PREFIX);
+            let dir = std::path::Path::new(&file)
+                .parent()
+                .unwrap()
+                .to_string_lossy()
+                .to_string();
+
+            let pool = Pool::new(&name);
+            pool.export(&ExportPool {
+                name: name.clone(),
+                force: false,
+            })
+            .unwrap();
+
+            let importable = pool.importable(std::slice::from_ref(&dir)).unwrap();
+            let found = importable.iter().find(|x| x.name == name).unwrap();
+            assert_eq!(found.health, PoolHealth::Online);
+            assert!(found.id.parse::<u64>().is_ok());
+
+            pool.import(&ImportPool {
+                name: found.id.clone(),
+                new_name: Some(format!("{}-controller-imported", BUCKLE_TEST_ZPOOL_PREFIX)),
+                dirs: vec![dir.clone()],
+                force: false,
+            })
+            .unwrap();
+
+            let imported = Pool::new(&format!("{}-controller-imported", BUCKLE_TEST_ZPOOL_PREFIX));
+            assert_eq!(imported.status().unwrap().health, PoolHealth::Online);
+            assert!(imported
+                .importable(&[dir])
+                .unwrap()
+                .iter()
+                .all(|x| x.name != name));
+
+            destroy_zpool("controller-imported", Some(&file)).unwrap();
         }
     }
 }
@@ -0,0 +1,575 @@
+use super::{
+    CommandOptions, Controller, QuotaKind, ZFSGet, ZFSList, ZFSPipe, ZFSSnapshotList, ZPoolList,
+    ZPoolStatus, ZfsBackend,
+};
+use anyhow::{anyhow, Result};
+use std::{
+    collections::HashMap,
+    ffi::{c_char, c_int, CString},
+    ptr,
+    str::FromStr,
+    sync::OnceLock,
+};
+use tracing::debug;
+
+// The subset of libzfs_core and libnvpair we call. These are stable, but have no published Rust
+// bindings, so they are declared by hand from libzfs_core.h and libnvpair.h.
+mod ffi {
+    use std::ffi::{c_char, c_int, c_uint};
+
+    #[repr(C)]
+    pub struct nvlist_t {
+        _private: [u8; 0],
+    }
+
+    pub const NV_UNIQUE_NAME: c_uint = 1;
+    // enum lzc_dataset_type
+    pub const LZC_DATSET_TYPE_ZFS: c_int = 2;
+
+    #[link(name = "nvpair")]
+    unsafe extern "C" {
+        pub fn nvlist_alloc(nvlp: *mut *mut nvlist_t, nvflag: c_uint, kmflag: c_int) -> c_int;
+        pub fn nvlist_free(nvl: *mut nvlist_t);
+        pub fn nvlist_add_boolean(nvl: *mut nvlist_t, name: *const c_char) -> c_int;
+        pub fn nvlist_add_string(
+            nvl: *mut nvlist_t,
+            name: *const c_char,
+            value: *const c_char,
+        ) -> c_int;
+        pub fn nvlist_add_uint64(nvl: *mut nvlist_t, name: *const c_char, value: u64) -> c_int;
+    }
+
+    #[link(name = "zfs_core")]
+    unsafe extern "C" {
+        pub fn libzfs_core_init() -> c_int;
+        pub fn lzc_create(
+            fsname: *const c_char,
+            typ: c_int,
+            props: *mut nvlist_t,
+            wkeydata: *mut u8,
+            wkeylen: c_uint,
+        ) -> c_int;
+        pub fn lzc_clone(
+            fsname: *const c_char,
+            origin: *const c_char,
+            props: *mut nvlist_t,
+        ) -> c_int;
+        pub fn lzc_promote(fsname: *const c_char, snapnamebuf: *mut c_char, len: c_int) -> c_int;
+        pub fn lzc_destroy(fsname: *const c_char) -> c_int;
+        pub fn lzc_snapshot(
+            snaps: *mut nvlist_t,
+            props: *mut nvlist_t,
+            errlist: *mut *mut nvlist_t,
+        ) -> c_int;
+        pub fn lzc_destroy_snaps(
+            snaps: *mut nvlist_t,
+            defer: c_int,
+            errlist: *mut *mut nvlist_t,
+        ) -> c_int;
+        pub fn lzc_rollback_to(fsname: *const c_char, snapname: *const c_char) -> c_int;
+        pub fn lzc_unload_key(fsname: *const c_char) -> c_int;
+    }
+}
+
+// errno values libzfs_core hands back that we act on
+const ENOENT: c_int = 2;
+const EBUSY: c_int = 16;
+const EEXIST: c_int = 17;
+
+// the kernel only takes strings for string-typed properties; index properties like compression
+// or atime need the numeric values libzfs translates them to, so those go through the CLI.
+const STRING_PROPERTIES: &[&str] = &["mountpoint", "keylocation"];
+
+fn cstring(s: &str) -> Result<CString> {
+    Ok(CString::new(s)?)
+}
+
+fn nvlist(ret: c_int) -> Result<()> {
+    match ret {
+        0 => Ok(()),
+        ret => Err(anyhow!(
+            "building nvlist: {}",
+            std::io::Error::from_raw_os_error(ret)
+        )),
+    }
+}
+
+fn check(operation: &str, name: &str, ret: c_int) -> Result<()> {
+    if ret == 0 {
+        return Ok(());
+    }
+
+    Err(anyhow!(
+        "{} '{}': {}",
+        operation,
+        name,
+        std::io::Error::from_raw_os_error(ret)
+    ))
+}
+
+// owns an nvlist and frees it on drop
+struct NvList(*mut ffi::nvlist_t);
+
+impl NvList {
+    fn new() -> Result<Self> {
+        let mut nvl = ptr::null_mut();
+        nvlist(unsafe { ffi::nvlist_alloc(&mut nvl, ffi::NV_UNIQUE_NAME, 0) })?;
+        Ok(Self(nvl))
+    }
+
+    fn add_boolean(&mut self, name: &str) -> Result<()> {
+        let name = cstring(name)?;
+        nvlist(unsafe { ffi::nvlist_add_boolean(self.0, name.as_ptr()) })
+    }
+
+    fn add_string(&mut self, name: &str, value: &str) -> Result<()> {
+        let (name, value) = (cstring(name)?, cstring(value)?);
+        nvlist(unsafe { ffi::nvlist_add_string(self.0, name.as_ptr(), value.as_ptr()) })
+    }
+
+    fn add_uint64(&mut self, name: &str, value: u64) -> Result<()> {
+        let name = cstring(name)?;
+        nvlist(unsafe { ffi::nvlist_add_uint64(self.0, name.as_ptr(), value) })
+    }
+}
+
+impl Drop for NvList {
+    fn drop(&mut self) {
+        unsafe { ffi::nvlist_free(self.0) }
+    }
+}
+
+fn init() -> Result<()> {
+    // libzfs_core keeps /dev/zfs open for the life of the process; there's no reason to close it
+    static INIT: OnceLock<c_int> = OnceLock::new();
+    check(
+        "opening",
+        "/dev/zfs",
+        *INIT.get_or_init(|| unsafe { ffi::libzfs_core_init() }),
+    )
+}
+
+// Options libzfs_core can set at creation time, or None when something needs the CLI.
+fn properties(options: &Option<CommandOptions>) -> Result<Option<NvList>> {
+    let mut props = NvList::new()?;
+
+    if let Some(options) = options {
+        for (key, value) in options.iter() {
+            // user properties are always strings to zfs, however numeric they look
+            if STRING_PROPERTIES.contains(&key.as_str()) || key.contains(':') {
+                props.add_string(key, value)?;
+            } else if let Ok(value) = value.parse::<u64>() {
+                props.add_uint64(key, value)?;
+            } else {
+                return Ok(None);
+            }
+        }
+    }
+
+    Ok(Some(props))
+}
+
+// Talks to /dev/zfs through libzfs_core for dataset and snapshot lifecycle operations, saving a
+// fork and exec for each. libzfs_core has no calls for listing, reading or setting properties,
+// or for anything that needs mounts managed, so those, and any case it can't express, fall back
+// to the CLI.
+#[derive(Debug, Clone, Default)]
+pub struct LzcBackend {
+    cli: Controller,
+}
+
+impl LzcBackend {
+    // the timeout only applies to commands run for the CLI fallback
+    pub fn with_timeout(timeout: Option<std::time::Duration>) -> Self {
+        Self {
+            cli: Controller::with_timeout(timeout),
+        }
+    }
+}
+
+impl ZfsBackend for LzcBackend {
+    fn list(
+        &self,
+        root: &str,
+        depth: Option<u32>,
+        types: &[&str],
+        properties: &[String],
+    ) -> Result<ZFSList> {
+        self.cli.list(root, depth, types, properties)
+    }
+
+    fn destroy(
+        &self,
+        pool: &str,
+        name: &str,
+        recursive: bool,
+        clones: bool,
+        force: bool,
+    ) -> Result<()> {
+        // lzc_destroy only takes the one dataset; zfs works out everything below it
+        if recursive || clones {
+            return self.cli.destroy(pool, name, recursive, clones, force);
+        }
+
+        init()?;
+        let full = format!("{}/{}", pool, name);
+
+        match unsafe { ffi::lzc_destroy(cstring(&full)?.as_ptr()) } {
+            // mounted; zfs destroy unmounts it first
+            EBUSY => self.cli.destroy(pool, name, false, false, force),
+            ret => check("destroying", &full, ret),
+        }
+    }
+
+    fn destroy_dry_run(
+        &self,
+        pool: &str,
+        name: &str,
+        recursive: bool,
+        clones: bool,
+    ) -> Result<String> {
+        self.cli.destroy_dry_run(pool, name, recursive, clones)
+    }
+
+    fn list_snapshots(&self) -> Result<ZFSSnapshotList> {
+        self.cli.list_snapshots()
+    }
+
+    fn snapshot(&self, pool: &str, dataset: &str, name: &str, recursive: bool) -> Result<()> {
+        // finding the descendants would need a listing, which costs more than zfs snapshot -r
+        if recursive {
+            return self.cli.snapshot(pool, dataset, name, recursive);
+        }
+
+        init()?;
+        let full = format!("{}/{}@{}", pool, dataset, name);
+        let mut snaps = NvList::new()?;
+        snaps.add_boolean(&full)?;
+
+        let mut errlist = ptr::null_mut();
+        let ret = unsafe { ffi::lzc_snapshot(snaps.0, ptr::null_mut(), &mut errlist) };
+        drop(NvList(errlist));
+        check("creating snapshot", &full, ret)
+    }
+
+    fn destroy_snapshot(
+        &self,
+        pool: &str,
+        dataset: &str,
+        name: &str,
+        recursive: bool,
+    ) -> Result<()> {
+        if recursive {
+            return self.cli.destroy_snapshot(pool, dataset, name, recursive);
+        }
+
+        init()?;
+        let full = format!("{}/{}@{}", pool, dataset, name);
+        let mut snaps = NvList::new()?;
+        snaps.add_boolean(&full)?;
+
+        let mut errlist = ptr::null_mut();
+        let ret = unsafe { ffi::lzc_destroy_snaps(snaps.0, 0, &mut errlist) };
+        drop(NvList(errlist));
+        check("destroying snapshot", &full, ret)
+    }
+
+    fn hold(
+        &self,
+        pool: &str,
+        dataset: &str,
+        name: &str,
+        tag: &str,
+        recursive: bool,
+    ) -> Result<()> {
+        self.cli.hold(pool, dataset, name, tag, recursive)
+    }
+
+    fn release(
+        &self,
+        pool: &str,
+        dataset: &str,
+        name: &str,
+        tag: &str,
+        recursive: bool,
+    ) -> Result<()> {
+        self.cli.release(pool, dataset, name, tag, recursive)
+    }
+
+    fn holds(&self, pool: &str, dataset: &str, name: &str, recursive: bool) -> Result<String> {
+        self.cli.holds(pool, dataset, name, recursive)
+    }
+
+    fn bookmark(&self, pool: &str, dataset: &str, snapshot: &str, name: &str) -> Result<()> {
+        self.cli.bookmark(pool, dataset, snapshot, name)
+    }
+
+    fn user_space(&self, pool: &str, name: &str, kind: QuotaKind) -> Result<String> {
+        self.cli.user_space(pool, name, kind)
+    }
+
+    fn destroy_bookmark(&self, pool: &str, dataset: &str, name: &str) -> Result<()> {
+        self.cli.destroy_bookmark(pool, dataset, name)
+    }
+
+    fn clone_snapshot(&self, pool: &str, dataset: &str, name: &str, target: &str) -> Result<()> {
+        init()?;
+        let origin = format!("{}/{}@{}", pool, dataset, name);
+        let full = format!("{}/{}", pool, target);
+
+        match unsafe {
+            ffi::lzc_clone(
+                cstring(&full)?.as_ptr(),
+                cstring(&origin)?.as_ptr(),
+                ptr::null_mut(),
+            )
+        } {
+            // missing parents, which zfs clone -p creates
+            ENOENT => self.cli.clone_snapshot(pool, dataset, name, target),
+            ret => {
+                // Pool mounts it along with everything else, as it does for zfs clone
+                check("cloning", &origin, ret)
+            }
+        }
+    }
+
+    fn promote(&self, pool: &str, name: &str) -> Result<()> {
+        init()?;
+        let full = format!("{}/{}", pool, name);
+        // names the conflicting snapshot on EEXIST
+        let mut conflict = [0 as c_char; 256];
+
+        let ret = unsafe {
+            ffi::lzc_promote(
+                cstring(&full)?.as_ptr(),
+                conflict.as_mut_ptr(),
+                conflict.len() as c_int,
+            )
+        };
+
+        if ret == EEXIST {
+            let conflict = unsafe { std::ffi::CStr::from_ptr(conflict.as_ptr()) };
+            return Err(anyhow!(
+                "promoting '{}': snapshot '{}' already exists",
+                full,
+                conflict.to_string_lossy()
+            ));
+        }
+
+        check("promoting", &full, ret)
+    }
+
+    fn send(&self, pool: &str, dataset: &str, name: &str, from: Option<&str>) -> Result<ZFSPipe> {
+        self.cli.send(pool, dataset, name, from)
+    }
+
+    fn receive(&self, pool: &str, name: &str, force: bool) -> Result<ZFSPipe> {
+        self.cli.receive(pool, name, force)
+    }
+
+    fn diff(&self, pool: &str, dataset: &str, from: &str, to: Option<&str>) -> Result<ZFSPipe> {
+        self.cli.diff(pool, dataset, from, to)
+    }
+
+    fn rollback(&self, pool: &str, dataset: &str, name: &str, destroy_newer: bool) -> Result<()> {
+        if destroy_newer {
+            return self.cli.rollback(pool, dataset, name, destroy_newer);
+        }
+
+        init()?;
+        let full = format!("{}/{}", pool, dataset);
+        let snapshot = format!("{}@{}", full, name);
+
+        match unsafe {
+            ffi::lzc_rollback_to(cstring(&full)?.as_ptr(), cstring(&snapshot)?.as_ptr())
+        } {
+            EEXIST => Err(anyhow!(
+                "rolling back to '{}': more recent snapshots or bookmarks exist",
+                snapshot
+            )),
+            ret => check("rolling back to", &snapshot, ret),
+        }
+    }
+
+    fn create_dataset(
+        &self,
+        pool: &str,
+        name: &str,
+        options: Option<CommandOptions>,
+        key: Option<&[u8]>,
+    ) -> Result<()> {
+        // keys are passphrases or hex that zfs turns into wrapping keys; leave that to it
+        let props = match properties(&options)? {
+            Some(props) if key.is_none() => props,
+            _ => return self.cli.create_dataset(pool, name, options, key),
+        };
+
+        init()?;
+        let full = format!("{}/{}", pool, name);
+        check("creating", &full, unsafe {
+            ffi::lzc_create(
+                cstring(&full)?.as_ptr(),
+                ffi::LZC_DATSET_TYPE_ZFS,
+                props.0,
+                ptr::null_mut(),
+                0,
+            )
+        })?;
+
+        // left unmounted; Pool mounts it along with everything else, as it does for zfs create
+        debug!("Created {} through libzfs_core", full);
+        Ok(())
+    }
+
+    fn load_key(&self, pool: &str, name: &str, key: Option<&[u8]>) -> Result<()> {
+        self.cli.load_key(pool, name, key)
+    }
+
+    fn unload_key(&self, pool: &str, name: &str) -> Result<()> {
+        init()?;
+        let full = format!("{}/{}", pool, name);
+        check("unloading key for", &full, unsafe {
+            ffi::lzc_unload_key(cstring(&full)?.as_ptr())
+        })
+    }
+
+    fn change_key(
+        &self,
+        pool: &str,
+        name: &str,
+        options: CommandOptions,
+        key: Option<&[u8]>,
+    ) -> Result<()> {
+        self.cli.change_key(pool, name, options, key)
+    }
+
+    // renaming mounted filesystems means unmounting and remounting their children
+    fn rename(&self, pool: &str, orig: &str, new: &str) -> Result<()> {
+        self.cli.rename(pool, orig, new)
+    }
+
+    fn set(&self, pool: &str, name: &str, properties: HashMap<&str, String>) -> Result<()> {
+        self.cli.set(pool, name, properties)
+    }
+
+    fn get<T>(&self, pool: &str, name: &str, property: &str) -> Result<T>
+    where
+        T: for<'de> serde::Deserialize<'de> + FromStr + Send + Sync + Clone,
+        T::Err: ToString,
+    {
+        self.cli.get(pool, name, property)
+    }
+
+    fn get_all(
+        &self,
+        pool: &str,
+        name: &str,
+        properties: &[String],
+    ) -> Result<ZFSGet<serde_json::Value>> {
+        self.cli.get_all(pool, name, properties)
+    }
+
+    fn inherit(&self, pool: &str, name: &str, property: &str, recursive: bool) -> Result<()> {
+        self.cli.inherit(pool, name, property, recursive)
+    }
+
+    fn mount(&self, pool: &str) -> Result<()> {
+        self.cli.mount(pool)
+    }
+
+    fn unmount(&self, pool: &str, name: &str) -> Result<()> {
+        self.cli.unmount(pool, name)
+    }
+
+    // zfs create computes the refreservation for volumes, and udev needs to see the device
+    fn create_volume(
+        &self,
+        pool: &str,
+        name: &str,
+        size: u64,
+        sparse: bool,
+        options: Option<CommandOptions>,
+        key: Option<&[u8]>,
+    ) -> Result<()> {
+        self.cli
+            .create_volume(pool, name, size, sparse, options, key)
+    }
+
+    fn wait_for_device(&self, path: &str) -> Result<()> {
+        self.cli.wait_for_device(path)
+    }
+
+    fn pool_status(&self, pool: &str) -> Result<ZPoolStatus> {
+        self.cli.pool_status(pool)
+    }
+
+    fn pool_list(&self, pool: &str) -> Result<ZPoolList> {
+        self.cli.pool_list(pool)
+    }
+
+    fn scrub(&self, pool: &str, flag: Option<&str>) -> Result<()> {
+        self.cli.scrub(pool, flag)
+    }
+
+    fn trim(&self, pool: &str, flag: Option<&str>) -> Result<()> {
+        self.cli.trim(pool, flag)
+    }
+
+    fn importable(&self, dirs: &[String]) -> Result<String> {
+        self.cli.importable(dirs)
+    }
+
+    fn import(
+        &self,
+        name: &str,
+        new_name: Option<&str>,
+        dirs: &[String],
+        force: bool,
+    ) -> Result<()> {
+        self.cli.import(name, new_name, dirs, force)
+    }
+
+    fn export(&self, name: &str, force: bool) -> Result<()> {
+        self.cli.export(name, force)
+    }
+
+    fn add_vdev(
+        &self,
+        pool: &str,
+        vdev: Vec<String>,
+        devices: &[String],
+        force: bool,
+    ) -> Result<()> {
+        self.cli.add_vdev(pool, vdev, devices, force)
+    }
+
+    fn attach(&self, pool: &str, device: &str, new_device: &str, force: bool) -> Result<()> {
+        self.cli.attach(pool, device, new_device, force)
+    }
+
+    fn detach(&self, pool: &str, device: &str) -> Result<()> {
+        self.cli.detach(pool, device)
+    }
+
+    fn replace(
+        &self,
+        pool: &str,
+        device: &str,
+        new_device: Option<&str>,
+        force: bool,
+    ) -> Result<()> {
+        self.cli.replace(pool, device, new_device, force)
+    }
+
+    fn online(&self, pool: &str, device: &str, expand: bool) -> Result<()> {
+        self.cli.online(pool, device, expand)
+    }
+
+    fn offline(&self, pool: &str, device: &str, temporary: bool) -> Result<()> {
+        self.cli.offline(pool, device, temporary)
+    }
+
+    fn clear(&self, pool: &str, device: Option<&str>) -> Result<()> {
+        self.cli.clear(pool, device)
+    }
+}
@@ -0,0 +1,1669 @@
+use super::{
+    CommandOptions, QuotaKind, ZFSGet, ZFSGetItem, ZFSList, ZFSListItem, ZFSNumber, ZFSOutputInfo,
+    ZFSPipe, ZFSSnapshotList, ZFSSnapshotListItem, ZFSSnapshotListItemProperties, ZFSSource,
+    ZFSValue, ZPoolList, ZPoolListItem, ZPoolListItemProperties, ZPoolScanStats, ZPoolStatus,
+    ZPoolStatusItem, ZPoolVdev, ZfsBackend, ZVOL_DEVICE_ROOT,
+};
+use anyhow::{anyhow, Result};
+use std::{
+    collections::{BTreeMap, HashMap},
+    str::FromStr,
+    sync::{Arc, Mutex, MutexGuard},
+    time::SystemTime,
+};
+
+// how much space the pretend pool has to hand out
+const POOL_SIZE: u64 = 10 * 1024 * 1024 * 1024;
+// roughly what zfs reports for an empty filesystem
+const EMPTY_USED: u64 = 96 * 1024;
+
+// computed by zfs or fixed at creation; these can't be set or inherited
+const READ_ONLY: &[&str] = &[
+    "type",
+    "name",
+    "used",
+    "available",
+    "referenced",
+    "creation",
+    "createtxg",
+    "origin",
+    "keystatus",
+    "encryptionroot",
+    "encryption",
+    "volblocksize",
+];
+
+// these only apply to the dataset they're set on
+const NOT_INHERITED: &[&str] = &[
+    "volsize",
+    "quota",
+    "refquota",
+    "reservation",
+    "refreservation",
+    "keyformat",
+    "keylocation",
+];
+
+#[derive(Debug, Clone)]
+struct Entry {
+    volume: bool,
+    sparse: bool,
+    createtxg: u64,
+    creation: u64,
+    origin: Option<String>,
+    // locally set properties; numbers are kept as numbers like zfs get --json-int reports them
+    properties: BTreeMap<String, serde_json::Value>,
+    // userquota@alice and friends; zfs doesn't list these with the other properties
+    quotas: BTreeMap<String, u64>,
+    // only set on encryption roots
+    encryption: Option<Encryption>,
+}
+
+#[derive(Debug, Clone)]
+struct Encryption {
+    key: Option<Vec<u8>>,
+    loaded: bool,
+}
+
+#[derive(Debug, Clone)]
+struct SnapshotEntry {
+    createtxg: u64,
+    creation: u64,
+    referenced: u64,
+    // tag to when it was placed
+    holds: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Clone)]
+struct BookmarkEntry {
+    createtxg: u64,
+    creation: u64,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    datasets: BTreeMap<String, Entry>,
+    snapshots: BTreeMap<String, SnapshotEntry>,
+    bookmarks: BTreeMap<String, BookmarkEntry>,
+    scan: Option<ZPoolScanStats>,
+    txg: u64,
+    clock: u64,
+}
+
+// An in-memory stand-in for the zfs and zpool commands, so Pool and the gRPC services can be
+// exercised without root or real pools. It tracks the dataset hierarchy, properties, snapshots,
+// clones and encryption keys closely enough for callers to see the same successes and failures
+// zfs would give them; there is no data, so streams and vdevs are not supported.
+#[derive(Debug, Clone)]
+pub struct MockBackend {
+    state: Arc<Mutex<State>>,
+}
+
+impl MockBackend {
+    pub fn new(pool: &str) -> Self {
+        let mut state = State::default();
+        let root = state.new_entry(false);
+        state.datasets.insert(pool.to_string(), root);
+
+        Self {
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    // stands in for a scan the pool ran by itself, like a resilver, or one still going; started
+    // this long ago
+    pub fn set_scan(&self, function: &str, state: &str, age: std::time::Duration) {
+        let start = now() - age.as_secs();
+        let end = if state == "SCANNING" { 0 } else { start };
+
+        self.state().scan = Some(ZPoolScanStats {
+            function: function.to_string(),
+            state: state.to_string(),
+            start_time: ZFSNumber::Int(start),
+            end_time: ZFSNumber::Int(end),
+            to_examine: ZFSNumber::default(),
+            examined: ZFSNumber::default(),
+            issued: ZFSNumber::default(),
+            errors: ZFSNumber::default(),
+            scrub_pause: None,
+        });
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        // a panicking test shouldn't take every other user of the backend down with it
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn unsupported(operation: &str) -> anyhow::Error {
+    anyhow!("{} is not supported by the in-memory backend", operation)
+}
+
+fn output_version(command: &str) -> ZFSOutputInfo {
+    ZFSOutputInfo {
+        command: command.to_string(),
+        vers_major: 0,
+        vers_minor: 1,
+    }
+}
+
+fn value<T>(value: T, typ: &str, data: &str) -> ZFSValue<T> {
+    ZFSValue {
+        value,
+        source: ZFSSource {
+            typ: typ.to_string(),
+            data: data.to_string(),
+        },
+    }
+}
+
+// zfs hands numbers back as numbers with --json-int, so store them that way
+fn parse_value(value: &str) -> serde_json::Value {
+    match value.parse::<u64>() {
+        Ok(x) => x.into(),
+        Err(_) => value.into(),
+    }
+}
+
+fn parent(name: &str) -> Option<&str> {
+    name.rsplit_once('/').map(|(parent, _)| parent)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+impl State {
+    fn new_entry(&mut self, volume: bool) -> Entry {
+        self.txg += 1;
+
+        Entry {
+            volume,
+            sparse: false,
+            createtxg: self.txg,
+            creation: now(),
+            origin: None,
+            properties: BTreeMap::new(),
+            quotas: BTreeMap::new(),
+            encryption: None,
+        }
+    }
+
+    // snapshots taken in the same second would otherwise be unordered
+    fn tick(&mut self) -> u64 {
+        self.clock = now().max(self.clock + 1);
+        self.clock
+    }
+
+    fn entry(&self, name: &str) -> Result<&Entry> {
+        self.datasets
+            .get(name)
+            .ok_or_else(|| anyhow!("cannot open '{}': dataset does not exist", name))
+    }
+
+    fn entry_mut(&mut self, name: &str) -> Result<&mut Entry> {
+        self.datasets
+            .get_mut(name)
+            .ok_or_else(|| anyhow!("cannot open '{}': dataset does not exist", name))
+    }
+
+    fn descendants(&self, name: &str) -> Vec<String> {
+        let prefix = format!("{}/", name);
+        self.datasets
+            .keys()
+            .filter(|x| x.starts_with(&prefix))
+            .cloned()
+            .collect()
+    }
+
+    fn snapshots_of(&self, name: &str) -> Vec<String> {
+        let prefix = format!("{}@", name);
+        self.snapshots
+            .keys()
+            .filter(|x| x.starts_with(&prefix))
+            .cloned()
+            .collect()
+    }
+
+    fn clones_of(&self, snapshot: &str) -> Vec<String> {
+        self.datasets
+            .iter()
+            .filter(|(_, entry)| entry.origin.as_deref() == Some(snapshot))
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    // What zfs destroy would take with it, as datasets and snapshots: -r adds descendants and
+    // snapshots, -R also clones of those snapshots and everything below them.
+    fn destroy_set(
+        &self,
+        name: &str,
+        recursive: bool,
+        clones: bool,
+    ) -> Result<(Vec<String>, Vec<String>)> {
+        self.entry(name)?;
+
+        let mut datasets: Vec<String> = Vec::new();
+        let mut snapshots = Vec::new();
+        let mut pending = vec![name.to_string()];
+
+        while let Some(current) = pending.pop() {
+            if datasets.contains(&current) {
+                continue;
+            }
+
+            let children = self.descendants(&current);
+            let own = self.snapshots_of(&current);
+
+            if !recursive && !clones && (!children.is_empty() || !own.is_empty()) {
+                return Err(anyhow!(
+                    "cannot destroy '{}': filesystem has children",
+                    current
+                ));
+            }
+
+            for snapshot in own {
+                let dependents = self.clones_of(&snapshot);
+                if !dependents.is_empty() && !clones {
+                    return Err(anyhow!(
+                        "cannot destroy '{}': filesystem has dependent clones",
+                        current
+                    ));
+                }
+
+                if !self.snapshots[&snapshot].holds.is_empty() {
+                    return Err(anyhow!(
+                        "cannot destroy snapshot {}: dataset is busy",
+                        snapshot
+                    ));
+                }
+
+                pending.extend(dependents);
+                snapshots.push(snapshot);
+            }
+
+            pending.extend(children);
+            datasets.push(current);
+        }
+
+        Ok((datasets, snapshots))
+    }
+
+    fn check_parent(&self, name: &str) -> Result<()> {
+        match parent(name) {
+            Some(parent) if self.datasets.contains_key(parent) => Ok(()),
+            _ => Err(anyhow!("cannot create '{}': parent does not exist", name)),
+        }
+    }
+
+    fn encryption_root(&self, name: &str) -> Option<String> {
+        let mut current = Some(name);
+
+        while let Some(name) = current {
+            if self.datasets.get(name)?.encryption.is_some() {
+                return Some(name.to_string());
+            }
+            current = parent(name);
+        }
+
+        None
+    }
+
+    fn used(&self, name: &str) -> u64 {
+        let Some(entry) = self.datasets.get(name) else {
+            return 0;
+        };
+
+        if entry.volume {
+            return if entry.sparse {
+                EMPTY_USED
+            } else {
+                self.local(name, "volsize")
+                    .and_then(|x| x.as_u64())
+                    .unwrap_or_default()
+            };
+        }
+
+        let prefix = format!("{}/", name);
+        EMPTY_USED
+            + self
+                .datasets
+                .keys()
+                .filter(|x| x.starts_with(&prefix) && !x[prefix.len()..].contains('/'))
+                .map(|x| self.used(x))
+                .sum::<u64>()
+    }
+
+    fn available(&self) -> u64 {
+        let pool = self
+            .datasets
+            .keys()
+            .find(|x| !x.contains('/'))
+            .cloned()
+            .unwrap_or_default();
+        POOL_SIZE.saturating_sub(self.used(&pool))
+    }
+
+    fn local(&self, name: &str, property: &str) -> Option<&serde_json::Value> {
+        self.datasets.get(name)?.properties.get(property)
+    }
+
+    // the value of a property and where it came from, as zfs get reports it
+    fn lookup(&self, name: &str, property: &str) -> Result<(serde_json::Value, String, String)> {
+        let entry = self.entry(name)?;
+        let none = |value: serde_json::Value| Ok((value, "NONE".to_string(), "-".to_string()));
+
+        match property {
+            "name" => return none(name.into()),
+            "type" => return none(if entry.volume { "volume" } else { "filesystem" }.into()),
+            "used" => return none(self.used(name).into()),
+            "available" => return none(self.available().into()),
+            "referenced" => {
+                return none(
+                    if entry.volume {
+                        EMPTY_USED
+                    } else {
+                        self.used(name)
+                    }
+                    .into(),
+                );
+            }
+            "creation" => return none(entry.creation.into()),
+            // nothing is written, so nothing compresses and snapshots hold nothing
+            "compressratio" => return none("1.00x".into()),
+            "usedbysnapshots" => return none(0.into()),
+            "usedbydataset" => return none(EMPTY_USED.into()),
+            // a volume's refreservation covers all of it that isn't written yet
+            "usedbychildren" if entry.volume => return none(0.into()),
+            "usedbychildren" => return none((self.used(name) - EMPTY_USED).into()),
+            "usedbyrefreservation" if entry.volume => {
+                return none(self.used(name).saturating_sub(EMPTY_USED).into());
+            }
+            "usedbyrefreservation" => return none(0.into()),
+            "logicalused" => return none(self.used(name).into()),
+            "createtxg" => return none(entry.createtxg.into()),
+            "origin" => return none(entry.origin.clone().unwrap_or("-".to_string()).into()),
+            "keystatus" => {
+                return none(
+                    match self.encryption_root(name) {
+                        Some(root) => match &self.datasets[&root].encryption {
+                            Some(Encryption { loaded: true, .. }) => "available",
+                            _ => "unavailable",
+                        },
+                        None => "-",
+                    }
+                    .into(),
+                );
+            }
+            "encryptionroot" => {
+                return none(self.encryption_root(name).unwrap_or("-".to_string()).into());
+            }
+            "mountpoint" | "recordsize" | "atime" | "canmount" | "sharenfs" | "sharesmb"
+                if entry.volume =>
+            {
+                return none("-".into());
+            }
+            "volblocksize" | "volmode" if !entry.volume => return none("-".into()),
+            _ => {}
+        }
+
+        if let Some(value) = entry.properties.get(property) {
+            return Ok((value.clone(), "LOCAL".to_string(), "-".to_string()));
+        }
+
+        if !NOT_INHERITED.contains(&property) {
+            let mut current = parent(name);
+            while let Some(ancestor) = current {
+                if let Some(value) = self.local(ancestor, property) {
+                    let value = match (property, value.as_str()) {
+                        // mountpoints carry the path below the dataset they're set on
+                        ("mountpoint", Some(path)) if path.starts_with('/') => {
+                            format!("{}{}", path, &name[ancestor.len()..]).into()
+                        }
+                        _ => value.clone(),
+                    };
+
+                    return Ok((value, "INHERITED".to_string(), ancestor.to_string()));
+                }
+                current = parent(ancestor);
+            }
+        }
+
+        let default: serde_json::Value = match property {
+            "mountpoint" => format!("/{}", name).into(),
+            "encryption" => "off".into(),
+            "compression" | "atime" | "canmount" => "on".into(),
+            "sharenfs" | "sharesmb" => "off".into(),
+            "quota" | "refquota" | "reservation" | "refreservation" => 0.into(),
+            "recordsize" => (128 * 1024).into(),
+            "volblocksize" => (16 * 1024).into(),
+            "volmode" => "default".into(),
+            // unset user properties
+            _ => return none("-".into()),
+        };
+
+        Ok((default, "DEFAULT".to_string(), "-".to_string()))
+    }
+
+    fn all_properties(&self, name: &str) -> Vec<String> {
+        let mut properties: Vec<String> = [
+            "type",
+            "name",
+            "used",
+            "available",
+            "referenced",
+            "creation",
+            "createtxg",
+            "origin",
+            "keystatus",
+            "encryptionroot",
+            "encryption",
+            "compression",
+            "quota",
+            "refquota",
+            "reservation",
+            "refreservation",
+        ]
+        .iter()
+        .map(|x| x.to_string())
+        .collect();
+
+        if self.datasets.get(name).is_some_and(|x| x.volume) {
+            properties.push("volsize".to_string());
+        } else {
+            properties.append(&mut vec![
+                "mountpoint".to_string(),
+                "recordsize".to_string(),
+                "atime".to_string(),
+                "canmount".to_string(),
+                "sharenfs".to_string(),
+                "sharesmb".to_string(),
+            ]);
+        }
+
+        // anything set locally or inherited, including user properties
+        let mut current = Some(name);
+        while let Some(name) = current {
+            if let Some(entry) = self.datasets.get(name) {
+                for property in entry.properties.keys() {
+                    if !properties.contains(property) {
+                        properties.push(property.clone());
+                    }
+                }
+            }
+            current = parent(name);
+        }
+
+        properties
+    }
+
+    fn create(
+        &mut self,
+        name: &str,
+        mut entry: Entry,
+        options: Option<CommandOptions>,
+        key: Option<&[u8]>,
+    ) -> Result<()> {
+        if self.datasets.contains_key(name) {
+            return Err(anyhow!("cannot create '{}': dataset already exists", name));
+        }
+
+        self.check_parent(name)?;
+
+        if let Some(options) = options {
+            for (k, v) in options.iter() {
+                if k == "encryption" {
+                    if v != "off" {
+                        entry.encryption = Some(Encryption {
+                            key: key.map(|x| x.to_vec()),
+                            loaded: true,
+                        });
+                    }
+                } else if READ_ONLY.contains(&k.as_str()) && k != "volblocksize" {
+                    return Err(anyhow!("cannot create '{}': '{}' is readonly", name, k));
+                }
+
+                entry.properties.insert(k.clone(), parse_value(v));
+            }
+        }
+
+        self.datasets.insert(name.to_string(), entry);
+        Ok(())
+    }
+
+    fn rename_prefix(&mut self, orig: &str, new: &str) {
+        let renamed = |name: &str| -> Option<String> {
+            if name == orig {
+                Some(new.to_string())
+            } else {
+                ["/", "@", "#"].iter().find_map(|sep| {
+                    name.strip_prefix(&format!("{}{}", orig, sep))
+                        .map(|rest| format!("{}{}{}", new, sep, rest))
+                })
+            }
+        };
+
+        self.datasets = std::mem::take(&mut self.datasets)
+            .into_iter()
+            .map(|(name, mut entry)| {
+                entry.origin = entry.origin.map(|x| renamed(&x).unwrap_or(x));
+                (renamed(&name).unwrap_or(name), entry)
+            })
+            .collect();
+
+        self.snapshots = std::mem::take(&mut self.snapshots)
+            .into_iter()
+            .map(|(name, entry)| (renamed(&name).unwrap_or(name), entry))
+            .collect();
+
+        self.bookmarks = std::mem::take(&mut self.bookmarks)
+            .into_iter()
+            .map(|(name, entry)| (renamed(&name).unwrap_or(name), entry))
+            .collect();
+    }
+}
+
+impl ZfsBackend for MockBackend {
+    fn list(
+        &self,
+        root: &str,
+        depth: Option<u32>,
+        types: &[&str],
+        properties: &[String],
+    ) -> Result<ZFSList> {
+        let state = self.state();
+        let mut datasets = HashMap::new();
+
+        // how far below root a name is; a snapshot is one level below its dataset
+        let within = |name: &str| {
+            let Some(rest) = name.strip_prefix(root) else {
+                return false;
+            };
+
+            let levels = if rest.is_empty() {
+                0
+            } else if let Some(rest) = rest.strip_prefix('/') {
+                rest.split('/').count()
+            } else if rest.starts_with(['@', '#']) {
+                0
+            } else {
+                return false;
+            };
+
+            let levels = levels + usize::from(name.contains(['@', '#']));
+            depth.is_none_or(|depth| levels <= depth as usize)
+        };
+
+        let mut insert = |name: &String, typ: &str, createtxg: u64, values| {
+            datasets.insert(
+                name.clone(),
+                ZFSListItem {
+                    name: name.clone(),
+                    typ: typ.to_string(),
+                    pool: name.split('/').next().unwrap_or_default().to_string(),
+                    createtxg,
+                    properties: values,
+                },
+            );
+        };
+
+        for (name, entry) in &state.datasets {
+            let typ = if entry.volume { "volume" } else { "filesystem" };
+            if !types.contains(&typ) || !within(name) {
+                continue;
+            }
+
+            let mut values = HashMap::new();
+            for property in properties {
+                let (value, typ, data) = state.lookup(name, property)?;
+                values.insert(
+                    property.clone(),
+                    ZFSValue {
+                        value,
+                        source: ZFSSource { typ, data },
+                    },
+                );
+            }
+
+            insert(name, &typ.to_uppercase(), entry.createtxg, values);
+        }
+
+        if types.contains(&"bookmark") {
+            for (name, bookmark) in &state.bookmarks {
+                if !within(name) {
+                    continue;
+                }
+
+                let mut values = HashMap::new();
+                for property in properties {
+                    let data: serde_json::Value = match property.as_str() {
+                        "creation" => bookmark.creation.into(),
+                        "createtxg" => bookmark.createtxg.into(),
+                        _ => "-".into(),
+                    };
+                    values.insert(property.clone(), value(data, "NONE", "-"));
+                }
+
+                insert(name, "BOOKMARK", bookmark.createtxg, values);
+            }
+        }
+
+        if types.contains(&"snapshot") {
+            for (name, snapshot) in &state.snapshots {
+                if !within(name) {
+                    continue;
+                }
+
+                let mut values = HashMap::new();
+                for property in properties {
+                    let data: serde_json::Value = match property.as_str() {
+                        "used" => 0.into(),
+                        "referenced" => snapshot.referenced.into(),
+                        "creation" => snapshot.creation.into(),
+                        "createtxg" => snapshot.createtxg.into(),
+                        _ => "-".into(),
+                    };
+                    values.insert(property.clone(), value(data, "NONE", "-"));
+                }
+
+                insert(name, "SNAPSHOT", snapshot.createtxg, values);
+            }
+        }
+
+        Ok(ZFSList {
+            output_version: output_version("zfs list"),
+            datasets,
+        })
+    }
+
+    // nothing is ever mounted here, let alone busy
+    fn destroy(
+        &self,
+        pool: &str,
+        name: &str,
+        recursive: bool,
+        clones: bool,
+        _force: bool,
+    ) -> Result<()> {
+        let full = format!("{}/{}", pool, name);
+        let mut state = self.state();
+        let (datasets, snapshots) = state.destroy_set(&full, recursive, clones)?;
+
+        for snapshot in snapshots {
+            state.snapshots.remove(&snapshot);
+        }
+
+        for dataset in datasets {
+            // bookmarks go with the dataset
+            let prefix = format!("{}#", dataset);
+            state.bookmarks.retain(|x, _| !x.starts_with(&prefix));
+            state.datasets.remove(&dataset);
+        }
+
+        Ok(())
+    }
+
+    fn destroy_dry_run(
+        &self,
+        pool: &str,
+        name: &str,
+        recursive: bool,
+        clones: bool,
+    ) -> Result<String> {
+        let full = format!("{}/{}", pool, name);
+        let state = self.state();
+        let (datasets, snapshots) = state.destroy_set(&full, recursive, clones)?;
+
+        // snapshots hold nothing here, and a dataset's used already counts what's below it
+        let reclaim: u64 = datasets
+            .iter()
+            .filter(|x| !parent(x).is_some_and(|p| datasets.iter().any(|d| d == p)))
+            .map(|x| state.used(x))
+            .sum();
+
+        let mut out = String::new();
+        for name in snapshots.iter().chain(datasets.iter().rev()) {
+            out.push_str(&format!("destroy\t{}\n", name));
+        }
+        out.push_str(&format!("reclaim\t{}\n", reclaim));
+
+        Ok(out)
+    }
+
+    fn list_snapshots(&self) -> Result<ZFSSnapshotList> {
+        let state = self.state();
+        let mut datasets = HashMap::new();
+
+        for (name, snapshot) in &state.snapshots {
+            datasets.insert(
+                name.clone(),
+                ZFSSnapshotListItem {
+                    name: name.clone(),
+                    typ: "SNAPSHOT".to_string(),
+                    pool: name.split('/').next().unwrap_or_default().to_string(),
+                    createtxg: snapshot.createtxg,
+                    properties: ZFSSnapshotListItemProperties {
+                        used: value(0, "NONE", "-"),
+                        referenced: value(snapshot.referenced, "NONE", "-"),
+                        creation: value(snapshot.creation, "NONE", "-"),
+                    },
+                },
+            );
+        }
+
+        Ok(ZFSSnapshotList {
+            output_version: output_version("zfs list"),
+            datasets,
+        })
+    }
+
+    fn snapshot(&self, pool: &str, dataset: &str, name: &str, recursive: bool) -> Result<()> {
+        let full = format!("{}/{}", pool, dataset);
+        let mut state = self.state();
+        state.entry(&full)?;
+
+        let mut datasets = vec![full];
+        if recursive {
+            datasets.append(&mut state.descendants(&datasets[0]));
+        }
+
+        for dataset in &datasets {
+            let snapshot = format!("{}@{}", dataset, name);
+            if state.snapshots.contains_key(&snapshot) {
+                return Err(anyhow!(
+                    "cannot create snapshot '{}': dataset already exists",
+                    snapshot
+                ));
+            }
+        }
+
+        // recursive snapshots are atomic, so they share a creation time
+        let creation = state.tick();
+        for dataset in datasets {
+            state.txg += 1;
+            let snapshot = SnapshotEntry {
+                createtxg: state.txg,
+                creation,
+                referenced: state
+                    .lookup(&dataset, "referenced")?
+                    .0
+                    .as_u64()
+                    .unwrap_or_default(),
+                holds: BTreeMap::new(),
+            };
+            state
+                .snapshots
+                .insert(format!("{}@{}", dataset, name), snapshot);
+        }
+
+        Ok(())
+    }
+
+    fn destroy_snapshot(
+        &self,
+        pool: &str,
+        dataset: &str,
+        name: &str,
+        recursive: bool,
+    ) -> Result<()> {
+        let full = format!("{}/{}", pool, dataset);
+        let mut state = self.state();
+
+        let mut snapshots = vec![format!("{}@{}", full, name)];
+        if recursive {
+            for dataset in state.descendants(&full) {
+                snapshots.push(format!("{}@{}", dataset, name));
+            }
+        }
+        snapshots.retain(|x| state.snapshots.contains_key(x));
+
+        if snapshots.is_empty() {
+            return Err(anyhow!(
+                "could not find any snapshots to destroy; check snapshot names."
+            ));
+        }
+
+        for snapshot in &snapshots {
+            if !state.clones_of(snapshot).is_empty() {
+                return Err(anyhow!(
+                    "cannot destroy '{}': snapshot has dependent clones",
+                    snapshot
+                ));
+            }
+
+            if !state.snapshots[snapshot].holds.is_empty() {
+                return Err(anyhow!(
+                    "cannot destroy snapshot {}: dataset is busy",
+                    snapshot
+                ));
+            }
+        }
+
+        for snapshot in snapshots {
+            state.snapshots.remove(&snapshot);
+        }
+
+        Ok(())
+    }
+
+    fn hold(
+        &self,
+        pool: &str,
+        dataset: &str,
+        name: &str,
+        tag: &str,
+        recursive: bool,
+    ) -> Result<()> {
+        let full = format!("{}/{}", pool, dataset);
+        let snapshot = format!("{}@{}", full, name);
+        let mut state = self.state();
+
+        if !state.snapshots.contains_key(&snapshot) {
+            return Err(anyhow!(
+                "cannot hold snapshot '{}': dataset does not exist",
+                snapshot
+            ));
+        }
+
+        let mut snapshots = vec![snapshot];
+        if recursive {
+            for dataset in state.descendants(&full) {
+                snapshots.push(format!("{}@{}", dataset, name));
+            }
+        }
+        snapshots.retain(|x| state.snapshots.contains_key(x));
+
+        if let Some(held) = snapshots
+            .iter()
+            .find(|x| state.snapshots[*x].holds.contains_key(tag))
+        {
+            return Err(anyhow!(
+                "cannot hold snapshot '{}': tag already exists on this dataset",
+                held
+            ));
+        }
+
+        let now = now();
+        for snapshot in snapshots {
+            if let Some(entry) = state.snapshots.get_mut(&snapshot) {
+                entry.holds.insert(tag.to_string(), now);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn release(
+        &self,
+        pool: &str,
+        dataset: &str,
+        name: &str,
+        tag: &str,
+        recursive: bool,
+    ) -> Result<()> {
+        let full = format!("{}/{}", pool, dataset);
+        let mut state = self.state();
+
+        let mut snapshots = vec![format!("{}@{}", full, name)];
+        if recursive {
+            for dataset in state.descendants(&full) {
+                snapshots.push(format!("{}@{}", dataset, name));
+            }
+        }
+
+        for snapshot in &snapshots {
+            match state.snapshots.get(snapshot) {
+                Some(entry) if !entry.holds.contains_key(tag) => {
+                    return Err(anyhow!(
+                        "cannot release hold from snapshot '{}': no such tag on this dataset",
+                        snapshot
+                    ));
+                }
+                None if snapshot == &snapshots[0] => {
+                    return Err(anyhow!(
+                        "cannot release hold from snapshot '{}': dataset does not exist",
+                        snapshot
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        for snapshot in snapshots {
+            if let Some(entry) = state.snapshots.get_mut(&snapshot) {
+                entry.holds.remove(tag);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn holds(&self, pool: &str, dataset: &str, name: &str, recursive: bool) -> Result<String> {
+        let full = format!("{}/{}", pool, dataset);
+        let snapshot = format!("{}@{}", full, name);
+        let state = self.state();
+
+        if !state.snapshots.contains_key(&snapshot) {
+            return Err(anyhow!(
+                "cannot open '{}': dataset does not exist",
+                snapshot
+            ));
+        }
+
+        let mut snapshots = vec![snapshot];
+        if recursive {
+            for dataset in state.descendants(&full) {
+                snapshots.push(format!("{}@{}", dataset, name));
+            }
+        }
+
+        let mut out = String::new();
+        for snapshot in snapshots {
+            if let Some(entry) = state.snapshots.get(&snapshot) {
+                for (tag, placed) in &entry.holds {
+                    out.push_str(&format!("{}\t{}\t{}\n", snapshot, tag, placed));
+                }
+            }
+        }
+
+        Ok(out)
+    }
+
+    fn bookmark(&self, pool: &str, dataset: &str, snapshot: &str, name: &str) -> Result<()> {
+        let snapshot = format!("{}/{}@{}", pool, dataset, snapshot);
+        let bookmark = format!("{}/{}#{}", pool, dataset, name);
+        let mut state = self.state();
+
+        let Some(entry) = state.snapshots.get(&snapshot) else {
+            return Err(anyhow!(
+                "cannot create bookmark '{}': snapshot does not exist",
+                bookmark
+            ));
+        };
+
+        if state.bookmarks.contains_key(&bookmark) {
+            return Err(anyhow!(
+                "cannot create bookmark '{}': bookmark exists",
+                bookmark
+            ));
+        }
+
+        let entry = BookmarkEntry {
+            createtxg: entry.createtxg,
+            creation: entry.creation,
+        };
+        state.bookmarks.insert(bookmark, entry);
+        Ok(())
+    }
+
+    fn user_space(&self, pool: &str, name: &str, kind: QuotaKind) -> Result<String> {
+        let full = format!("{}/{}", pool, name);
+        let state = self.state();
+        let entry = state.entry(&full)?;
+        let prefix = format!("{}@", kind.property());
+
+        // nothing is ever written, so only ids with a quota show up
+        let mut out = String::new();
+        for (property, quota) in &entry.quotas {
+            if let Some(id) = property.strip_prefix(&prefix) {
+                out.push_str(&format!("{}\t0\t{}\t0\tnone\n", id, quota));
+            }
+        }
+
+        Ok(out)
+    }
+
+    fn destroy_bookmark(&self, pool: &str, dataset: &str, name: &str) -> Result<()> {
+        let bookmark = format!("{}/{}#{}", pool, dataset, name);
+
+        if self.state().bookmarks.remove(&bookmark).is_none() {
+            return Err(anyhow!(
+                "cannot destroy '{}': bookmark does not exist",
+                bookmark
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn clone_snapshot(&self, pool: &str, dataset: &str, name: &str, target: &str) -> Result<()> {
+        let snapshot = format!("{}/{}@{}", pool, dataset, name);
+        let target = format!("{}/{}", pool, target);
+        let mut state = self.state();
+
+        if !state.snapshots.contains_key(&snapshot) {
+            return Err(anyhow!(
+                "cannot open '{}': dataset does not exist",
+                snapshot
+            ));
+        }
+
+        if state.datasets.contains_key(&target) {
+            return Err(anyhow!(
+                "cannot create '{}': dataset already exists",
+                target
+            ));
+        }
+
+        // clone -p creates any missing parents
+        let mut missing = Vec::new();
+        let mut current = parent(&target);
+        while let Some(name) = current {
+            if state.datasets.contains_key(name) {
+                break;
+            }
+            missing.push(name.to_string());
+            current = parent(name);
+        }
+
+        for name in missing.into_iter().rev() {
+            let entry = state.new_entry(false);
+            state.datasets.insert(name, entry);
+        }
+
+        let source = state.entry(&format!("{}/{}", pool, dataset))?.clone();
+        let mut entry = state.new_entry(source.volume);
+        entry.sparse = source.sparse;
+        entry.origin = Some(snapshot);
+        if let Some(volsize) = source.properties.get("volsize") {
+            entry
+                .properties
+                .insert("volsize".to_string(), volsize.clone());
+        }
+
+        state.datasets.insert(target, entry);
+        Ok(())
+    }
+
+    fn promote(&self, pool: &str, name: &str) -> Result<()> {
+        let full = format!("{}/{}", pool, name);
+        let mut state = self.state();
+
+        let Some(origin) = state.entry(&full)?.origin.clone() else {
+            return Err(anyhow!(
+                "cannot promote '{}': not a cloned filesystem",
+                full
+            ));
+        };
+
+        let Some((dataset, _)) = origin.split_once('@') else {
+            return Err(anyhow!("invalid origin '{}'", origin));
+        };
+        let dataset = dataset.to_string();
+        let cutoff = state.snapshots[&origin].createtxg;
+
+        // the origin and every snapshot before it move to the clone
+        let moving: Vec<String> = state
+            .snapshots_of(&dataset)
+            .into_iter()
+            .filter(|x| state.snapshots[x].createtxg <= cutoff)
+            .collect();
+
+        for snapshot in moving {
+            let moved = snapshot.replacen(&dataset, &full, 1);
+            let entry = state.snapshots.remove(&snapshot).unwrap();
+            state.snapshots.insert(moved.clone(), entry);
+
+            for entry in state.datasets.values_mut() {
+                if entry.origin.as_deref() == Some(snapshot.as_str()) {
+                    entry.origin = Some(moved.clone());
+                }
+            }
+        }
+
+        // and the two swap places in the dependency chain
+        let previous = state.entry(&dataset)?.origin.clone();
+        state.entry_mut(&dataset)?.origin = Some(origin.replacen(&dataset, &full, 1));
+        state.entry_mut(&full)?.origin = previous;
+        Ok(())
+    }
+
+    fn send(
+        &self,
+        _pool: &str,
+        _dataset: &str,
+        _name: &str,
+        _from: Option<&str>,
+    ) -> Result<ZFSPipe> {
+        Err(unsupported("send"))
+    }
+
+    fn receive(&self, _pool: &str, _name: &str, _force: bool) -> Result<ZFSPipe> {
+        Err(unsupported("receive"))
+    }
+
+    fn diff(&self, _pool: &str, _dataset: &str, _from: &str, _to: Option<&str>) -> Result<ZFSPipe> {
+        Err(unsupported("diff"))
+    }
+
+    fn rollback(&self, pool: &str, dataset: &str, name: &str, destroy_newer: bool) -> Result<()> {
+        let full = format!("{}/{}", pool, dataset);
+        let snapshot = format!("{}@{}", full, name);
+        let mut state = self.state();
+
+        let Some(target) = state.snapshots.get(&snapshot) else {
+            return Err(anyhow!(
+                "cannot open '{}': dataset does not exist",
+                snapshot
+            ));
+        };
+        let cutoff = target.createtxg;
+
+        let newer: Vec<String> = state
+            .snapshots_of(&full)
+            .into_iter()
+            .filter(|x| state.snapshots[x].createtxg > cutoff)
+            .collect();
+        let bookmarks = format!("{}#", full);
+        let newer_bookmarks: Vec<String> = state
+            .bookmarks
+            .iter()
+            .filter(|(x, bookmark)| x.starts_with(&bookmarks) && bookmark.createtxg > cutoff)
+            .map(|(x, _)| x.clone())
+            .collect();
+
+        if (!newer.is_empty() || !newer_bookmarks.is_empty()) && !destroy_newer {
+            return Err(anyhow!(
+                "cannot rollback to '{}': more recent snapshots or bookmarks exist",
+                snapshot
+            ));
+        }
+
+        if let Some(clone) = newer.iter().flat_map(|x| state.clones_of(x)).next() {
+            return Err(anyhow!(
+                "cannot rollback to '{}': clones of previous snapshots exist ({})",
+                snapshot,
+                clone
+            ));
+        }
+
+        if let Some(held) = newer.iter().find(|x| !state.snapshots[*x].holds.is_empty()) {
+            return Err(anyhow!("cannot destroy snapshot {}: dataset is busy", held));
+        }
+
+        for snapshot in newer {
+            state.snapshots.remove(&snapshot);
+        }
+
+        for bookmark in newer_bookmarks {
+            state.bookmarks.remove(&bookmark);
+        }
+
+        Ok(())
+    }
+
+    fn create_dataset(
+        &self,
+        pool: &str,
+        name: &str,
+        options: Option<CommandOptions>,
+        key: Option<&[u8]>,
+    ) -> Result<()> {
+        let mut state = self.state();
+        let entry = state.new_entry(false);
+        state.create(&format!("{}/{}", pool, name), entry, options, key)
+    }
+
+    fn load_key(&self, pool: &str, name: &str, key: Option<&[u8]>) -> Result<()> {
+        let full = format!("{}/{}", pool, name);
+        let mut state = self.state();
+
+        let Some(encryption) = &mut state.entry_mut(&full)?.encryption else {
+            return Err(anyhow!(
+                "Keys must be loaded for encryption root of '{}'",
+                full
+            ));
+        };
+
+        if encryption.loaded {
+            return Err(anyhow!("Key already loaded for '{}'", full));
+        }
+
+        // without a key zfs reads keylocation, which we take on faith
+        match (&encryption.key, key) {
+            (Some(expected), Some(key)) if expected.as_slice() != key => {
+                return Err(anyhow!("Incorrect key provided for '{}'", full));
+            }
+            _ => {}
+        }
+
+        encryption.loaded = true;
+        Ok(())
+    }
+
+    fn unload_key(&self, pool: &str, name: &str) -> Result<()> {
+        let full = format!("{}/{}", pool, name);
+        let mut state = self.state();
+
+        let Some(encryption) = &mut state.entry_mut(&full)?.encryption else {
+            return Err(anyhow!(
+                "Keys must be unloaded for encryption root of '{}'",
+                full
+            ));
+        };
+
+        if !encryption.loaded {
+            return Err(anyhow!("Key already unloaded for '{}'", full));
+        }
+
+        encryption.loaded = false;
+        Ok(())
+    }
+
+    fn change_key(
+        &self,
+        pool: &str,
+        name: &str,
+        options: CommandOptions,
+        key: Option<&[u8]>,
+    ) -> Result<()> {
+        let full = format!("{}/{}", pool, name);
+        let mut state = self.state();
+        let entry = state.entry_mut(&full)?;
+
+        let Some(encryption) = &mut entry.encryption else {
+            return Err(anyhow!(
+                "cannot change key for '{}': not an encryption root",
+                full
+            ));
+        };
+
+        if !encryption.loaded {
+            return Err(anyhow!("Key must be loaded for '{}'", full));
+        }
+
+        if let Some(key) = key {
+            encryption.key = Some(key.to_vec());
+        }
+
+        for (k, v) in options.iter() {
+            entry.properties.insert(k.clone(), parse_value(v));
+        }
+
+        Ok(())
+    }
+
+    fn rename(&self, pool: &str, orig: &str, new: &str) -> Result<()> {
+        let orig = format!("{}/{}", pool, orig);
+        let new = format!("{}/{}", pool, new);
+        let mut state = self.state();
+        state.entry(&orig)?;
+
+        if state.datasets.contains_key(&new) {
+            return Err(anyhow!(
+                "cannot rename to '{}': dataset already exists",
+                new
+            ));
+        }
+
+        if new.starts_with(&format!("{}/", orig)) {
+            return Err(anyhow!(
+                "cannot rename to '{}': New dataset name cannot be a descendant of current dataset name",
+                new
+            ));
+        }
+
+        state.check_parent(&new)?;
+        state.rename_prefix(&orig, &new);
+        Ok(())
+    }
+
+    fn set(&self, pool: &str, name: &str, properties: HashMap<&str, String>) -> Result<()> {
+        let full = format!("{}/{}", pool, name);
+        let mut state = self.state();
+        let entry = state.entry_mut(&full)?;
+
+        for property in properties.keys() {
+            if READ_ONLY.contains(property) {
+                return Err(anyhow!(
+                    "cannot set property for '{}': '{}' is readonly",
+                    full,
+                    property
+                ));
+            }
+
+            if *property == "volsize" && !entry.volume {
+                return Err(anyhow!(
+                    "cannot set property for '{}': 'volsize' does not apply to datasets of this type",
+                    full
+                ));
+            }
+        }
+
+        for (property, value) in properties {
+            if [QuotaKind::User, QuotaKind::Group, QuotaKind::Project]
+                .iter()
+                .any(|x| property.starts_with(&format!("{}@", x.property())))
+            {
+                match value.parse::<u64>() {
+                    Ok(x) => entry.quotas.insert(property.to_string(), x),
+                    Err(_) if value == "none" => entry.quotas.remove(property),
+                    Err(_) => {
+                        return Err(anyhow!(
+                            "cannot set property for '{}': bad numeric value '{}'",
+                            full,
+                            value
+                        ));
+                    }
+                };
+                continue;
+            }
+
+            entry
+                .properties
+                .insert(property.to_string(), parse_value(&value));
+        }
+
+        Ok(())
+    }
+
+    fn get<T>(&self, pool: &str, name: &str, property: &str) -> Result<T>
+    where
+        T: for<'de> serde::Deserialize<'de> + FromStr + Send + Sync + Clone,
+        T::Err: ToString,
+    {
+        let (value, _, _) = self
+            .state()
+            .lookup(&format!("{}/{}", pool, name), property)?;
+
+        match serde_json::from_value(value.clone()) {
+            Ok(x) => Ok(x),
+            Err(e) => match value.as_str() {
+                Some(s) => T::from_str(s).map_err(|e| anyhow!(e.to_string())),
+                None => Err(e.into()),
+            },
+        }
+    }
+
+    fn get_all(
+        &self,
+        pool: &str,
+        name: &str,
+        properties: &[String],
+    ) -> Result<ZFSGet<serde_json::Value>> {
+        let full = format!("{}/{}", pool, name);
+        let state = self.state();
+        let entry = state.entry(&full)?;
+
+        let properties = if properties.is_empty() {
+            state.all_properties(&full)
+        } else {
+            properties.to_vec()
+        };
+
+        let mut values = HashMap::new();
+        for property in properties {
+            let (value, typ, data) = state.lookup(&full, &property)?;
+            values.insert(
+                property,
+                ZFSValue {
+                    value,
+                    source: ZFSSource { typ, data },
+                },
+            );
+        }
+
+        let mut datasets = HashMap::new();
+        datasets.insert(
+            full.clone(),
+            ZFSGetItem {
+                name: full.clone(),
+                typ: if entry.volume { "VOLUME" } else { "FILESYSTEM" }.to_string(),
+                pool: pool.to_string(),
+                createtxg: entry.createtxg,
+                properties: values,
+            },
+        );
+
+        Ok(ZFSGet {
+            output_version: output_version("zfs get"),
+            datasets,
+        })
+    }
+
+    fn inherit(&self, pool: &str, name: &str, property: &str, recursive: bool) -> Result<()> {
+        let full = format!("{}/{}", pool, name);
+        let mut state = self.state();
+        state.entry(&full)?;
+
+        if READ_ONLY.contains(&property) || NOT_INHERITED.contains(&property) {
+            return Err(anyhow!("'{}' property cannot be inherited", property));
+        }
+
+        let mut datasets = vec![full];
+        if recursive {
+            datasets.append(&mut state.descendants(&datasets[0]));
+        }
+
+        for dataset in datasets {
+            state.entry_mut(&dataset)?.properties.remove(property);
+        }
+
+        Ok(())
+    }
+
+    // nothing is actually mounted; mountpoints are still reported like zfs would
+    fn mount(&self, pool: &str) -> Result<()> {
+        self.state().entry(pool)?;
+        Ok(())
+    }
+
+    fn unmount(&self, pool: &str, name: &str) -> Result<()> {
+        self.state().entry(&format!("{}/{}", pool, name))?;
+        Ok(())
+    }
+
+    fn create_volume(
+        &self,
+        pool: &str,
+        name: &str,
+        size: u64,
+        sparse: bool,
+        options: Option<CommandOptions>,
+        key: Option<&[u8]>,
+    ) -> Result<()> {
+        let mut state = self.state();
+        let mut entry = state.new_entry(true);
+        entry.sparse = sparse;
+        entry.properties.insert("volsize".to_string(), size.into());
+
+        if !sparse && size > state.available() {
+            return Err(anyhow!("cannot create '{}/{}': out of space", pool, name));
+        }
+
+        state.create(&format!("{}/{}", pool, name), entry, options, key)
+    }
+
+    fn wait_for_device(&self, path: &str) -> Result<()> {
+        let name = path
+            .strip_prefix(ZVOL_DEVICE_ROOT)
+            .and_then(|x| x.strip_prefix('/'))
+            .ok_or_else(|| anyhow!("{} is not a zvol", path))?;
+
+        // like udev, which never sees a device for these
+        match self.state().lookup(name, "volmode")?.0.as_str() {
+            Some("none") => Err(anyhow!("timed out waiting for {}", path)),
+            _ => Ok(()),
+        }
+    }
+
+    fn pool_status(&self, pool: &str) -> Result<ZPoolStatus> {
+        let state = self.state();
+        state.entry(pool)?;
+
+        let mut vdevs = BTreeMap::new();
+        vdevs.insert(
+            pool.to_string(),
+            ZPoolVdev {
+                name: pool.to_string(),
+                vdev_type: "root".to_string(),
+                state: "ONLINE".to_string(),
+                path: None,
+                read_errors: ZFSNumber::default(),
+                write_errors: ZFSNumber::default(),
+                checksum_errors: ZFSNumber::default(),
+                vdevs: BTreeMap::new(),
+                trim_state: None,
+                trim_bytes_done: ZFSNumber::default(),
+                trim_bytes_est: ZFSNumber::default(),
+            },
+        );
+
+        let mut pools = HashMap::new();
+        pools.insert(
+            pool.to_string(),
+            ZPoolStatusItem {
+                name: pool.to_string(),
+                state: "ONLINE".to_string(),
+                status: None,
+                action: None,
+                scan_stats: state.scan.clone(),
+                vdevs,
+                logs: BTreeMap::new(),
+                l2cache: BTreeMap::new(),
+                spares: BTreeMap::new(),
+                error_count: ZFSNumber::default(),
+            },
+        );
+
+        Ok(ZPoolStatus {
+            output_version: output_version("zpool status"),
+            pools,
+        })
+    }
+
+    fn pool_list(&self, pool: &str) -> Result<ZPoolList> {
+        let state = self.state();
+        state.entry(pool)?;
+        let allocated = state.used(pool);
+
+        let mut pools = HashMap::new();
+        pools.insert(
+            pool.to_string(),
+            ZPoolListItem {
+                name: pool.to_string(),
+                properties: ZPoolListItemProperties {
+                    size: value(ZFSNumber::Int(POOL_SIZE), "NONE", "-"),
+                    allocated: value(ZFSNumber::Int(allocated), "NONE", "-"),
+                    free: value(
+                        ZFSNumber::Int(POOL_SIZE.saturating_sub(allocated)),
+                        "NONE",
+                        "-",
+                    ),
+                    capacity: value(ZFSNumber::Int(allocated * 100 / POOL_SIZE), "NONE", "-"),
+                    fragmentation: value(ZFSNumber::Int(0), "NONE", "-"),
+                    dedupratio: value(ZFSNumber::Float(1.0), "NONE", "-"),
+                    health: value("ONLINE".to_string(), "NONE", "-"),
+                },
+            },
+        );
+
+        Ok(ZPoolList {
+            output_version: output_version("zpool list"),
+            pools,
+        })
+    }
+
+    // there's no data to read, so scrubs finish the moment they start
+    fn scrub(&self, pool: &str, flag: Option<&str>) -> Result<()> {
+        let mut state = self.state();
+        state.entry(pool)?;
+
+        if flag.is_some() {
+            return Err(anyhow!(
+                "cannot cancel scrubbing {}: there is no active scrub",
+                pool
+            ));
+        }
+
+        let now = ZFSNumber::Int(now());
+        state.scan = Some(ZPoolScanStats {
+            function: "SCRUB".to_string(),
+            state: "FINISHED".to_string(),
+            start_time: now.clone(),
+            end_time: now,
+            to_examine: ZFSNumber::default(),
+            examined: ZFSNumber::default(),
+            issued: ZFSNumber::default(),
+            errors: ZFSNumber::default(),
+            scrub_pause: None,
+        });
+
+        Ok(())
+    }
+
+    fn trim(&self, pool: &str, flag: Option<&str>) -> Result<()> {
+        self.state().entry(pool)?;
+
+        if flag.is_some() {
+            return Err(anyhow!(
+                "cannot cancel trim {}: there is no active trim",
+                pool
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn importable(&self, _dirs: &[String]) -> Result<String> {
+        Ok(String::new())
+    }
+
+    fn import(
+        &self,
+        _name: &str,
+        _new_name: Option<&str>,
+        _dirs: &[String],
+        _force: bool,
+    ) -> Result<()> {
+        Err(unsupported("import"))
+    }
+
+    fn export(&self, _name: &str, _force: bool) -> Result<()> {
+        Err(unsupported("export"))
+    }
+
+    fn add_vdev(
+        &self,
+        _pool: &str,
+        _vdev: Vec<String>,
+        _devices: &[String],
+        _force: bool,
+    ) -> Result<()> {
+        Err(unsupported("adding vdevs"))
+    }
+
+    fn attach(&self, _pool: &str, _device: &str, _new_device: &str, _force: bool) -> Result<()> {
+        Err(unsupported("attach"))
+    }
+
+    fn detach(&self, _pool: &str, _device: &str) -> Result<()> {
+        Err(unsupported("detach"))
+    }
+
+    fn replace(
+        &self,
+        _pool: &str,
+        _device: &str,
+        _new_device: Option<&str>,
+        _force: bool,
+    ) -> Result<()> {
+        Err(unsupported("replace"))
+    }
+
+    fn online(&self, _pool: &str, _device: &str, _expand: bool) -> Result<()> {
+        Err(unsupported("online"))
+    }
+
+    fn offline(&self, _pool: &str, _device: &str, _temporary: bool) -> Result<()> {
+        Err(unsupported("offline"))
+    }
+
+    fn clear(&self, pool: &str, _device: Option<&str>) -> Result<()> {
+        self.state().entry(pool)?;
+        Ok(())
+    }
+}